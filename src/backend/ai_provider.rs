@@ -19,6 +19,16 @@ pub struct AiResult {
     pub message: Option<String>,
     pub content: Option<String>,
     pub code: Option<String>,
+    /// What the safety scan found in the provider output (if it ran).
+    #[serde(default)]
+    pub safety: Option<crate::backend::safety::SafetyReport>,
+}
+
+/// One turn of a conversation sent to a provider (`system`, `user` or `assistant`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
 }
 
 // In-memory master secret cache with TTL (best-effort, process-local)
@@ -115,17 +125,19 @@ pub async fn list_prompt_templates(user_id: i64) -> Result<Vec<(String,String)>,
 #[tauri::command]
 pub async fn generate_journal_entry(user_id: i64, provider: String, master_label: String, prompt_template_name: String, timeout_secs: Option<u64>, model: Option<String>, _store_in_keyring: bool) -> Result<AiResult, String> {
     // consent check + fetch template before any await that touches DB internals
-    let user_check = tokio::task::spawn_blocking(move || -> Result<(), String> {
+    let user_check = tokio::task::spawn_blocking(move || -> Result<bool, String> {
         let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
         let mut stmt = conn.prepare("SELECT ai_opt_in, ai_provider FROM user WHERE id = ?").map_err(|e| e.to_string())?;
         let mut rows = stmt.query(params![user_id]).map_err(|e| e.to_string())?;
         if let Some(row) = rows.next().map_err(|e| e.to_string())? {
             let ai_flag: bool = row.get::<_, Option<bool>>(0).unwrap_or(Some(false)).unwrap_or(false);
-            if !ai_flag { return Err("User has not consented to AI operations".to_string()); }
-            Ok(())
+            Ok(ai_flag)
         } else { Err("User not found".to_string()) }
     }).await.map_err(|e| e.to_string())?;
-    user_check?;
+    // Without AI consent the journal is still written, locally
+    if !user_check? {
+        return local_journal_fallback(user_id, "User has not consented to AI operations".to_string()).await;
+    }
     let template = get_prompt_template(user_id, prompt_template_name.clone()).await?;
     let template = template.ok_or("Template not found".to_string())?;
 
@@ -144,36 +156,37 @@ pub async fn generate_journal_entry(user_id: i64, provider: String, master_label
     // Fill template (simple replacement of {{prompt}} and {{user_id}})
     let filled = template.replace("{{user_id}}", &user_id.to_string()).replace("{{prompt}}", "Please summarize my day and generate a short lockscreen note.");
 
-    // Call provider
-    let res = match provider.to_lowercase().as_str() {
-        "chatgpt" | "openai" => call_chatgpt(&key, &filled, timeout_secs.unwrap_or(30), model.clone()).await,
-        "gemini" | "google" => call_gemini(&key, &filled, timeout_secs.unwrap_or(30), model.clone()).await,
-        other => Err(format!("Unknown provider: {}", other)),
-    };
+    // Call provider (through the outbound privacy filter)
+    let res = send_to_provider(user_id, &provider, &key, &filled, timeout_secs.unwrap_or(30), model.clone()).await;
 
     match res {
     Ok(content) => {
             // Safety checks and redaction
-            match crate::backend::safety::policy_check(&content) {
-                Ok(()) => {
-                    // redact PII before saving/display
-                    let redacted = crate::backend::safety::redact_pii(&content);
-                    // Persist journal entry off the async reactor
-                    let provider_clone = provider.clone();
-                    let model_clone = model.clone();
-                    let redacted_clone = redacted.clone();
-                    let save_res = tokio::task::spawn_blocking(move || {
-                        crate::backend::journals::save_journal_entry(user_id, provider_clone, model_clone, redacted_clone, None)
-                    }).await.map_err(|e| e.to_string())?;
-                    match save_res {
-                        Ok(id) => Ok(AiResult { success: true, message: Some(format!("saved: {}", id)), content: Some(content), code: None }),
-                        Err(e) => Ok(AiResult { success: true, message: Some(format!("save_failed: {}", e)), content: Some(content), code: Some("save_error".to_string()) }),
-                    }
+            let report = scan_output(user_id, &content).await?;
+            let leak = crate::backend::prompt_guard::check_output_leakage(&content, &crate::backend::prompt_assembler::SYSTEM_INSTRUCTIONS);
+            if leak.leaked {
+                return Ok(AiResult { success: false, message: Some("response repeated the system instructions".to_string()), content: None, code: Some("instruction_leak".to_string()), safety: Some(report) });
+            }
+            if report.blocked {
+                return Ok(AiResult { success: false, message: Some(format!("policy_violation: {}", report.block_reasons.join(", "))), content: None, code: Some("policy_violation".to_string()), safety: Some(report) });
+            }
+            // Persist the redacted journal entry off the async reactor
+            let provider_clone = provider.clone();
+            let model_clone = model.clone();
+            let redacted_clone = report.redacted.clone();
+            let save_res = tokio::task::spawn_blocking(move || {
+                crate::backend::journals::save_journal_entry(user_id, provider_clone, model_clone, redacted_clone, None)
+            }).await.map_err(|e| e.to_string())?;
+            match save_res {
+                Ok(saved) => {
+                    let (message, code) = saved.status();
+                    Ok(AiResult { success: true, message: Some(message), content: Some(content), code, safety: Some(report) })
                 }
-                Err(policy_err) => Ok(AiResult { success: false, message: Some(policy_err), content: None, code: Some("policy_violation".to_string()) }),
+                Err(e) => Ok(AiResult { success: true, message: Some(format!("save_failed: {}", e)), content: Some(content), code: Some("save_error".to_string()), safety: Some(report) }),
             }
         }
-        Err(e) => Ok(AiResult { success: false, message: Some(e), content: None, code: Some("provider_error".to_string()) }),
+        // Provider unreachable or failing: fall back to the offline journal
+        Err(e) => local_journal_fallback(user_id, e).await,
     }
 }
 
@@ -204,14 +217,14 @@ pub async fn set_provider_api_key(user_id: i64, provider: String, api_key: Strin
     if let Err(e) = kr.set_password(&api_key) {
         eprintln!("keyring set error: {:?}", e);
     }
-    Ok(AiResult { success: true, message: None, content: None, code: None })
+    Ok(AiResult { success: true, message: None, content: None, code: None, safety: None })
 }
 
 #[tauri::command]
 pub async fn get_provider_api_key(user_id: i64, provider: String, master: String) -> Result<AiResult, String> {
     match tokio::task::spawn_blocking(move || fetch_provider_api_key(user_id, provider.clone(), master)).await.map_err(|e| e.to_string())? {
-        Ok(opt) => Ok(AiResult { success: true, message: None, content: opt, code: None }),
-        Err(e) => Ok(AiResult { success: false, message: Some(e), content: None, code: Some("key_error".to_string()) }),
+        Ok(opt) => Ok(AiResult { success: true, message: None, content: opt, code: None, safety: None }),
+        Err(e) => Ok(AiResult { success: false, message: Some(e), content: None, code: Some("key_error".to_string()), safety: None }),
     }
 }
 
@@ -245,19 +258,84 @@ pub async fn delete_provider_api_key(user_id: i64, provider: String) -> Result<A
     }).await.map_err(|e| e.to_string())?;
     let kr = Entry::new(&format!("focusd_provider_{}", provider), &format!("user_{}", user_id));
     let _ = kr.delete_password(); // best-effort
-    Ok(AiResult { success: true, message: None, content: None, code: None })
+    Ok(AiResult { success: true, message: None, content: None, code: None, safety: None })
+}
+
+/// Single entry point for outbound provider calls. The prompt is masked with the user's privacy
+/// filter before it leaves the machine and placeholders in the response are re-hydrated locally.
+pub async fn send_to_provider(user_id: i64, provider: &str, api_key: &str, prompt: &str, timeout_secs: u64, model: Option<String>) -> Result<String, String> {
+    let mut filter = tokio::task::spawn_blocking(move || crate::backend::privacy::outbound_filter_for_user(user_id)).await.map_err(|e| e.to_string())??;
+    let masked = filter.mask(prompt);
+    let res = match provider.to_lowercase().as_str() {
+        "chatgpt" | "openai" => call_chatgpt(api_key, &masked, timeout_secs, model).await,
+        "gemini" | "google" => call_gemini(api_key, &masked, timeout_secs, model).await,
+        other => Err(format!("Unknown provider: {}", other)),
+    };
+    res.map(|content| filter.rehydrate(&content))
+}
+
+/// Multi-turn variant of `send_to_provider`: every message is masked with the same filter so a
+/// value keeps one placeholder across the whole conversation.
+pub async fn send_messages_to_provider(user_id: i64, provider: &str, api_key: &str, messages: &[ChatMessage], timeout_secs: u64, model: Option<String>) -> Result<String, String> {
+    let mut filter = tokio::task::spawn_blocking(move || crate::backend::privacy::outbound_filter_for_user(user_id)).await.map_err(|e| e.to_string())??;
+    let masked: Vec<ChatMessage> = messages.iter().map(|m| ChatMessage { role: m.role.clone(), content: filter.mask(&m.content) }).collect();
+    let res = match provider.to_lowercase().as_str() {
+        "chatgpt" | "openai" => call_chatgpt_messages(api_key, &masked, timeout_secs, model).await,
+        "gemini" | "google" => call_gemini_messages(api_key, &masked, timeout_secs, model).await,
+        other => Err(format!("Unknown provider: {}", other)),
+    };
+    res.map(|content| filter.rehydrate(&content))
+}
+
+/// Check `user.ai_opt_in` before any AI work is done on the user's behalf.
+pub fn check_ai_consent(conn: &Connection, user_id: i64) -> Result<(), String> {
+    let mut stmt = conn.prepare("SELECT ai_opt_in FROM user WHERE id = ?").map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params![user_id]).map_err(|e| e.to_string())?;
+    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let ai_flag: bool = row.get::<_, Option<bool>>(0).unwrap_or(Some(false)).unwrap_or(false);
+        if !ai_flag { return Err("User has not consented to AI operations".to_string()); }
+        Ok(())
+    } else { Err("User not found".to_string()) }
+}
+
+/// Write the offline, rule-based journal instead of an AI one. `reason` explains why.
+async fn local_journal_fallback(user_id: i64, reason: String) -> Result<AiResult, String> {
+    let (saved, content) = tokio::task::spawn_blocking(move || crate::backend::local_journal::save_local_journal(user_id, None)).await.map_err(|e| e.to_string())??;
+    Ok(AiResult { success: true, message: Some(format!("{} ({})", saved.status().0, reason)), content: Some(content), code: Some("local_fallback".to_string()), safety: None })
+}
+
+/// Scan provider output with the user's detector configuration.
+pub(crate) async fn scan_output(user_id: i64, content: &str) -> Result<crate::backend::safety::SafetyReport, String> {
+    let config = tokio::task::spawn_blocking(move || crate::backend::safety::safety_config_for_user(user_id)).await.map_err(|e| e.to_string())??;
+    Ok(crate::backend::safety::scan(content, &config))
 }
 
 // Minimal ChatGPT client (uses OpenAI-compatible REST endpoint)
 pub async fn call_chatgpt(api_key: &str, prompt: &str, timeout_secs: u64, model: Option<String>) -> Result<String, String> {
-    let client = Client::builder().timeout(StdDuration::from_secs(timeout_secs)).build().map_err(|e| e.to_string())?;
-    let url = "https://api.openai.com/v1/chat/completions";
+    let messages = [ChatMessage { role: "user".to_string(), content: prompt.to_string() }];
+    call_chatgpt_messages(api_key, &messages, timeout_secs, model).await
+}
+
+/// Multi-message variant of `call_chatgpt` (system / user / assistant turns).
+pub async fn call_chatgpt_messages(api_key: &str, messages: &[ChatMessage], timeout_secs: u64, model: Option<String>) -> Result<String, String> {
+    let body = chatgpt_messages_body(model, messages);
+    let j = post_chatgpt(api_key, &body, timeout_secs).await?;
+    Ok(parse_chatgpt_response(&j))
+}
+
+pub fn chatgpt_messages_body(model: Option<String>, messages: &[ChatMessage]) -> serde_json::Value {
     let model = model.unwrap_or_else(|| "gpt-4o-mini".to_string());
-    let body = serde_json::json!({
+    serde_json::json!({
         "model": model,
-        "messages": [{"role": "user", "content": prompt}],
+        "messages": messages.iter().map(|m| serde_json::json!({"role": m.role, "content": m.content})).collect::<Vec<_>>(),
         "max_tokens": 800
-    });
+    })
+}
+
+/// POST a chat-completions body with retry/backoff and return the raw JSON response.
+pub(crate) async fn post_chatgpt(api_key: &str, body: &serde_json::Value, timeout_secs: u64) -> Result<serde_json::Value, String> {
+    let client = Client::builder().timeout(StdDuration::from_secs(timeout_secs)).build().map_err(|e| e.to_string())?;
+    let url = "https://api.openai.com/v1/chat/completions";
 
     // Simple retry with exponential backoff
     let mut attempt = 0u32;
@@ -267,15 +345,14 @@ pub async fn call_chatgpt(api_key: &str, prompt: &str, timeout_secs: u64, model:
         attempt += 1;
         let res = client.post(url)
             .bearer_auth(api_key)
-            .json(&body)
+            .json(body)
             .send().await;
         match res {
             Ok(r) => {
                 if !r.status().is_success() {
                     last_err = Some(format!("ChatGPT API error: {}", r.status()));
                 } else {
-                    let j: serde_json::Value = r.json().await.map_err(|e| e.to_string())?;
-                    return Ok(parse_chatgpt_response(&j));
+                    return r.json().await.map_err(|e| e.to_string());
                 }
             }
             Err(e) => {
@@ -291,15 +368,42 @@ pub async fn call_chatgpt(api_key: &str, prompt: &str, timeout_secs: u64, model:
 
 // Minimal Gemini client (Google's PaLM REST API compatibility)
 pub async fn call_gemini(api_key: &str, prompt: &str, timeout_secs: u64, model: Option<String>) -> Result<String, String> {
-    let client = Client::builder().timeout(StdDuration::from_secs(timeout_secs)).build().map_err(|e| e.to_string())?;
-    let model = model.unwrap_or_else(|| "gemini-2.0-flash".to_string());
     // Use the Generative Language API `generateContent` endpoint and the `contents -> parts -> text` body
-    let url = format!("https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent", model);
     let body = serde_json::json!({
         "contents": [
             { "parts": [ { "text": prompt } ] }
         ]
     });
+    let j = post_gemini(api_key, model, &body, timeout_secs).await?;
+    Ok(parse_gemini_response(&j))
+}
+
+/// Multi-message variant of `call_gemini`. System turns become `systemInstruction` and assistant
+/// turns use Gemini's `model` role.
+pub async fn call_gemini_messages(api_key: &str, messages: &[ChatMessage], timeout_secs: u64, model: Option<String>) -> Result<String, String> {
+    let body = gemini_messages_body(messages);
+    let j = post_gemini(api_key, model, &body, timeout_secs).await?;
+    Ok(parse_gemini_response(&j))
+}
+
+pub fn gemini_messages_body(messages: &[ChatMessage]) -> serde_json::Value {
+    let system: Vec<&str> = messages.iter().filter(|m| m.role == "system").map(|m| m.content.as_str()).collect();
+    let contents: Vec<serde_json::Value> = messages.iter().filter(|m| m.role != "system").map(|m| {
+        let role = if m.role == "assistant" { "model" } else { "user" };
+        serde_json::json!({ "role": role, "parts": [ { "text": m.content } ] })
+    }).collect();
+    let mut body = serde_json::json!({ "contents": contents });
+    if !system.is_empty() {
+        body["systemInstruction"] = serde_json::json!({ "parts": [ { "text": system.join("\n\n") } ] });
+    }
+    body
+}
+
+/// POST a `generateContent` body with retry/backoff and return the raw JSON response.
+pub(crate) async fn post_gemini(api_key: &str, model: Option<String>, body: &serde_json::Value, timeout_secs: u64) -> Result<serde_json::Value, String> {
+    let client = Client::builder().timeout(StdDuration::from_secs(timeout_secs)).build().map_err(|e| e.to_string())?;
+    let model = model.unwrap_or_else(|| "gemini-2.0-flash".to_string());
+    let url = format!("https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent", model);
 
     let mut attempt = 0u32;
     let max_attempts = 3;
@@ -313,12 +417,12 @@ pub async fn call_gemini(api_key: &str, prompt: &str, timeout_secs: u64, model:
             let url_with_key = format!("{}?key={}", url, api_key);
             client.post(&url_with_key)
                 .header("x-goog-api-key", api_key)
-                .json(&body)
+                .json(body)
                 .send().await
         } else {
             client.post(&url)
                 .bearer_auth(api_key)
-                .json(&body)
+                .json(body)
                 .send().await
         };
         match res {
@@ -329,8 +433,7 @@ pub async fn call_gemini(api_key: &str, prompt: &str, timeout_secs: u64, model:
                 if !status.is_success() {
                     last_err = Some(format!("Gemini API error: {}: {}", status, body_text));
                 } else {
-                    let j: serde_json::Value = serde_json::from_str(&body_text).map_err(|e| e.to_string())?;
-                    return Ok(parse_gemini_response(&j));
+                    return serde_json::from_str(&body_text).map_err(|e| e.to_string());
                 }
             }
             Err(e) => {
@@ -431,27 +534,27 @@ pub async fn generate_ai_via_provider(user_id: i64, provider: String, master: St
     let key_opt = fetch_provider_api_key(user_id, provider.clone(), master)?;
     let key = key_opt.ok_or("API key not found for provider")?;
     let to = timeout_secs.unwrap_or(30);
-    let res = match provider.to_lowercase().as_str() {
-        "chatgpt" | "openai" => call_chatgpt(&key, &prompt, to, model.clone()).await,
-        "gemini" | "google" => call_gemini(&key, &prompt, to, model.clone()).await,
-        other => Err(format!("Unknown provider: {}", other)),
-    };
+    let res = send_to_provider(user_id, &provider, &key, &prompt, to, model.clone()).await;
     match res {
     Ok(c) => {
-            // Run policy check and redact before returning/saving
-            if let Err(pol) = crate::backend::safety::policy_check(&c) {
-                return Ok(AiResult { success: false, message: Some(pol), content: None, code: Some("policy_violation".to_string()) });
+            // Run the safety scan and redact before returning/saving
+            let report = scan_output(user_id, &c).await?;
+            let leak = crate::backend::prompt_guard::check_output_leakage(&c, &crate::backend::prompt_assembler::SYSTEM_INSTRUCTIONS);
+            if leak.leaked {
+                return Ok(AiResult { success: false, message: Some("response repeated the system instructions".to_string()), content: None, code: Some("instruction_leak".to_string()), safety: Some(report) });
+            }
+            if report.blocked {
+                return Ok(AiResult { success: false, message: Some(format!("policy_violation: {}", report.block_reasons.join(", "))), content: None, code: Some("policy_violation".to_string()), safety: Some(report) });
             }
-            let redacted = crate::backend::safety::redact_pii(&c);
             // Save in background (best-effort)
             let provider_clone = provider.clone();
             let model_clone = model.clone();
-            let redacted_clone = redacted.clone();
+            let redacted_clone = report.redacted.clone();
             let _ = tokio::task::spawn_blocking(move || {
                 let _ = crate::backend::journals::save_journal_entry(user_id, provider_clone, model_clone, redacted_clone, None);
             }).await;
-            Ok(AiResult { success: true, message: None, content: Some(c), code: None })
+            Ok(AiResult { success: true, message: None, content: Some(c), code: None, safety: Some(report) })
         }
-        Err(e) => Ok(AiResult { success: false, message: Some(e), content: None, code: Some("provider_error".to_string()) }),
+        Err(e) => Ok(AiResult { success: false, message: Some(e), content: None, code: Some("provider_error".to_string()), safety: None }),
     }
 }
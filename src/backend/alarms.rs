@@ -1,6 +1,13 @@
 use serde::{Serialize, Deserialize};
-use rusqlite::Connection;
+use rusqlite::{Connection, params, OptionalExtension};
+use chrono::{Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
 use crate::backend::utility;
+use crate::backend::local_journal::parse_timestamp;
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+use crate::backend::recurrence;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Alarm {
@@ -49,6 +56,395 @@ pub fn get_alarms(workspace_dir: Option<String>) -> Result<Vec<Alarm>, String> {
         Alarm { time: "07:30".to_string(), label: "Morning wake-up alarm".to_string() },
     ])
 }
+// ---------- runtime: firing, snooze, dismissal, punctuality ----------
+
+pub const ALARM_FIRED_EVENT: &str = "alarm_fired";
+pub const STATUS_RINGING: &str = "ringing";
+pub const STATUS_SNOOZED: &str = "snoozed";
+pub const STATUS_DISMISSED: &str = "dismissed";
+pub const STATUS_MISSED: &str = "missed";
+/// How far back a tick looks for alarms it has not seen yet (e.g. after the app was closed).
+const LOOKBACK_HOURS: i64 = 24;
+const TICK_SECONDS: u64 = 15;
+const TS_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlarmPreferences {
+    pub snooze_minutes: i64,
+    pub max_snoozes: i64,
+    /// Dismissed within this many minutes of the scheduled time counts as on time.
+    pub on_time_grace_minutes: i64,
+    /// A ringing alarm nobody responds to for this long is recorded as missed.
+    pub missed_after_minutes: i64,
+    /// Dismissal needs the RFID of the registered core card.
+    pub require_core_tap: bool,
+}
+
+impl Default for AlarmPreferences {
+    fn default() -> Self {
+        AlarmPreferences { snooze_minutes: 9, max_snoozes: 3, on_time_grace_minutes: 5, missed_after_minutes: 60, require_core_tap: false }
+    }
+}
+
+/// Payload of the `alarm_fired` event; also what `list_active_alarms` returns.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActiveAlarm {
+    /// `daily:<date>:<alarm id>` or `recurrence:<id>:<occurrence start>`.
+    pub alarm_key: String,
+    pub user_id: i64,
+    pub label: String,
+    pub scheduled_time: String,
+    pub fire_time: String,
+    pub status: String,
+    pub snooze_count: i64,
+    pub require_core_tap: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PunctualityRecord {
+    pub id: i64,
+    pub user_id: i64,
+    pub alarm_key: String,
+    pub label: String,
+    pub scheduled_time: String,
+    /// Dismissal time; the scheduled time for missed alarms so they count on the right day.
+    pub actual_time: String,
+    pub response_seconds: Option<i64>,
+    pub snooze_count: i64,
+    /// "on_time" | "late" | "missed"
+    pub status: String,
+}
+
+pub fn ensure_alarm_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS alarm_preferences (
+            user_id INTEGER PRIMARY KEY,
+            snooze_minutes INTEGER NOT NULL,
+            max_snoozes INTEGER NOT NULL,
+            on_time_grace_minutes INTEGER NOT NULL,
+            missed_after_minutes INTEGER NOT NULL,
+            require_core_tap INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS alarm_state (
+            alarm_key TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            scheduled_time TEXT NOT NULL,
+            fire_time TEXT NOT NULL,
+            status TEXT NOT NULL,
+            snooze_count INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS punctuality_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            alarm_key TEXT NOT NULL UNIQUE,
+            label TEXT NOT NULL,
+            scheduled_time TEXT NOT NULL,
+            actual_time TEXT NOT NULL,
+            response_seconds INTEGER,
+            snooze_count INTEGER NOT NULL DEFAULT 0,
+            status TEXT NOT NULL CHECK (status IN ('on_time', 'late', 'missed')),
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_punctuality_user_time ON punctuality_log(user_id, actual_time);
+    "#).map_err(|e| e.to_string())
+}
+
+pub fn load_preferences(conn: &Connection, user_id: i64) -> Result<AlarmPreferences, String> {
+    ensure_alarm_tables(conn)?;
+    let prefs = conn.query_row(
+        "SELECT snooze_minutes, max_snoozes, on_time_grace_minutes, missed_after_minutes, require_core_tap FROM alarm_preferences WHERE user_id = ?",
+        params![user_id],
+        |r| Ok(AlarmPreferences { snooze_minutes: r.get(0)?, max_snoozes: r.get(1)?, on_time_grace_minutes: r.get(2)?, missed_after_minutes: r.get(3)?, require_core_tap: r.get::<_, i64>(4)? != 0 }),
+    ).optional().map_err(|e| e.to_string())?;
+    Ok(prefs.unwrap_or_default())
+}
+
+pub fn save_preferences(conn: &Connection, user_id: i64, prefs: &AlarmPreferences) -> Result<(), String> {
+    ensure_alarm_tables(conn)?;
+    if prefs.snooze_minutes < 1 || prefs.max_snoozes < 0 || prefs.on_time_grace_minutes < 0 || prefs.missed_after_minutes < 1 {
+        return Err("Invalid alarm preferences".to_string());
+    }
+    conn.execute(
+        "INSERT INTO alarm_preferences (user_id, snooze_minutes, max_snoozes, on_time_grace_minutes, missed_after_minutes, require_core_tap) VALUES (?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(user_id) DO UPDATE SET snooze_minutes = excluded.snooze_minutes, max_snoozes = excluded.max_snoozes, \
+         on_time_grace_minutes = excluded.on_time_grace_minutes, missed_after_minutes = excluded.missed_after_minutes, require_core_tap = excluded.require_core_tap",
+        params![user_id, prefs.snooze_minutes, prefs.max_snoozes, prefs.on_time_grace_minutes, prefs.missed_after_minutes, prefs.require_core_tap as i64],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn fmt_ts(t: NaiveDateTime) -> String {
+    t.format(TS_FORMAT).to_string()
+}
+
+/// Alarms scheduled in `from..=to`: rows of the daily DBs plus recurring alarm occurrences.
+fn scheduled_alarms(conn: &Connection, workspace_dir: Option<&str>, user_id: i64, from: NaiveDateTime, to: NaiveDateTime) -> Vec<(String, String, NaiveDateTime)> {
+    let mut out = Vec::new();
+    let mut day = from.date();
+    while day <= to.date() {
+        if let Some(path) = utility::daily_db_path(workspace_dir, day) {
+            if let Ok(db) = Connection::open(path) {
+                if let Ok(mut stmt) = db.prepare("SELECT id, alarm_time, COALESCE(label, '') FROM alarm") {
+                    let rows = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?)));
+                    if let Ok(rows) = rows {
+                        for (id, time, label) in rows.flatten() {
+                            if let Some(t) = parse_timestamp(&time, day).filter(|t| *t >= from && *t <= to) {
+                                out.push((format!("daily:{}:{}", day, id), label, t));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        day = match day.succ_opt() { Some(d) => d, None => break };
+    }
+    if let Ok(occurrences) = recurrence::occurrences_between(conn, Some(user_id), from, to) {
+        for o in occurrences.into_iter().filter(|o| o.kind == "alarm") {
+            if let Ok(t) = recurrence::parse_local_datetime(&o.start) {
+                out.push((format!("recurrence:{}:{}", o.recurrence_id, o.original_start), o.title, t));
+            }
+        }
+    }
+    out.sort_by_key(|a| a.2);
+    out
+}
+
+/// Mark a daily-DB alarm row as triggered once it has fired.
+fn mark_triggered(workspace_dir: Option<&str>, alarm_key: &str) {
+    let mut parts = alarm_key.splitn(3, ':');
+    if let (Some("daily"), Some(date), Some(id)) = (parts.next(), parts.next(), parts.next()) {
+        if let (Ok(date), Ok(id)) = (NaiveDate::parse_from_str(date, "%Y-%m-%d"), id.parse::<i64>()) {
+            if let Some(path) = utility::daily_db_path(workspace_dir, date) {
+                if let Ok(db) = Connection::open(path) {
+                    let _ = db.execute("UPDATE alarm SET triggered = 1 WHERE id = ?", params![id]);
+                }
+            }
+        }
+    }
+}
+
+fn alarm_from_row(r: &rusqlite::Row, require_core_tap: bool) -> rusqlite::Result<ActiveAlarm> {
+    Ok(ActiveAlarm { alarm_key: r.get(0)?, user_id: r.get(1)?, label: r.get(2)?, scheduled_time: r.get(3)?, fire_time: r.get(4)?, status: r.get(5)?, snooze_count: r.get(6)?, require_core_tap })
+}
+
+const STATE_COLUMNS: &str = "alarm_key, user_id, label, scheduled_time, fire_time, status, snooze_count";
+
+pub fn get_state(conn: &Connection, alarm_key: &str) -> Result<Option<ActiveAlarm>, String> {
+    ensure_alarm_tables(conn)?;
+    let alarm = conn.query_row(&format!("SELECT {} FROM alarm_state WHERE alarm_key = ?", STATE_COLUMNS), params![alarm_key], |r| alarm_from_row(r, false))
+        .optional().map_err(|e| e.to_string())?;
+    match alarm {
+        Some(mut a) => {
+            a.require_core_tap = load_preferences(conn, a.user_id)?.require_core_tap;
+            Ok(Some(a))
+        }
+        None => Ok(None),
+    }
+}
+
+/// State of `alarm_key` if it belongs to `user_id`; another user's alarm reads as not active.
+fn owned_state(conn: &Connection, user_id: i64, alarm_key: &str) -> Result<ActiveAlarm, String> {
+    get_state(conn, alarm_key)?.filter(|a| a.user_id == user_id).ok_or_else(|| format!("Alarm {} is not active", alarm_key))
+}
+
+fn log_punctuality(conn: &Connection, alarm: &ActiveAlarm, actual: NaiveDateTime, response_seconds: Option<i64>, status: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO punctuality_log (user_id, alarm_key, label, scheduled_time, actual_time, response_seconds, snooze_count, status) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![alarm.user_id, alarm.alarm_key, alarm.label, alarm.scheduled_time, fmt_ts(actual), response_seconds, alarm.snooze_count, status],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn set_status(conn: &Connection, alarm_key: &str, status: &str, fire_time: Option<NaiveDateTime>, snooze_count: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE alarm_state SET status = ?, fire_time = COALESCE(?, fire_time), snooze_count = ?, updated_at = datetime('now') WHERE alarm_key = ?",
+        params![status, fire_time.map(fmt_ts), snooze_count, alarm_key],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// One scheduler step at `now`: start ringing newly due alarms, re-ring snoozed ones whose snooze
+/// ran out, and record alarms nobody answered as missed. Returns the alarms that fired.
+pub fn tick(conn: &Connection, workspace_dir: Option<&str>, user_id: i64, now: NaiveDateTime) -> Result<Vec<ActiveAlarm>, String> {
+    let prefs = load_preferences(conn, user_id)?;
+    let missed_after = ChronoDuration::minutes(prefs.missed_after_minutes);
+    let mut fired = Vec::new();
+
+    for (key, label, scheduled) in scheduled_alarms(conn, workspace_dir, user_id, now - ChronoDuration::hours(LOOKBACK_HOURS), now) {
+        if get_state(conn, &key)?.is_some() { continue; }
+        let missed = scheduled + missed_after < now;
+        conn.execute(
+            "INSERT INTO alarm_state (alarm_key, user_id, label, scheduled_time, fire_time, status) VALUES (?, ?, ?, ?, ?, ?)",
+            params![key, user_id, label, fmt_ts(scheduled), fmt_ts(scheduled), if missed { STATUS_MISSED } else { STATUS_RINGING }],
+        ).map_err(|e| e.to_string())?;
+        mark_triggered(workspace_dir, &key);
+        let alarm = get_state(conn, &key)?.ok_or("alarm state missing")?;
+        if missed {
+            // Went off while the app was closed
+            log_punctuality(conn, &alarm, scheduled, None, "missed")?;
+        } else {
+            fired.push(alarm);
+        }
+    }
+
+    let pending: Vec<ActiveAlarm> = {
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM alarm_state WHERE user_id = ? AND status IN (?, ?) ORDER BY fire_time", STATE_COLUMNS)).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![user_id, STATUS_RINGING, STATUS_SNOOZED], |r| alarm_from_row(r, prefs.require_core_tap)).map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+    };
+    for mut alarm in pending {
+        let fire_time = match recurrence::parse_local_datetime(&alarm.fire_time) { Ok(t) => t, Err(_) => continue };
+        if fire_time > now { continue; }
+        if fire_time + missed_after < now {
+            set_status(conn, &alarm.alarm_key, STATUS_MISSED, None, alarm.snooze_count)?;
+            let scheduled = recurrence::parse_local_datetime(&alarm.scheduled_time).unwrap_or(fire_time);
+            log_punctuality(conn, &alarm, scheduled, None, "missed")?;
+        } else if alarm.status == STATUS_SNOOZED {
+            set_status(conn, &alarm.alarm_key, STATUS_RINGING, None, alarm.snooze_count)?;
+            alarm.status = STATUS_RINGING.to_string();
+            fired.push(alarm);
+        }
+    }
+    Ok(fired)
+}
+
+/// Snooze a ringing alarm for the user's snooze length.
+pub fn snooze(conn: &Connection, user_id: i64, alarm_key: &str, now: NaiveDateTime) -> Result<ActiveAlarm, String> {
+    let alarm = owned_state(conn, user_id, alarm_key)?;
+    if alarm.status != STATUS_RINGING { return Err(format!("Alarm {} is {}, not ringing", alarm_key, alarm.status)); }
+    let prefs = load_preferences(conn, alarm.user_id)?;
+    if alarm.snooze_count >= prefs.max_snoozes { return Err("Snooze limit reached".to_string()); }
+    set_status(conn, alarm_key, STATUS_SNOOZED, Some(now + ChronoDuration::minutes(prefs.snooze_minutes)), alarm.snooze_count + 1)?;
+    get_state(conn, alarm_key)?.ok_or_else(|| "alarm state missing".to_string())
+}
+
+/// Whether `rfid` belongs to the core card registered in the day's DB.
+pub fn is_core_card(workspace_dir: Option<&str>, day: NaiveDate, rfid: &str) -> bool {
+    utility::daily_db_path(workspace_dir, day)
+        .and_then(|p| Connection::open(p).ok())
+        .and_then(|db| db.query_row("SELECT COUNT(*) FROM card WHERE rfid = ? AND type = 'core'", params![rfid], |r| r.get::<_, i64>(0)).ok())
+        .map(|n| n > 0)
+        .unwrap_or(false)
+}
+
+/// Dismiss a ringing or snoozed alarm and record how long the response took. When the user
+/// requires a core card tap, `core_tap_rfid` must be the registered core card.
+pub fn dismiss(conn: &Connection, user_id: i64, alarm_key: &str, now: NaiveDateTime, workspace_dir: Option<&str>, core_tap_rfid: Option<&str>) -> Result<PunctualityRecord, String> {
+    let alarm = owned_state(conn, user_id, alarm_key)?;
+    if alarm.status != STATUS_RINGING && alarm.status != STATUS_SNOOZED {
+        return Err(format!("Alarm {} is already {}", alarm_key, alarm.status));
+    }
+    let prefs = load_preferences(conn, alarm.user_id)?;
+    if prefs.require_core_tap {
+        match core_tap_rfid {
+            Some(rfid) if is_core_card(workspace_dir, now.date(), rfid) => {}
+            Some(_) => return Err("RFID is not the registered core card".to_string()),
+            None => return Err("Tap the core card to dismiss this alarm".to_string()),
+        }
+    }
+    let scheduled = recurrence::parse_local_datetime(&alarm.scheduled_time)?;
+    let response = (now - scheduled).num_seconds().max(0);
+    let status = if response <= prefs.on_time_grace_minutes * 60 { "on_time" } else { "late" };
+    set_status(conn, alarm_key, STATUS_DISMISSED, None, alarm.snooze_count)?;
+    log_punctuality(conn, &alarm, now, Some(response), status)?;
+    conn.query_row(
+        "SELECT id, user_id, alarm_key, label, scheduled_time, actual_time, response_seconds, snooze_count, status FROM punctuality_log WHERE alarm_key = ?",
+        params![alarm_key],
+        punctuality_from_row,
+    ).map_err(|e| e.to_string())
+}
+
+fn punctuality_from_row(r: &rusqlite::Row) -> rusqlite::Result<PunctualityRecord> {
+    Ok(PunctualityRecord { id: r.get(0)?, user_id: r.get(1)?, alarm_key: r.get(2)?, label: r.get(3)?, scheduled_time: r.get(4)?, actual_time: r.get(5)?, response_seconds: r.get(6)?, snooze_count: r.get(7)?, status: r.get(8)? })
+}
+
+pub fn active_alarms(conn: &Connection, user_id: i64) -> Result<Vec<ActiveAlarm>, String> {
+    let prefs = load_preferences(conn, user_id)?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM alarm_state WHERE user_id = ? AND status IN (?, ?) ORDER BY fire_time", STATE_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![user_id, STATUS_RINGING, STATUS_SNOOZED], |r| alarm_from_row(r, prefs.require_core_tap)).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn punctuality_since(conn: &Connection, user_id: i64, since: NaiveDate) -> Result<Vec<PunctualityRecord>, String> {
+    ensure_alarm_tables(conn)?;
+    let mut stmt = conn.prepare("SELECT id, user_id, alarm_key, label, scheduled_time, actual_time, response_seconds, snooze_count, status FROM punctuality_log WHERE user_id = ? AND DATE(scheduled_time) >= ? ORDER BY scheduled_time").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![user_id, since.to_string()], punctuality_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+struct SchedulerConfig {
+    user_id: i64,
+    workspace_dir: Option<String>,
+}
+
+static SCHEDULER: Lazy<Mutex<Option<SchedulerConfig>>> = Lazy::new(|| Mutex::new(None));
+
+/// Start the background scheduler that emits `alarm_fired`. Calling it again only switches the
+/// user/workspace it watches.
+#[tauri::command]
+pub fn start_alarm_scheduler(app: AppHandle, user_id: i64, workspace_dir: Option<String>) -> Result<(), String> {
+    let mut cfg = SCHEDULER.lock().map_err(|e| e.to_string())?;
+    let already_running = cfg.is_some();
+    *cfg = Some(SchedulerConfig { user_id, workspace_dir });
+    if already_running { return Ok(()); }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let current = SCHEDULER.lock().ok().and_then(|c| c.as_ref().map(|c| (c.user_id, c.workspace_dir.clone())));
+            if let Some((user_id, workspace_dir)) = current {
+                let fired = tokio::task::spawn_blocking(move || {
+                    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+                    tick(&conn, workspace_dir.as_deref(), user_id, Local::now().naive_local())
+                }).await;
+                match fired {
+                    Ok(Ok(alarms)) => for alarm in alarms { let _ = app.emit(ALARM_FIRED_EVENT, alarm); },
+                    Ok(Err(e)) => utility::log_error("alarm_scheduler", &e),
+                    Err(e) => utility::log_error("alarm_scheduler", &e.to_string()),
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(TICK_SECONDS)).await;
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn snooze_alarm(user_id: i64, alarm_key: String) -> Result<ActiveAlarm, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    snooze(&conn, user_id, &alarm_key, Local::now().naive_local())
+}
+
+#[tauri::command]
+pub fn dismiss_alarm(user_id: i64, alarm_key: String, workspace_dir: Option<String>, core_tap_rfid: Option<String>) -> Result<PunctualityRecord, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    dismiss(&conn, user_id, &alarm_key, Local::now().naive_local(), workspace_dir.as_deref(), core_tap_rfid.as_deref())
+}
+
+#[tauri::command]
+pub fn list_active_alarms(user_id: i64) -> Result<Vec<ActiveAlarm>, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    active_alarms(&conn, user_id)
+}
+
+#[tauri::command]
+pub fn get_alarm_preferences(user_id: i64) -> Result<AlarmPreferences, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    load_preferences(&conn, user_id)
+}
+
+#[tauri::command]
+pub fn set_alarm_preferences(user_id: i64, preferences: AlarmPreferences) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    save_preferences(&conn, user_id, &preferences)
+}
+
+#[tauri::command]
+pub fn get_punctuality_log(user_id: i64, days: i64) -> Result<Vec<PunctualityRecord>, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    punctuality_since(&conn, user_id, Local::now().date_naive() - ChronoDuration::days(days.max(1) - 1))
+}
+
 /// Alarms module: Alarm logic and CRUD.
 
 pub mod alarms {
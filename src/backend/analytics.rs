@@ -1,6 +1,304 @@
 //! Analytics module: Metrics, trends, recommendations.
 
-pub mod analytics {
-    // Analytics structs, trend logic, doc comments
-    // ...
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use std::collections::HashMap;
+use crate::TrendPoint;
+use crate::backend::{goal_time, local_journal, streaks, utility};
+
+// ---------- metric trends ----------
+//
+// Every metric is first read as one value per calendar day; a day without any rows has no value
+// at all rather than a zero. Trend points then reduce the days of a bucket:
+//   day   - one day, labelled YYYY-MM-DD
+//   week  - an ISO week (Monday to Sunday), labelled YYYY-Www
+//   month - a calendar month, labelled YYYY-MM
+// The current bucket only covers days up to today. Each point carries how many days contributed.
+// "streaks" is the current focus streak (see `streaks`) as of each day, read from the daily DBs of
+// the workspace holding the connection's database. Every day from the first one with a session on
+// has a value (zero once a streak is broken); earlier days have none.
+
+pub const METRICS: [&str; 4] = ["focus_score", "burnout", "punctuality", "streaks"];
+
+/// Per-day values of `metric` between `from` and `to` (inclusive). Days without data are absent.
+pub fn daily_values(conn: &Connection, user_id: i64, metric: &str, from: NaiveDate, to: NaiveDate) -> Result<HashMap<NaiveDate, f64>, String> {
+    if metric == "streaks" { return streak_values(conn, user_id, from, to); }
+    let sql = match metric {
+        "focus_score" => "SELECT DATE(start_time), AVG(focus_score) FROM session WHERE user_id = ? AND DATE(start_time) BETWEEN ? AND ? GROUP BY 1",
+        "burnout" => "SELECT DATE(start_time), AVG(burnout_score) FROM session WHERE user_id = ? AND DATE(start_time) BETWEEN ? AND ? GROUP BY 1",
+        "punctuality" => "SELECT DATE(actual_time), AVG(status = 'on_time') FROM punctuality_log WHERE user_id = ? AND DATE(actual_time) BETWEEN ? AND ? GROUP BY 1",
+        _ => return Err("Unsupported metric".to_string()),
+    };
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![user_id, from.to_string(), to.to_string()], |r| Ok((r.get::<_, Option<String>>(0)?, r.get::<_, Option<f64>>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut out = HashMap::new();
+    for row in rows {
+        if let (Some(day), Some(value)) = row.map_err(|e| e.to_string())? {
+            if let Ok(day) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") { out.insert(day, value); }
+        }
+    }
+    Ok(out)
+}
+
+/// Current streak length as of each day from the first day with a session, with `user_id`'s
+/// `min_session_time` as the threshold. An in-memory connection has no workspace and so no streak
+/// data.
+fn streak_values(conn: &Connection, user_id: i64, from: NaiveDate, to: NaiveDate) -> Result<HashMap<NaiveDate, f64>, String> {
+    let db = match conn.path().filter(|p| !p.is_empty()) { Some(p) => p.to_string(), None => return Ok(HashMap::new()) };
+    let workspace = std::path::Path::new(&db).parent().map(|p| p.to_string_lossy().to_string()).filter(|p| !p.is_empty());
+    let mut criteria = streaks::default_criteria(workspace.as_deref(), Some(user_id));
+    if let Some(m) = streaks::min_session_setting(conn, Some(user_id)) { criteria.min_focus_minutes = m; }
+    let days = streaks::streak_days(workspace.as_deref(), to, &criteria)?;
+    let first = match days.iter().position(|d| d.focus_minutes > 0) { Some(i) => i, None => return Ok(HashMap::new()) };
+    Ok(streaks::current_streak_by_day(&days, criteria.rest_days_per_week).into_iter().skip(first)
+        .filter(|(day, _)| *day >= from)
+        .map(|(day, current)| (day, current as f64))
+        .collect())
+}
+
+/// First day of the bucket holding `day`.
+pub fn bucket_start(day: NaiveDate, aggregation: &str) -> NaiveDate {
+    match aggregation {
+        "week" => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+        "month" => day.with_day(1).unwrap_or(day),
+        _ => day,
+    }
+}
+
+fn previous_bucket(start: NaiveDate, aggregation: &str) -> NaiveDate {
+    match aggregation {
+        "week" => start - Duration::days(7),
+        "month" => bucket_start(start - Duration::days(1), "month"),
+        _ => start - Duration::days(1),
+    }
+}
+
+fn bucket_end(start: NaiveDate, aggregation: &str) -> NaiveDate {
+    match aggregation {
+        "week" => start + Duration::days(6),
+        "month" => {
+            let next = if start.month() == 12 { NaiveDate::from_ymd_opt(start.year() + 1, 1, 1) } else { NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1) };
+            next.map(|n| n - Duration::days(1)).unwrap_or(start)
+        }
+        _ => start,
+    }
+}
+
+pub fn bucket_label(start: NaiveDate, aggregation: &str) -> String {
+    match aggregation {
+        "week" => format!("{}-W{:02}", start.iso_week().year(), start.iso_week().week()),
+        "month" => start.format("%Y-%m").to_string(),
+        _ => start.to_string(),
+    }
+}
+
+/// Check a reducer name: "avg", "sum", "max", "median" or a percentile "p1".."p100".
+pub fn parse_reducer(reducer: &str) -> Result<String, String> {
+    let r = reducer.trim().to_lowercase();
+    match r.as_str() {
+        "avg" | "sum" | "max" | "median" => Ok(r),
+        p if p.starts_with('p') && p[1..].parse::<u32>().is_ok_and(|n| (1..=100).contains(&n)) => Ok(r),
+        _ => Err(format!("Unsupported reducer '{}' (use avg, sum, max, median or p1-p100)", reducer)),
+    }
+}
+
+/// Percentile `p` (0-100) of `values` with linear interpolation between ranks.
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() { return None; }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64))
+}
+
+/// Reduce the values of one bucket; `None` when the bucket has no data.
+pub fn reduce(values: &[f64], reducer: &str) -> Option<f64> {
+    if values.is_empty() { return None; }
+    match reducer {
+        "sum" => Some(values.iter().sum()),
+        "max" => values.iter().copied().reduce(f64::max),
+        "median" => percentile(values, 50.0),
+        p if p.starts_with('p') => percentile(values, p[1..].parse().ok()?),
+        _ => Some(values.iter().sum::<f64>() / values.len() as f64),
+    }
+}
+
+/// The last `points` buckets of `metric` up to `today`, oldest first. Buckets without data have a
+/// value of 0.0 and `contributing_days` of 0.
+pub fn metric_trend(conn: &Connection, user_id: i64, metric: &str, points: i64, aggregation: &str, reducer: &str, today: NaiveDate) -> Result<Vec<TrendPoint>, String> {
+    if !METRICS.contains(&metric) { return Err("Unsupported metric".to_string()); }
+    let aggregation = match aggregation { "week" | "month" => aggregation, "day" => "day", other => return Err(format!("Unsupported aggregation '{}'", other)) };
+    let reducer = parse_reducer(reducer)?;
+    let mut starts = Vec::new();
+    let mut start = bucket_start(today, aggregation);
+    for _ in 0..points.max(0) {
+        starts.push(start);
+        start = previous_bucket(start, aggregation);
+    }
+    starts.reverse();
+    let first = match starts.first() { Some(d) => *d, None => return Ok(vec![]) };
+    let values = daily_values(conn, user_id, metric, first, today)?;
+
+    Ok(starts.into_iter().map(|start| {
+        let end = bucket_end(start, aggregation).min(today);
+        let mut day = start;
+        let mut bucket = Vec::new();
+        while day <= end {
+            if let Some(v) = values.get(&day) { bucket.push(*v); }
+            day += Duration::days(1);
+        }
+        TrendPoint {
+            date: bucket_label(start, aggregation),
+            value: reduce(&bucket, &reducer).unwrap_or(0.0),
+            contributing_days: bucket.len() as i64,
+        }
+    }).collect())
+}
+
+// ---------- focus heatmap ----------
+//
+// Weekday (Monday first) by hour-of-day matrices built from the daily DBs in a date range. Each
+// finished session on a session card is split at hour boundaries; distraction intervals are cut
+// out of it to get net focused minutes. The focus score of a cell is the minute-weighted average
+// of the sessions' `focus_score` where the daily DB records one, otherwise the share of session
+// time that was not lost to distractions (0-100). The distraction rate is distractions started
+// per hour of session time.
+
+const HEATMAP_DEFAULT_DAYS: i64 = 28;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FocusHeatmap {
+    pub from: String,
+    pub to: String,
+    /// Days in the range that had a daily DB.
+    pub days_with_data: i64,
+    /// [weekday][hour], weekday 0 = Monday.
+    pub focused_minutes: Vec<Vec<f64>>,
+    pub session_minutes: Vec<Vec<f64>>,
+    pub focus_score: Vec<Vec<Option<f64>>>,
+    pub distraction_rate: Vec<Vec<Option<f64>>>,
+    /// Net focused minutes per hour of day across all weekdays.
+    pub hour_totals: Vec<f64>,
+    /// Hour of day with the most net focus, if any.
+    pub best_hour: Option<u32>,
+    /// (weekday, hour) cell with the most net focus, if any.
+    pub best_slot: Option<(u32, u32)>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Cell {
+    gross: i64,
+    net: i64,
+    scored: i64,
+    score_sum: f64,
+    distractions: i64,
+}
+
+fn round1(x: f64) -> f64 { (x * 10.0).round() / 10.0 }
+
+/// `[start, end)` cut at each full hour.
+pub fn split_at_hours(start: NaiveDateTime, end: NaiveDateTime) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut out = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let hour_start = cursor.date().and_hms_opt(cursor.hour(), 0, 0).unwrap_or(cursor);
+        let next = (hour_start + Duration::hours(1)).min(end);
+        out.push((cursor, next));
+        cursor = next;
+    }
+    out
+}
+
+fn add_day(cells: &mut [[Cell; 24]; 7], conn: &Connection, day: NaiveDate) {
+    let gaps = goal_time::distraction_intervals(conn, day);
+    let score_expr = if utility::table_columns(conn, "session").contains("focus_score") { "s.focus_score" } else { "NULL" };
+    let sql = format!("SELECT s.start_time, s.end_time, {} FROM session s JOIN card c ON c.id = s.card_id WHERE c.type = 'session' AND s.end_time IS NOT NULL", score_expr);
+    let mut sessions: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+    if let Ok(mut stmt) = conn.prepare(&sql) {
+        if let Ok(rows) = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<f64>>(2)?))) {
+            for (start, end, score) in rows.flatten() {
+                let (start, end) = match (local_journal::parse_timestamp(&start, day), local_journal::parse_timestamp(&end, day)) {
+                    (Some(s), Some(e)) if e > s => (s, e),
+                    _ => continue,
+                };
+                sessions.push((start, end));
+                for (a, b) in split_at_hours(start, end) {
+                    let cell = &mut cells[a.weekday().num_days_from_monday() as usize][a.hour() as usize];
+                    let gross = (b - a).num_seconds();
+                    cell.gross += gross;
+                    cell.net += goal_time::net_seconds(a, b, &gaps);
+                    if let Some(score) = score {
+                        cell.scored += gross;
+                        cell.score_sum += score * gross as f64;
+                    }
+                }
+            }
+        }
+    }
+    let dcols = utility::table_columns(conn, "distraction");
+    let start_expr = if dcols.contains("start_time") { "COALESCE(start_time, created_at)" } else { "created_at" };
+    if let Ok(mut stmt) = conn.prepare(&format!("SELECT {} FROM distraction", start_expr)) {
+        if let Ok(rows) = stmt.query_map([], |r| r.get::<_, Option<String>>(0)) {
+            for at in rows.flatten().flatten().filter_map(|s| local_journal::parse_timestamp(&s, day)) {
+                // Only distractions that interrupted a session count toward the rate
+                if sessions.iter().any(|(s, e)| at >= *s && at < *e) {
+                    cells[at.weekday().num_days_from_monday() as usize][at.hour() as usize].distractions += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Build the heatmap over the daily DBs between `from` and `to` (inclusive).
+pub fn focus_heatmap(workspace_dir: Option<&str>, from: NaiveDate, to: NaiveDate) -> FocusHeatmap {
+    let mut cells = [[Cell::default(); 24]; 7];
+    let mut days_with_data = 0;
+    for (day, path) in utility::list_daily_dbs(workspace_dir) {
+        if day < from || day > to { continue; }
+        if let Ok(conn) = Connection::open(&path) {
+            days_with_data += 1;
+            add_day(&mut cells, &conn, day);
+        }
+    }
+
+    let grid = |f: &dyn Fn(&Cell) -> f64| -> Vec<Vec<f64>> { cells.iter().map(|row| row.iter().map(f).collect()).collect() };
+    let opt_grid = |f: &dyn Fn(&Cell) -> Option<f64>| -> Vec<Vec<Option<f64>>> { cells.iter().map(|row| row.iter().map(f).collect()).collect() };
+    let hour_totals: Vec<f64> = (0..24).map(|h| round1(cells.iter().map(|row| row[h].net).sum::<i64>() as f64 / 60.0)).collect();
+    let best_hour = (0..24).filter(|&h| hour_totals[h] > 0.0).max_by(|&a, &b| hour_totals[a].total_cmp(&hour_totals[b]).then(b.cmp(&a))).map(|h| h as u32);
+    let best_slot = (0..7).flat_map(|w| (0..24).map(move |h| (w, h)))
+        .filter(|&(w, h)| cells[w][h].net > 0)
+        .max_by(|&(aw, ah), &(bw, bh)| cells[aw][ah].net.cmp(&cells[bw][bh].net).then((bw, bh).cmp(&(aw, ah))))
+        .map(|(w, h)| (w as u32, h as u32));
+
+    FocusHeatmap {
+        from: from.to_string(),
+        to: to.to_string(),
+        days_with_data,
+        focused_minutes: grid(&|c| round1(c.net as f64 / 60.0)),
+        session_minutes: grid(&|c| round1(c.gross as f64 / 60.0)),
+        focus_score: opt_grid(&|c| {
+            if c.scored > 0 { Some(round1(c.score_sum / c.scored as f64)) }
+            else if c.gross > 0 { Some(round1(100.0 * c.net as f64 / c.gross as f64)) }
+            else { None }
+        }),
+        distraction_rate: opt_grid(&|c| if c.gross > 0 { Some((c.distractions as f64 / (c.gross as f64 / 3600.0) * 100.0).round() / 100.0) } else { None }),
+        hour_totals,
+        best_hour,
+        best_slot,
+    }
+}
+
+/// Weekday by hour focus heatmap between `from` and `to` (YYYY-MM-DD, default the last 28 days).
+#[tauri::command]
+pub fn get_focus_heatmap(workspace_dir: Option<String>, from: Option<String>, to: Option<String>) -> Result<FocusHeatmap, String> {
+    let parse = |s: Option<String>| -> Result<Option<NaiveDate>, String> {
+        s.map(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))).transpose()
+    };
+    let to = parse(to)?.unwrap_or_else(|| Local::now().date_naive());
+    let from = parse(from)?.unwrap_or(to - Duration::days(HEATMAP_DEFAULT_DAYS - 1));
+    if from > to { return Err("from must not be after to".to_string()); }
+    Ok(focus_heatmap(workspace_dir.as_deref(), from, to))
 }
@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 use rusqlite::{Connection};
-use crate::backend::{utility};
+use crate::backend::{goal_time, goals, habits, utility};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DashboardAlarm {
@@ -36,6 +36,20 @@ pub struct DashboardSummary {
     pub upcoming_events: Vec<DashboardEvent>,
     pub reminders: Vec<String>,
     pub recent_journal_entries: Vec<DashboardJournalEntry>,
+    /// Net focus hours invested in each open goal, all time and today.
+    #[serde(default)]
+    pub goal_hours: Vec<DashboardGoalHours>,
+    /// Active habits with today's progress and streaks.
+    #[serde(default)]
+    pub habits: Vec<habits::HabitStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DashboardGoalHours {
+    pub goal_id: i64,
+    pub title: String,
+    pub hours: f64,
+    pub today_minutes: i64,
 }
 
 fn find_db(workspace_dir: Option<String>) -> Option<std::path::PathBuf> {
@@ -57,7 +71,7 @@ fn pick_alarm_time_col(conn: &Connection) -> Option<String> {
 
 #[tauri::command]
 pub fn get_dashboard_summary(workspace_dir: Option<String>, user_id: Option<i64>) -> Result<DashboardSummary, String> {
-    let db_path = find_db(workspace_dir).ok_or("Daily DB not found".to_string())?;
+    let db_path = find_db(workspace_dir.clone()).ok_or("Daily DB not found".to_string())?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
     // date label
@@ -74,11 +88,15 @@ pub fn get_dashboard_summary(workspace_dir: Option<String>, user_id: Option<i64>
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM distraction WHERE DATE(created_at) = DATE('now')").map_err(|e| e.to_string())?;
     let distractions_count: i64 = stmt.query_row([], |r| r.get(0)).map_err(|e| e.to_string())?;
 
-    // pending goals/tasks
-    let mut stmt = conn.prepare("SELECT COUNT(*) FROM goal WHERE COALESCE(completed,0)=0").map_err(|e| e.to_string())?;
-    let pending_goals: i64 = stmt.query_row([], |r| r.get(0)).map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT COUNT(*) FROM task WHERE COALESCE(completed,0)=0").map_err(|e| e.to_string())?;
-    let pending_tasks: i64 = stmt.query_row([], |r| r.get(0)).map_err(|e| e.to_string())?;
+    // pending goals/tasks, from the workspace store
+    let store = goals::open_store_for_read(workspace_dir.as_deref())?;
+    let (pending_goals, pending_tasks) = match &store {
+        Some(store) => (
+            goals::list_goal_rows(store, None)?.iter().filter(|g| goals::is_active(&g.status)).count() as i64,
+            goals::list_task_rows(store, None, None)?.iter().filter(|t| goals::is_active(&t.status)).count() as i64,
+        ),
+        None => (0, 0),
+    };
 
     // upcoming alarms (limit 10)
     let mut upcoming_alarms = Vec::new();
@@ -131,6 +149,21 @@ pub fn get_dashboard_summary(workspace_dir: Option<String>, user_id: Option<i64>
         }
     }
 
+    // hours invested per goal (sessions attributed through tags, card goal_ids or linked cards)
+    let mut goal_hours = Vec::new();
+    if let Some(store) = &store {
+        if let Ok(sessions) = goal_time::attributed_sessions(store, workspace_dir.as_deref()) {
+            let today = chrono::Local::now().date_naive();
+            for g in goal_time::goal_time(store, &sessions, None, None).unwrap_or_default().into_iter().filter(|g| goals::is_active(&g.status)) {
+                let today_minutes = sessions.iter().filter(|s| s.day == today).map(|s| s.minutes_for(g.goal_id)).sum();
+                goal_hours.push(DashboardGoalHours { goal_id: g.goal_id, title: g.title, hours: g.hours, today_minutes });
+            }
+        }
+    }
+
+    // habits
+    let habits = habits::get_habit_status(workspace_dir, None).unwrap_or_default();
+
     Ok(DashboardSummary {
         date,
         sessions_count,
@@ -142,5 +175,7 @@ pub fn get_dashboard_summary(workspace_dir: Option<String>, user_id: Option<i64>
         upcoming_events,
         reminders,
         recent_journal_entries,
+        goal_hours,
+        habits,
     })
 }
@@ -0,0 +1,446 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use crate::backend::{goals, local_journal, utility};
+
+// ---------- distraction analytics ----------
+//
+// Free-text distraction reasons are grouped into categories that the user can edit. A category
+// matches when the lowercased reason contains one of its keywords; categories are tried in
+// `position` order, an empty reason is "Unspecified" and anything else unmatched is "Other".
+// Categories live in a workspace-level store and apply to the whole history, so renaming or
+// re-keywording one regroups past distractions too.
+//
+// Durations come from the distraction's start_time/end_time. A distraction belongs to the
+// session it was logged against, or else the finished session it started in. Recovery time is
+// measured for distractions that ended outside any session: the gap until the next session starts
+// on the same day.
+
+/// Workspace store holding the distraction categories.
+pub const STORE_FILE: &str = "distraction_categories.sqlite3";
+
+pub const OTHER: &str = "Other";
+pub const UNSPECIFIED: &str = "Unspecified";
+
+const DEFAULT_CATEGORIES: [(&str, &str); 5] = [
+    ("Phone", "phone,call,text,sms,whatsapp"),
+    ("Social media", "social,instagram,tiktok,twitter,facebook,reddit,youtube"),
+    ("Messages", "email,mail,slack,teams,message,notification"),
+    ("People", "colleague,family,visitor,kid,chat,interrupt"),
+    ("Self", "hungry,snack,coffee,tired,break,bathroom,restless"),
+];
+
+const SESSION_LENGTH_BUCKETS: [(&str, i64); 4] = [("<25 min", 25), ("25-49 min", 50), ("50-89 min", 90), ("90+ min", i64::MAX)];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DistractionCategory {
+    pub id: i64,
+    pub name: String,
+    pub keywords: Vec<String>,
+    pub position: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParetoEntry {
+    pub category: String,
+    pub count: i64,
+    pub minutes: f64,
+    /// Share of all distractions (by count or by minutes, depending on the list).
+    pub share: f64,
+    pub cumulative_share: f64,
+    /// Distinct raw reasons grouped into this category.
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HourStat {
+    pub hour: u32,
+    pub count: i64,
+    pub minutes: f64,
+    pub session_minutes: f64,
+    /// Distractions per hour of session time.
+    pub rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionLengthStat {
+    pub bucket: String,
+    pub sessions: i64,
+    pub distractions: i64,
+    pub rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WeekOverWeek {
+    pub this_week_count: i64,
+    pub last_week_count: i64,
+    /// Percent change, `None` when last week had nothing to compare with.
+    pub count_change_pct: Option<f64>,
+    pub this_week_minutes: f64,
+    pub last_week_minutes: f64,
+    pub minutes_change_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DistractionAnalytics {
+    pub from: String,
+    pub to: String,
+    pub total: i64,
+    /// Distractions with both a start and an end.
+    pub timed: i64,
+    pub total_minutes: f64,
+    pub by_count: Vec<ParetoEntry>,
+    pub by_minutes: Vec<ParetoEntry>,
+    pub by_hour: Vec<HourStat>,
+    pub by_session_length: Vec<SessionLengthStat>,
+    /// Pearson correlation between session length and distractions per hour, when at least three
+    /// sessions vary in both.
+    pub session_length_correlation: Option<f64>,
+    pub avg_recovery_minutes: Option<f64>,
+    pub recovery_samples: i64,
+    /// The 7 days ending `to` against the 7 days before.
+    pub week_over_week: WeekOverWeek,
+}
+
+pub fn store_path(workspace_dir: Option<&str>) -> PathBuf {
+    utility::workspace_store_path(workspace_dir, STORE_FILE)
+}
+
+pub fn ensure_store_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS distraction_categories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            keywords TEXT NOT NULL DEFAULT '',
+            position INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    "#).map_err(|e| e.to_string())?;
+    // Seed the defaults once; deleting them all later must not bring them back
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0)).map_err(|e| e.to_string())?;
+    if version == 0 {
+        for (i, (name, keywords)) in DEFAULT_CATEGORIES.iter().enumerate() {
+            conn.execute("INSERT OR IGNORE INTO distraction_categories (name, keywords, position) VALUES (?, ?, ?)", params![name, keywords, i as i64]).map_err(|e| e.to_string())?;
+        }
+        conn.execute_batch("PRAGMA user_version = 1;").map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn open_store(workspace_dir: Option<&str>) -> Result<Connection, String> {
+    let path = store_path(workspace_dir);
+    if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+    let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+    ensure_store_schema(&conn)?;
+    Ok(conn)
+}
+
+fn keyword_list(keywords: &str) -> Vec<String> {
+    let mut out: Vec<String> = keywords.split(',').map(|k| k.trim().to_lowercase()).filter(|k| !k.is_empty()).collect();
+    out.dedup();
+    out
+}
+
+/// The workspace's categories in matching order; the built-in defaults (with id 0) when it has no
+/// category store yet.
+pub fn load_categories(workspace_dir: Option<&str>) -> Result<Vec<DistractionCategory>, String> {
+    if !store_path(workspace_dir).is_file() {
+        return Ok(DEFAULT_CATEGORIES.iter().enumerate()
+            .map(|(i, (name, keywords))| DistractionCategory { id: 0, name: name.to_string(), keywords: keyword_list(keywords), position: i as i64 })
+            .collect());
+    }
+    let conn = open_store(workspace_dir)?;
+    let mut stmt = conn.prepare("SELECT id, name, keywords, position FROM distraction_categories ORDER BY position, id").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, i64>(3)?))).map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (id, name, keywords, position) = row.map_err(|e| e.to_string())?;
+        out.push(DistractionCategory { id, name, keywords: keyword_list(&keywords), position });
+    }
+    Ok(out)
+}
+
+fn words(s: &str) -> Vec<&str> {
+    s.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect()
+}
+
+/// Whether `keyword` appears in `reason` as whole words, so "text" matches "texts from mum" but
+/// not "context switch". A trailing plural "s" on the last word is allowed.
+fn mentions(reason: &[&str], keyword: &str) -> bool {
+    let kw = words(keyword);
+    if kw.is_empty() { return false; }
+    reason.windows(kw.len()).any(|w| {
+        w.iter().zip(&kw).enumerate().all(|(i, (r, k))| r == k || (i + 1 == kw.len() && r.strip_suffix('s') == Some(k)))
+    })
+}
+
+/// Category name for a raw reason.
+pub fn categorize(reason: &str, categories: &[DistractionCategory]) -> String {
+    let reason = reason.trim().to_lowercase();
+    if reason.is_empty() { return UNSPECIFIED.to_string(); }
+    let tokens = words(&reason);
+    categories.iter()
+        .find(|c| c.keywords.iter().any(|k| mentions(&tokens, k)) || c.name.to_lowercase() == reason)
+        .map(|c| c.name.clone())
+        .unwrap_or_else(|| OTHER.to_string())
+}
+
+struct Record {
+    day: NaiveDate,
+    session: Option<usize>,
+    reason: String,
+    category: String,
+    at: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    seconds: i64,
+}
+
+struct SessionSpan {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+}
+
+fn read_day(conn: &Connection, day: NaiveDate, categories: &[DistractionCategory], sessions: &mut Vec<SessionSpan>, records: &mut Vec<Record>) {
+    let base = sessions.len();
+    let mut ids: HashMap<i64, usize> = HashMap::new();
+    if let Ok(mut stmt) = conn.prepare("SELECT s.id, s.start_time, s.end_time FROM session s JOIN card c ON c.id = s.card_id WHERE c.type = 'session' AND s.end_time IS NOT NULL ORDER BY s.start_time") {
+        if let Ok(rows) = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?))) {
+            for (id, start, end) in rows.flatten() {
+                if let (Some(start), Some(end)) = (local_journal::parse_timestamp(&start, day), local_journal::parse_timestamp(&end, day)) {
+                    if end > start {
+                        ids.insert(id, sessions.len());
+                        sessions.push(SessionSpan { start, end });
+                    }
+                }
+            }
+        }
+    }
+    let timed = utility::table_columns(conn, "distraction").contains("start_time");
+    let sql = if timed {
+        "SELECT session_id, COALESCE(reason, ''), start_time, end_time, created_at FROM distraction"
+    } else {
+        "SELECT session_id, COALESCE(reason, ''), NULL, NULL, created_at FROM distraction"
+    };
+    let mut stmt = match conn.prepare(sql) { Ok(s) => s, Err(_) => return };
+    let rows = match stmt.query_map([], |r| Ok((r.get::<_, Option<i64>>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?, r.get::<_, Option<String>>(3)?, r.get::<_, Option<String>>(4)?))) {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    for (session_id, reason, start, end, created) in rows.flatten() {
+        let start = start.as_deref().and_then(|s| local_journal::parse_timestamp(s, day));
+        let end = end.as_deref().and_then(|s| local_journal::parse_timestamp(s, day)).filter(|e| start.is_some_and(|s| *e >= s));
+        let at = start.or_else(|| created.as_deref().and_then(|s| local_journal::parse_timestamp(s, day)));
+        let session = session_id.and_then(|id| ids.get(&id).copied())
+            .or_else(|| at.and_then(|t| (base..sessions.len()).find(|&i| sessions[i].start <= t && t < sessions[i].end)));
+        records.push(Record {
+            day,
+            session,
+            category: categorize(&reason, categories),
+            reason: reason.trim().to_string(),
+            at,
+            end,
+            seconds: match (start, end) { (Some(s), Some(e)) => (e - s).num_seconds(), _ => 0 },
+        });
+    }
+}
+
+fn minutes(seconds: i64) -> f64 {
+    (seconds as f64 / 60.0 * 10.0).round() / 10.0
+}
+
+fn pct_change(now: f64, before: f64) -> Option<f64> {
+    if before <= 0.0 { return None; }
+    Some(((now - before) / before * 1000.0).round() / 10.0)
+}
+
+fn pareto(records: &[&Record], by_minutes: bool) -> Vec<ParetoEntry> {
+    let mut groups: HashMap<String, (i64, i64, Vec<String>)> = HashMap::new();
+    for r in records {
+        let g = groups.entry(r.category.clone()).or_default();
+        g.0 += 1;
+        g.1 += r.seconds;
+        if !r.reason.is_empty() && !g.2.iter().any(|x| x.eq_ignore_ascii_case(&r.reason)) { g.2.push(r.reason.clone()); }
+    }
+    let mut rows: Vec<(String, i64, i64, Vec<String>)> = groups.into_iter().map(|(k, (c, s, mut reasons))| { reasons.sort(); (k, c, s, reasons) }).collect();
+    if by_minutes {
+        rows.retain(|r| r.2 > 0);
+        rows.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)).then(a.0.cmp(&b.0)));
+    } else {
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+    }
+    let total: i64 = rows.iter().map(|r| if by_minutes { r.2 } else { r.1 }).sum();
+    let mut running = 0;
+    rows.into_iter().map(|(category, count, seconds, reasons)| {
+        let part = if by_minutes { seconds } else { count };
+        running += part;
+        let share = |x: i64| if total > 0 { (x as f64 / total as f64 * 1000.0).round() / 1000.0 } else { 0.0 };
+        ParetoEntry { category, count, minutes: minutes(seconds), share: share(part), cumulative_share: share(running), reasons }
+    }).collect()
+}
+
+fn pearson(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 3 { return None; }
+    let n = points.len() as f64;
+    let (mx, my) = (points.iter().map(|p| p.0).sum::<f64>() / n, points.iter().map(|p| p.1).sum::<f64>() / n);
+    let cov: f64 = points.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+    let vx: f64 = points.iter().map(|p| (p.0 - mx).powi(2)).sum();
+    let vy: f64 = points.iter().map(|p| (p.1 - my).powi(2)).sum();
+    if vx <= 0.0 || vy <= 0.0 { return None; }
+    Some((cov / (vx.sqrt() * vy.sqrt()) * 1000.0).round() / 1000.0)
+}
+
+/// Distraction analytics over the daily DBs between `from` and `to` (inclusive).
+pub fn distraction_analytics(workspace_dir: Option<&str>, from: NaiveDate, to: NaiveDate) -> Result<DistractionAnalytics, String> {
+    let categories = load_categories(workspace_dir)?;
+    let week_start = to - Duration::days(13);
+    let mut sessions = Vec::new();
+    let mut records = Vec::new();
+    for (day, path) in utility::list_daily_dbs(workspace_dir) {
+        if day > to || (day < from && day < week_start) { continue; }
+        if let Ok(conn) = Connection::open(&path) { read_day(&conn, day, &categories, &mut sessions, &mut records); }
+    }
+
+    let this_week = |r: &&Record| r.day > to - Duration::days(7);
+    let last_week = |r: &&Record| r.day <= to - Duration::days(7) && r.day >= week_start;
+    let (this_w, last_w): (Vec<&Record>, Vec<&Record>) = (records.iter().filter(this_week).collect(), records.iter().filter(last_week).collect());
+    let (this_s, last_s) = (this_w.iter().map(|r| r.seconds).sum::<i64>(), last_w.iter().map(|r| r.seconds).sum::<i64>());
+    let week_over_week = WeekOverWeek {
+        this_week_count: this_w.len() as i64,
+        last_week_count: last_w.len() as i64,
+        count_change_pct: pct_change(this_w.len() as f64, last_w.len() as f64),
+        this_week_minutes: minutes(this_s),
+        last_week_minutes: minutes(last_s),
+        minutes_change_pct: pct_change(this_s as f64, last_s as f64),
+    };
+
+    let in_range: Vec<&Record> = records.iter().filter(|r| r.day >= from).collect();
+    let session_ids: HashSet<usize> = (0..sessions.len()).filter(|&i| sessions[i].start.date() >= from).collect();
+
+    // Time of day: distractions by the hour they started, against session time in that hour
+    let mut by_hour: Vec<HourStat> = (0..24).map(|h| HourStat { hour: h, count: 0, minutes: 0.0, session_minutes: 0.0, rate: None }).collect();
+    let mut hour_seconds = [0i64; 24];
+    let mut session_seconds = [0i64; 24];
+    for r in &in_range {
+        if let Some(at) = r.at {
+            by_hour[at.hour() as usize].count += 1;
+            hour_seconds[at.hour() as usize] += r.seconds;
+        }
+    }
+    for &i in &session_ids {
+        let (mut cursor, end) = (sessions[i].start, sessions[i].end);
+        while cursor < end {
+            let next = (cursor.date().and_hms_opt(cursor.hour(), 0, 0).unwrap_or(cursor) + Duration::hours(1)).min(end);
+            session_seconds[cursor.hour() as usize] += (next - cursor).num_seconds();
+            cursor = next;
+        }
+    }
+    for (h, stat) in by_hour.iter_mut().enumerate() {
+        stat.minutes = minutes(hour_seconds[h]);
+        stat.session_minutes = minutes(session_seconds[h]);
+        if session_seconds[h] > 0 { stat.rate = Some((stat.count as f64 / (session_seconds[h] as f64 / 3600.0) * 100.0).round() / 100.0); }
+    }
+
+    // Session length: distractions per hour for short and long sessions
+    let mut per_session: HashMap<usize, i64> = HashMap::new();
+    for r in &in_range { if let Some(i) = r.session { *per_session.entry(i).or_default() += 1; } }
+    let mut buckets: Vec<(i64, i64, i64)> = vec![(0, 0, 0); SESSION_LENGTH_BUCKETS.len()];
+    let mut points = Vec::new();
+    for &i in &session_ids {
+        let secs = (sessions[i].end - sessions[i].start).num_seconds();
+        let mins = secs / 60;
+        let n = per_session.get(&i).copied().unwrap_or(0);
+        let b = SESSION_LENGTH_BUCKETS.iter().position(|(_, upper)| mins < *upper).unwrap_or(SESSION_LENGTH_BUCKETS.len() - 1);
+        buckets[b] = (buckets[b].0 + 1, buckets[b].1 + n, buckets[b].2 + secs);
+        points.push((mins as f64, n as f64 / (secs as f64 / 3600.0)));
+    }
+    let by_session_length = SESSION_LENGTH_BUCKETS.iter().zip(buckets).map(|((label, _), (count, n, secs))| SessionLengthStat {
+        bucket: label.to_string(),
+        sessions: count,
+        distractions: n,
+        rate: if secs > 0 { Some((n as f64 / (secs as f64 / 3600.0) * 100.0).round() / 100.0) } else { None },
+    }).collect();
+
+    // Recovery: from a distraction that ended outside any session to the next session that day
+    let mut recoveries = Vec::new();
+    for r in &in_range {
+        let end = match r.end { Some(e) => e, None => continue };
+        if sessions.iter().any(|s| s.start <= end && end < s.end) { continue; }
+        if let Some(next) = sessions.iter().filter(|s| s.start >= end && s.start.date() == end.date()).map(|s| s.start).min() {
+            recoveries.push((next - end).num_seconds());
+        }
+    }
+
+    Ok(DistractionAnalytics {
+        from: from.to_string(),
+        to: to.to_string(),
+        total: in_range.len() as i64,
+        timed: in_range.iter().filter(|r| r.end.is_some()).count() as i64,
+        total_minutes: minutes(in_range.iter().map(|r| r.seconds).sum()),
+        by_count: pareto(&in_range, false),
+        by_minutes: pareto(&in_range, true),
+        by_hour,
+        by_session_length,
+        session_length_correlation: pearson(&points),
+        avg_recovery_minutes: if recoveries.is_empty() { None } else { Some(minutes(recoveries.iter().sum::<i64>() / recoveries.len() as i64)) },
+        recovery_samples: recoveries.len() as i64,
+        week_over_week,
+    })
+}
+
+/// Distraction breakdown between `from` and `to` (YYYY-MM-DD, default the last 28 days).
+#[tauri::command]
+pub fn get_distraction_analytics(workspace_dir: Option<String>, from: Option<String>, to: Option<String>) -> Result<DistractionAnalytics, String> {
+    let parse = |s: Option<String>| -> Result<Option<NaiveDate>, String> {
+        s.map(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))).transpose()
+    };
+    let to = parse(to)?.unwrap_or_else(|| Local::now().date_naive());
+    let from = parse(from)?.unwrap_or(to - Duration::days(27));
+    if from > to { return Err("from must not be after to".to_string()); }
+    distraction_analytics(workspace_dir.as_deref(), from, to)
+}
+
+fn require_category(conn: &Connection, id: i64) -> Result<(), String> {
+    let n: i64 = conn.query_row("SELECT COUNT(*) FROM distraction_categories WHERE id = ?", params![id], |r| r.get(0)).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("category {} not found", id)); }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_distraction_categories(workspace_dir: Option<String>) -> Result<Vec<DistractionCategory>, String> {
+    load_categories(workspace_dir.as_deref())
+}
+
+/// Create a category (no `id`) or rename / re-keyword / move an existing one. Keywords are matched
+/// case-insensitively as substrings of the reason.
+#[tauri::command]
+pub fn save_distraction_category(workspace_dir: Option<String>, id: Option<i64>, name: String, keywords: Vec<String>, position: Option<i64>) -> Result<DistractionCategory, String> {
+    let name = goals::non_empty(Some(&name)).ok_or("Category name must not be empty")?;
+    if name.eq_ignore_ascii_case(OTHER) || name.eq_ignore_ascii_case(UNSPECIFIED) { return Err(format!("'{}' is reserved", name)); }
+    let keywords = keyword_list(&keywords.join(","));
+    let conn = open_store(workspace_dir.as_deref())?;
+    let taken: Option<i64> = conn.query_row("SELECT id FROM distraction_categories WHERE name = ?", params![name], |r| r.get(0)).ok();
+    if taken.is_some() && taken != id { return Err(format!("A category named '{}' already exists", name)); }
+    let id = match id {
+        Some(id) => {
+            require_category(&conn, id)?;
+            conn.execute("UPDATE distraction_categories SET name = ?, keywords = ?, position = COALESCE(?, position) WHERE id = ?", params![name, keywords.join(","), position, id]).map_err(|e| e.to_string())?;
+            id
+        }
+        None => {
+            let next: i64 = conn.query_row("SELECT COALESCE(MAX(position) + 1, 0) FROM distraction_categories", [], |r| r.get(0)).map_err(|e| e.to_string())?;
+            conn.execute("INSERT INTO distraction_categories (name, keywords, position) VALUES (?, ?, ?)", params![name, keywords.join(","), position.unwrap_or(next)]).map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        }
+    };
+    load_categories(workspace_dir.as_deref())?.into_iter().find(|c| c.id == id).ok_or_else(|| format!("category {} not found", id))
+}
+
+#[tauri::command]
+pub fn delete_distraction_category(workspace_dir: Option<String>, id: i64) -> Result<(), String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    require_category(&conn, id)?;
+    conn.execute("DELETE FROM distraction_categories WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
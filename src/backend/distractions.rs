@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
-use rusqlite::Connection;
-use crate::backend::utility;
+use rusqlite::{Connection, params, OptionalExtension};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use crate::backend::{local_journal, utility};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DistractionLog {
@@ -14,38 +15,118 @@ pub struct DistractionLog {
 pub fn get_today_distractions(workspace_dir: Option<String>) -> Result<Vec<DistractionLog>, String> {
     if let Some(path) = utility::find_daily_db(workspace_dir, None) {
         if let Ok(conn) = Connection::open(path) {
-            let mut cols = std::collections::HashSet::new();
-            if let Ok(mut s) = conn.prepare("PRAGMA table_info(distraction)") {
-                if let Ok(mut rows) = s.query([]) {
-                    while let Ok(Some(r)) = rows.next() {
-                        if let Ok(name) = r.get::<_, String>(1) { cols.insert(name); }
-                    }
-                }
-            }
-
-            let start_expr = if cols.contains("start_time") { "start_time AS start_time".to_string() } else { "'' AS start_time".to_string() };
-            let end_expr = if cols.contains("end_time") { "end_time AS end_time".to_string() } else { "'' AS end_time".to_string() };
-            let label_expr = if cols.contains("label") { "label AS label".to_string() } else { "'' AS label".to_string() };
-            let reason_expr = if cols.contains("reason") { "reason AS reason".to_string() } else { "'' AS reason".to_string() };
-
-            let sql = format!("SELECT {start}, {end}, {label}, {reason} FROM distraction ORDER BY start_time DESC", start = start_expr, end = end_expr, label = label_expr, reason = reason_expr);
-            if let Ok(mut stmt) = conn.prepare(&sql) {
-                let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-                let mut out = Vec::new();
-                while let Ok(Some(r)) = rows.next() {
-                    let start: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
-                    let end: String = r.get::<_, Option<String>>(1).unwrap_or(None).unwrap_or_default();
-                    let label: String = r.get::<_, Option<String>>(2).unwrap_or(None).unwrap_or_default();
-                    let reason: String = r.get::<_, Option<String>>(3).unwrap_or(None).unwrap_or_default();
-                    out.push(DistractionLog { start, end, label, reason });
-                }
+            if let Some(out) = read_distractions(&conn)? {
                 return Ok(out);
             }
-            else { /* prepare failed for distraction SQL */ }
         }
     }
     Ok(vec![DistractionLog { start: "11:30".to_string(), end: "11:45".to_string(), label: "Phone call".to_string(), reason: "Family call".to_string() }])
 }
+
+/// Distractions recorded in the daily DB for `date` (no demo fallback; empty if the DB is missing).
+pub fn get_distractions_for_date(workspace_dir: Option<&str>, date: NaiveDate) -> Result<Vec<DistractionLog>, String> {
+    match utility::daily_db_path(workspace_dir, date) {
+        Some(path) => {
+            let conn = Connection::open(path).map_err(|e| e.to_string())?;
+            Ok(read_distractions(&conn)?.unwrap_or_default())
+        }
+        None => Ok(vec![]),
+    }
+}
+
+/// Read the distraction table of an open daily DB, tolerating missing columns.
+/// Returns `None` if the table cannot be queried.
+fn read_distractions(conn: &Connection) -> Result<Option<Vec<DistractionLog>>, String> {
+    let cols = utility::table_columns(conn, "distraction");
+
+    let start_expr = if cols.contains("start_time") { "start_time AS start_time".to_string() } else { "'' AS start_time".to_string() };
+    let end_expr = if cols.contains("end_time") { "end_time AS end_time".to_string() } else { "'' AS end_time".to_string() };
+    let label_expr = if cols.contains("label") { "label AS label".to_string() } else { "'' AS label".to_string() };
+    let reason_expr = if cols.contains("reason") { "reason AS reason".to_string() } else { "'' AS reason".to_string() };
+
+    let sql = format!("SELECT {start}, {end}, {label}, {reason} FROM distraction ORDER BY start_time DESC", start = start_expr, end = end_expr, label = label_expr, reason = reason_expr);
+    if let Ok(mut stmt) = conn.prepare(&sql) {
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+        let mut out = Vec::new();
+        while let Ok(Some(r)) = rows.next() {
+            let start: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
+            let end: String = r.get::<_, Option<String>>(1).unwrap_or(None).unwrap_or_default();
+            let label: String = r.get::<_, Option<String>>(2).unwrap_or(None).unwrap_or_default();
+            let reason: String = r.get::<_, Option<String>>(3).unwrap_or(None).unwrap_or_default();
+            out.push(DistractionLog { start, end, label, reason });
+        }
+        return Ok(Some(out));
+    }
+    Ok(None)
+}
+/// Add the `start_time`/`end_time` interval columns to a daily DB created before they existed.
+pub fn ensure_interval_columns(conn: &Connection) -> Result<(), String> {
+    let cols = utility::table_columns(conn, "distraction");
+    if cols.is_empty() { return Ok(()); }
+    for col in ["start_time", "end_time"] {
+        if !cols.contains(col) {
+            conn.execute(&format!("ALTER TABLE distraction ADD COLUMN {} TEXT", col), []).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_at(at: Option<String>, day: NaiveDate) -> Result<NaiveDateTime, String> {
+    match at {
+        Some(s) => local_journal::parse_timestamp(&s, day).ok_or_else(|| format!("Invalid time '{}'", s)),
+        None => Ok(Local::now().naive_local()),
+    }
+}
+
+fn open_day(workspace_dir: Option<&str>, date: Option<String>) -> Result<(NaiveDate, Connection), String> {
+    let day = match date {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))?,
+        None => Local::now().date_naive(),
+    };
+    let conn = Connection::open(utility::ensure_daily_db(workspace_dir, day)?).map_err(|e| e.to_string())?;
+    Ok((day, conn))
+}
+
+/// Start timing a distraction in the daily DB of `date` (default today) at `at` (default now).
+/// Without a `session_id` it is attached to the session still running at that moment, if any.
+#[tauri::command]
+pub fn start_distraction(workspace_dir: Option<String>, date: Option<String>, session_id: Option<i64>, reason: String, at: Option<String>) -> Result<i64, String> {
+    let (day, conn) = open_day(workspace_dir.as_deref(), date)?;
+    let at = parse_at(at, day)?;
+    let session_id = match session_id {
+        Some(id) => {
+            let n: i64 = conn.query_row("SELECT COUNT(*) FROM session WHERE id = ?", params![id], |r| r.get(0)).map_err(|e| e.to_string())?;
+            if n == 0 { return Err(format!("session {} not found on {}", id, day)); }
+            Some(id)
+        }
+        None => conn.query_row("SELECT id FROM session WHERE end_time IS NULL ORDER BY start_time DESC LIMIT 1", [], |r| r.get(0))
+            .optional().map_err(|e| e.to_string())?,
+    };
+    let open: Option<i64> = conn.query_row("SELECT id FROM distraction WHERE start_time IS NOT NULL AND end_time IS NULL LIMIT 1", [], |r| r.get(0))
+        .optional().map_err(|e| e.to_string())?;
+    if let Some(id) = open { return Err(format!("distraction {} is still running", id)); }
+    conn.execute("INSERT INTO distraction (session_id, reason, resolved, start_time) VALUES (?, ?, 0, ?)",
+        params![session_id, reason.trim(), at.format("%Y-%m-%dT%H:%M:%S").to_string()]).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Stop a running distraction (default: the one still open) at `at` (default now) and mark it
+/// resolved. Returns its length in minutes.
+#[tauri::command]
+pub fn end_distraction(workspace_dir: Option<String>, date: Option<String>, distraction_id: Option<i64>, at: Option<String>) -> Result<i64, String> {
+    let (day, conn) = open_day(workspace_dir.as_deref(), date)?;
+    let at = parse_at(at, day)?;
+    let row: Option<(i64, Option<String>, Option<String>)> = match distraction_id {
+        Some(id) => conn.query_row("SELECT id, start_time, end_time FROM distraction WHERE id = ?", params![id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))),
+        None => conn.query_row("SELECT id, start_time, end_time FROM distraction WHERE start_time IS NOT NULL AND end_time IS NULL ORDER BY start_time DESC LIMIT 1", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))),
+    }.optional().map_err(|e| e.to_string())?;
+    let (id, start, end) = row.ok_or_else(|| "No running distraction".to_string())?;
+    if end.is_some() { return Err(format!("distraction {} has already ended", id)); }
+    let start = start.as_deref().and_then(|s| local_journal::parse_timestamp(s, day)).ok_or_else(|| format!("distraction {} has no start time", id))?;
+    if at < start { return Err("A distraction cannot end before it starts".to_string()); }
+    conn.execute("UPDATE distraction SET end_time = ?, resolved = 1 WHERE id = ?", params![at.format("%Y-%m-%dT%H:%M:%S").to_string(), id]).map_err(|e| e.to_string())?;
+    Ok((at - start).num_minutes())
+}
 /// Distractions module: Distraction logic and CRUD.
 
 pub mod distractions {
@@ -0,0 +1,324 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::Connection;
+use chrono::{Duration, Local, NaiveDate};
+use crate::backend::analytics;
+
+// ---------- metric forecasting ----------
+//
+// Forecasts any `get_metric_trend` metric from its daily history. Days without data inside the
+// history are filled with the average of the same weekday (the overall average if that weekday
+// never has data), so the weekly pattern survives gaps. Three methods:
+//   holt_winters   - additive Holt-Winters with a 7-day season; smoothing parameters are picked
+//                    by one-step squared error on a small grid (needs two full weeks)
+//   seasonal_naive - the value of the same weekday last week plus the average daily drift
+//                    (needs eight days)
+//   linear         - least-squares line, for short histories (needs two days)
+// "auto" backtests every method the history allows and keeps the one with the lowest MAE.
+// Prediction intervals come from the in-sample one-step errors, widened with the horizon. The
+// forecast starts the day after `today`, which is the last day of the training data.
+
+pub const SEASON: usize = 7;
+pub const METHODS: [&str; 3] = ["holt_winters", "seasonal_naive", "linear"];
+const BACKTEST_FOLDS: usize = 3;
+const ALPHAS: [f64; 5] = [0.1, 0.2, 0.3, 0.5, 0.7];
+const BETAS: [f64; 4] = [0.01, 0.05, 0.1, 0.2];
+const GAMMAS: [f64; 4] = [0.05, 0.1, 0.2, 0.3];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForecastOptions {
+    /// "auto" | "holt_winters" | "seasonal_naive" | "linear"
+    pub method: String,
+    /// Days of history to train on, ending today.
+    pub history_days: i64,
+    /// Coverage of the prediction interval, e.g. 0.8 or 0.95.
+    pub level: f64,
+}
+
+impl Default for ForecastOptions {
+    fn default() -> Self {
+        ForecastOptions { method: "auto".to_string(), history_days: 84, level: 0.8 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForecastPoint {
+    pub date: String,
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BacktestMetrics {
+    pub folds: i64,
+    pub horizon: i64,
+    pub mae: f64,
+    pub rmse: f64,
+    /// Over actual values that are not zero; `None` if there were none.
+    pub mape: Option<f64>,
+    /// MAE of repeating the last observed value, for comparison.
+    pub naive_mae: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricForecast {
+    pub metric: String,
+    pub method: String,
+    pub level: f64,
+    /// Days of history used (from the first day with data).
+    pub history_days: i64,
+    pub points: Vec<ForecastPoint>,
+    pub backtest: Option<BacktestMetrics>,
+}
+
+/// Point forecasts plus the standard deviation of the forecast error for each horizon step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fit {
+    pub values: Vec<f64>,
+    pub std_errors: Vec<f64>,
+}
+
+/// Smallest history each method can fit.
+pub fn min_history(method: &str) -> usize {
+    match method {
+        "holt_winters" => 2 * SEASON,
+        "seasonal_naive" => SEASON + 1,
+        _ => 2,
+    }
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    if xs.is_empty() { 0.0 } else { xs.iter().sum::<f64>() / xs.len() as f64 }
+}
+
+fn rms(errors: &[f64]) -> f64 {
+    if errors.is_empty() { 0.0 } else { (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt() }
+}
+
+/// Standard normal quantile (Acklam's rational approximation).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2, 1.38357751867269e2, -3.066479806614716e1, 2.506628277459239];
+    const B: [f64; 5] = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2, 6.680131188771972e1, -1.328068155288572e1];
+    const C: [f64; 6] = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838, -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    let p = p.clamp(1e-9, 1.0 - 1e-9);
+    let tail = |q: f64| (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0);
+    if p < 0.02425 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.02425 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+struct HoltWinters {
+    level: f64,
+    trend: f64,
+    season: Vec<f64>,
+    sse: f64,
+    errors: Vec<f64>,
+}
+
+fn run_holt_winters(y: &[f64], alpha: f64, beta: f64, gamma: f64) -> HoltWinters {
+    let first = mean(&y[..SEASON]);
+    let mut level = first;
+    let mut trend = (mean(&y[SEASON..2 * SEASON]) - first) / SEASON as f64;
+    let mut season: Vec<f64> = y[..SEASON].iter().map(|v| v - first).collect();
+    let mut errors = Vec::new();
+    for (t, &obs) in y.iter().enumerate().skip(SEASON) {
+        let s = season[t % SEASON];
+        let err = obs - (level + trend + s);
+        errors.push(err);
+        let prev_level = level;
+        level = alpha * (obs - s) + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - prev_level) + (1.0 - beta) * trend;
+        season[t % SEASON] = gamma * (obs - level) + (1.0 - gamma) * s;
+    }
+    HoltWinters { level, trend, season, sse: errors.iter().map(|e| e * e).sum(), errors }
+}
+
+fn holt_winters(y: &[f64], horizon: usize) -> Fit {
+    let mut best: Option<(f64, f64, f64, HoltWinters)> = None;
+    for &a in &ALPHAS {
+        for &b in &BETAS {
+            for &g in &GAMMAS {
+                let hw = run_holt_winters(y, a, b, g);
+                if best.as_ref().is_none_or(|(_, _, _, cur)| hw.sse < cur.sse) { best = Some((a, b, g, hw)); }
+            }
+        }
+    }
+    let (alpha, beta, gamma, hw) = match best { Some(b) => b, None => return Fit { values: vec![], std_errors: vec![] } };
+    let sigma = rms(&hw.errors);
+    let n = y.len();
+    let mut values = Vec::new();
+    let mut std_errors = Vec::new();
+    let mut var_mult: f64 = 1.0;
+    for h in 1..=horizon {
+        values.push(hw.level + h as f64 * hw.trend + hw.season[(n + h - 1) % SEASON]);
+        std_errors.push(sigma * var_mult.sqrt());
+        // Error variance grows with each step of smoothed level, trend and season
+        let seasonal = if h % SEASON == 0 { gamma } else { 0.0 };
+        var_mult += (alpha * (1.0 + h as f64 * beta) + seasonal).powi(2);
+    }
+    Fit { values, std_errors }
+}
+
+fn seasonal_naive(y: &[f64], horizon: usize) -> Fit {
+    let n = y.len();
+    // Average change per day between the first and last week of the history
+    let drift = (mean(&y[n - SEASON..]) - mean(&y[..SEASON])) / (n - SEASON) as f64;
+    let errors: Vec<f64> = (SEASON..n).map(|t| y[t] - (y[t - SEASON] + SEASON as f64 * drift)).collect();
+    let sigma = rms(&errors);
+    let mut values = Vec::new();
+    let mut std_errors = Vec::new();
+    for h in 1..=horizon {
+        let weeks_back = (h - 1) / SEASON + 1;
+        let base = y[n + h - 1 - weeks_back * SEASON];
+        values.push(base + (weeks_back * SEASON) as f64 * drift);
+        std_errors.push(sigma * (weeks_back as f64).sqrt());
+    }
+    Fit { values, std_errors }
+}
+
+fn linear(y: &[f64], horizon: usize) -> Fit {
+    let n = y.len() as f64;
+    let xm = (n - 1.0) / 2.0;
+    let ym = mean(y);
+    let sxx: f64 = (0..y.len()).map(|i| (i as f64 - xm).powi(2)).sum();
+    let slope = if sxx > 0.0 { y.iter().enumerate().map(|(i, v)| (i as f64 - xm) * (v - ym)).sum::<f64>() / sxx } else { 0.0 };
+    let intercept = ym - slope * xm;
+    let residuals: Vec<f64> = y.iter().enumerate().map(|(i, v)| v - (intercept + slope * i as f64)).collect();
+    let dof = (y.len() as f64 - 2.0).max(1.0);
+    let sigma = (residuals.iter().map(|r| r * r).sum::<f64>() / dof).sqrt();
+    let mut values = Vec::new();
+    let mut std_errors = Vec::new();
+    for h in 1..=horizon {
+        let x = n - 1.0 + h as f64;
+        values.push(intercept + slope * x);
+        let spread = if sxx > 0.0 { (x - xm).powi(2) / sxx } else { 0.0 };
+        std_errors.push(sigma * (1.0 + 1.0 / n + spread).sqrt());
+    }
+    Fit { values, std_errors }
+}
+
+/// Fit `method` to `y` and forecast `horizon` steps past its end.
+pub fn fit(method: &str, y: &[f64], horizon: usize) -> Result<Fit, String> {
+    if y.len() < min_history(method) {
+        return Err(format!("{} needs at least {} days of history", method, min_history(method)));
+    }
+    match method {
+        "holt_winters" => Ok(holt_winters(y, horizon)),
+        "seasonal_naive" => Ok(seasonal_naive(y, horizon)),
+        "linear" => Ok(linear(y, horizon)),
+        other => Err(format!("Unsupported forecast method '{}'", other)),
+    }
+}
+
+/// Rolling-origin backtest: up to three folds, each holding out the `horizon` days after its
+/// origin, the last fold ending at the end of `y`.
+pub fn backtest(method: &str, y: &[f64], horizon: usize) -> Option<BacktestMetrics> {
+    let horizon = horizon.max(1);
+    let mut errors = Vec::new();
+    let mut naive_errors = Vec::new();
+    let mut pct = Vec::new();
+    let mut folds = 0;
+    for k in 1..=BACKTEST_FOLDS {
+        let cut = match y.len().checked_sub(k * horizon) { Some(c) if c >= min_history(method) => c, _ => break };
+        let f = fit(method, &y[..cut], horizon).ok()?;
+        for (i, actual) in y[cut..cut + horizon].iter().enumerate() {
+            errors.push(actual - f.values[i]);
+            naive_errors.push(actual - y[cut - 1]);
+            if *actual != 0.0 { pct.push(((actual - f.values[i]) / actual).abs()); }
+        }
+        folds += 1;
+    }
+    if folds == 0 { return None; }
+    let round = |x: f64| (x * 1000.0).round() / 1000.0;
+    Some(BacktestMetrics {
+        folds,
+        horizon: horizon as i64,
+        mae: round(mean(&errors.iter().map(|e| e.abs()).collect::<Vec<_>>())),
+        rmse: round(rms(&errors)),
+        mape: if pct.is_empty() { None } else { Some(round(100.0 * mean(&pct))) },
+        naive_mae: round(mean(&naive_errors.iter().map(|e| e.abs()).collect::<Vec<_>>())),
+    })
+}
+
+/// Valid range of a metric's daily value. A streak is a day count without an upper limit.
+fn bounds(metric: &str) -> (f64, f64) {
+    match metric {
+        "punctuality" => (0.0, 1.0),
+        "streaks" => (0.0, f64::INFINITY),
+        _ => (0.0, 100.0),
+    }
+}
+
+/// Daily history from the first day with data in the last `history_days` through `today`, gaps
+/// filled with the same weekday's average.
+pub fn daily_history(conn: &Connection, user_id: i64, metric: &str, history_days: i64, today: NaiveDate) -> Result<Vec<f64>, String> {
+    let from = today - Duration::days(history_days.max(1) - 1);
+    let values = analytics::daily_values(conn, user_id, metric, from, today)?;
+    let first = match values.keys().min() { Some(d) => *d, None => return Ok(vec![]) };
+    let days: Vec<NaiveDate> = (0..=(today - first).num_days()).map(|i| first + Duration::days(i)).collect();
+    let overall = mean(&values.values().copied().collect::<Vec<_>>());
+    let mut by_weekday = [(0.0, 0); SEASON];
+    for (d, v) in &values {
+        let w = (*d - first).num_days() as usize % SEASON;
+        by_weekday[w] = (by_weekday[w].0 + v, by_weekday[w].1 + 1);
+    }
+    Ok(days.iter().enumerate().map(|(i, d)| match values.get(d) {
+        Some(v) => *v,
+        None => {
+            let (sum, n) = by_weekday[i % SEASON];
+            if n > 0 { sum / n as f64 } else { overall }
+        }
+    }).collect())
+}
+
+/// Forecast `metric` for the `days_ahead` days after `today`.
+pub fn forecast_metric(conn: &Connection, user_id: i64, metric: &str, days_ahead: i64, today: NaiveDate, options: &ForecastOptions) -> Result<MetricForecast, String> {
+    if !analytics::METRICS.contains(&metric) { return Err("Unsupported metric".to_string()); }
+    if !(1..=90).contains(&days_ahead) { return Err("days_ahead must be between 1 and 90".to_string()); }
+    if !(options.level > 0.0 && options.level < 1.0) { return Err("level must be between 0 and 1".to_string()); }
+    let y = daily_history(conn, user_id, metric, options.history_days, today)?;
+    if y.len() < min_history("linear") { return Err("Not enough data for forecast".to_string()); }
+    let horizon = days_ahead as usize;
+    let bt_horizon = horizon.min(SEASON);
+
+    let (method, backtest) = if options.method == "auto" {
+        let candidates: Vec<&str> = METHODS.iter().copied().filter(|m| y.len() >= min_history(m)).collect();
+        let scored: Vec<(&str, Option<BacktestMetrics>)> = candidates.iter().map(|m| (*m, backtest(m, &y, bt_horizon))).collect();
+        // Prefer the lowest backtest error; without any backtest, the richest method that fits
+        match scored.iter().filter(|(_, b)| b.is_some()).min_by(|a, b| a.1.as_ref().map(|x| x.mae).unwrap_or(f64::MAX).total_cmp(&b.1.as_ref().map(|x| x.mae).unwrap_or(f64::MAX))) {
+            Some((m, b)) => (m.to_string(), b.clone()),
+            None => (candidates[0].to_string(), None),
+        }
+    } else {
+        if !METHODS.contains(&options.method.as_str()) { return Err(format!("Unsupported forecast method '{}'", options.method)); }
+        (options.method.clone(), backtest(&options.method, &y, bt_horizon))
+    };
+
+    let f = fit(&method, &y, horizon)?;
+    let z = normal_quantile(0.5 + options.level / 2.0);
+    let (lo, hi) = bounds(metric);
+    let round = |x: f64| (x * 100.0).round() / 100.0;
+    let points = f.values.iter().zip(&f.std_errors).enumerate().map(|(i, (v, se))| ForecastPoint {
+        date: (today + Duration::days(i as i64 + 1)).to_string(),
+        value: round(v.clamp(lo, hi)),
+        lower: round((v - z * se).clamp(lo, hi)),
+        upper: round((v + z * se).clamp(lo, hi)),
+    }).collect();
+    Ok(MetricForecast { metric: metric.to_string(), method, level: options.level, history_days: y.len() as i64, points, backtest })
+}
+
+/// Forecast with prediction intervals and backtest metrics for any `get_metric_trend` metric.
+#[tauri::command]
+pub fn get_metric_forecast(db_path: String, user_id: i64, metric: String, days_ahead: i64, options: Option<ForecastOptions>) -> Result<MetricForecast, String> {
+    if user_id <= 0 { return Err("Invalid user_id".to_string()); }
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    forecast_metric(&conn, user_id, &metric, days_ahead, Local::now().date_naive(), &options.unwrap_or_default())
+}
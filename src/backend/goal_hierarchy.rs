@@ -0,0 +1,266 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use crate::backend::goals::{self, GoalItem, TaskItem};
+use crate::backend::goal_time::{self, AttributedSession};
+
+// ---------- goal hierarchy ----------
+//
+// goal -> sub-goals / milestones -> tasks, all in the workspace goals store. Progress is computed,
+// never stored: a task is 0 or 100, a milestone averages its tasks, and a goal averages its
+// sub-goals, milestones and loose tasks. Cancelled children are left out of every average, and a
+// completed item is always 100. Focus time is the net session time attributed to the goal (see
+// `goal_time`); when a goal has `target_minutes`, that time counts as one more child.
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Milestone {
+    pub id: i64,
+    pub goal_id: i64,
+    pub title: String,
+    pub due_date: Option<String>,
+    /// Same statuses as goals.
+    pub status: String,
+    pub position: i64,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilestoneNode {
+    pub milestone: Milestone,
+    pub progress: f64,
+    pub tasks: Vec<TaskItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalNode {
+    pub goal: GoalItem,
+    /// 0-100, rolled up from children.
+    pub progress: f64,
+    /// Net focus minutes attributed to this goal itself.
+    pub own_minutes: i64,
+    /// `own_minutes` plus everything invested in sub-goals.
+    pub time_invested_minutes: i64,
+    /// Day of the latest session attributed to this goal or a sub-goal.
+    pub last_session_day: Option<String>,
+    /// RFIDs of the session cards linked to this goal.
+    pub cards: Vec<String>,
+    pub milestones: Vec<MilestoneNode>,
+    /// Tasks directly under the goal, outside any milestone.
+    pub tasks: Vec<TaskItem>,
+    pub children: Vec<GoalNode>,
+}
+
+const MILESTONE_COLUMNS: &str = "id, goal_id, title, due_date, status, position, created_at, updated_at, completed_at";
+
+fn milestone_from_row(r: &rusqlite::Row) -> rusqlite::Result<Milestone> {
+    Ok(Milestone { id: r.get(0)?, goal_id: r.get(1)?, title: r.get(2)?, due_date: r.get(3)?, status: r.get(4)?, position: r.get(5)?, created_at: r.get(6)?, updated_at: r.get(7)?, completed_at: r.get(8)? })
+}
+
+pub fn get_milestone(conn: &Connection, id: i64) -> Result<Milestone, String> {
+    conn.query_row(&format!("SELECT {} FROM milestones WHERE id = ?", MILESTONE_COLUMNS), params![id], milestone_from_row)
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("milestone {} not found", id))
+}
+
+/// Milestones of one goal (or all), in their position order.
+pub fn list_milestone_rows(conn: &Connection, goal_id: Option<i64>) -> Result<Vec<Milestone>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM milestones WHERE ?1 IS NULL OR goal_id = ?1 ORDER BY goal_id, position, id", MILESTONE_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![goal_id], milestone_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Append a milestone to the end of a goal's list.
+pub fn insert_milestone(conn: &Connection, goal_id: i64, title: &str, due_date: Option<&str>) -> Result<Milestone, String> {
+    if title.trim().is_empty() { return Err("Title is required".to_string()); }
+    goals::validate_deadline(due_date)?;
+    goals::require_row(conn, "goals", Some(goal_id))?;
+    conn.execute(
+        "INSERT INTO milestones (goal_id, title, due_date, position) VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(position), -1) + 1 FROM milestones WHERE goal_id = ?1))",
+        params![goal_id, title.trim(), goals::non_empty(due_date)],
+    ).map_err(|e| e.to_string())?;
+    get_milestone(conn, conn.last_insert_rowid())
+}
+
+/// Make `goal_id` a sub-goal of `parent_id` (`None` makes it top-level again).
+pub fn set_parent(conn: &Connection, goal_id: i64, parent_id: Option<i64>) -> Result<GoalItem, String> {
+    goals::get_goal(conn, goal_id)?;
+    if let Some(parent) = parent_id {
+        // Walk up from the new parent; meeting the goal itself would close a loop
+        let mut cursor = Some(parent);
+        let mut seen = HashSet::new();
+        while let Some(id) = cursor {
+            if id == goal_id { return Err("A goal cannot be nested under itself or one of its sub-goals".to_string()); }
+            if !seen.insert(id) { break; }
+            cursor = goals::get_goal(conn, id)?.parent_id;
+        }
+    }
+    conn.execute("UPDATE goals SET parent_id = ?, updated_at = datetime('now') WHERE id = ?", params![parent_id, goal_id]).map_err(|e| e.to_string())?;
+    goals::get_goal(conn, goal_id)
+}
+
+pub fn link_card(conn: &Connection, goal_id: i64, rfid: &str) -> Result<(), String> {
+    if rfid.trim().is_empty() { return Err("RFID is required".to_string()); }
+    goals::require_row(conn, "goals", Some(goal_id))?;
+    conn.execute("INSERT OR IGNORE INTO goal_cards (goal_id, rfid) VALUES (?, ?)", params![goal_id, rfid.trim()]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn unlink_card(conn: &Connection, goal_id: i64, rfid: &str) -> Result<(), String> {
+    let n = conn.execute("DELETE FROM goal_cards WHERE goal_id = ? AND rfid = ?", params![goal_id, rfid.trim()]).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("card {} is not linked to goal {}", rfid, goal_id)); }
+    Ok(())
+}
+
+/// goal id -> linked card RFIDs.
+pub fn card_links(conn: &Connection) -> Result<HashMap<i64, Vec<String>>, String> {
+    let mut stmt = conn.prepare("SELECT goal_id, rfid FROM goal_cards ORDER BY goal_id, rfid").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))).map_err(|e| e.to_string())?;
+    let mut out: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (goal_id, rfid) = row.map_err(|e| e.to_string())?;
+        out.entry(goal_id).or_default().push(rfid);
+    }
+    Ok(out)
+}
+
+fn round1(x: f64) -> f64 {
+    (x * 10.0).round() / 10.0
+}
+
+fn task_progress(t: &TaskItem) -> f64 {
+    if t.status == "completed" { 100.0 } else { 0.0 }
+}
+
+/// Average of the progress values, or `None` when there is nothing to average.
+fn average(values: &[f64]) -> Option<f64> {
+    if values.is_empty() { None } else { Some(values.iter().sum::<f64>() / values.len() as f64) }
+}
+
+struct TreeData<'a> {
+    goals: Vec<GoalItem>,
+    milestones: Vec<Milestone>,
+    tasks: Vec<TaskItem>,
+    cards: HashMap<i64, Vec<String>>,
+    sessions: &'a [AttributedSession],
+}
+
+fn build_node(data: &TreeData, goal: &GoalItem, path: &mut Vec<i64>) -> GoalNode {
+    path.push(goal.id);
+    let sub_goals: Vec<&GoalItem> = data.goals.iter().filter(|g| g.parent_id == Some(goal.id) && !path.contains(&g.id)).collect();
+    let children: Vec<GoalNode> = sub_goals.into_iter().map(|g| build_node(data, g, path)).collect();
+    path.pop();
+
+    let milestones: Vec<MilestoneNode> = data.milestones.iter().filter(|m| m.goal_id == goal.id).map(|m| {
+        let tasks: Vec<TaskItem> = data.tasks.iter().filter(|t| t.milestone_id == Some(m.id)).cloned().collect();
+        let counted: Vec<f64> = tasks.iter().filter(|t| t.status != "cancelled").map(task_progress).collect();
+        let progress = if m.status == "completed" { 100.0 } else { average(&counted).unwrap_or(0.0) };
+        MilestoneNode { milestone: m.clone(), progress: round1(progress), tasks }
+    }).collect();
+    let tasks: Vec<TaskItem> = data.tasks.iter().filter(|t| t.goal_id == Some(goal.id) && t.milestone_id.is_none()).cloned().collect();
+
+    let cards = data.cards.get(&goal.id).cloned().unwrap_or_default();
+    let own: Vec<&AttributedSession> = data.sessions.iter().filter(|s| s.goal_ids.contains(&goal.id)).collect();
+    let own_minutes: i64 = own.iter().map(|s| s.minutes_for(goal.id)).sum();
+    let time_invested_minutes = own_minutes + children.iter().map(|c| c.time_invested_minutes).sum::<i64>();
+    let last_session_day = own.iter().map(|s| s.day.format("%Y-%m-%d").to_string())
+        .chain(children.iter().filter_map(|c| c.last_session_day.clone()))
+        .max();
+
+    let mut parts: Vec<f64> = Vec::new();
+    parts.extend(children.iter().filter(|c| c.goal.status != "cancelled").map(|c| c.progress));
+    parts.extend(milestones.iter().filter(|m| m.milestone.status != "cancelled").map(|m| m.progress));
+    parts.extend(tasks.iter().filter(|t| t.status != "cancelled").map(task_progress));
+    if let Some(target) = goal.target_minutes.filter(|t| *t > 0) {
+        parts.push((time_invested_minutes as f64 / target as f64 * 100.0).min(100.0));
+    }
+    let progress = if goal.status == "completed" { 100.0 } else { average(&parts).unwrap_or(0.0) };
+
+    GoalNode { goal: goal.clone(), progress: round1(progress), own_minutes, time_invested_minutes, last_session_day, cards, milestones, tasks, children }
+}
+
+/// Drop finished (completed/cancelled) goals, milestones and tasks from a built tree. Progress was
+/// already rolled up with them included.
+fn prune_closed(node: &mut GoalNode) {
+    node.children.retain(|c| goals::is_active(&c.goal.status));
+    node.milestones.retain(|m| goals::is_active(&m.milestone.status));
+    node.tasks.retain(|t| goals::is_active(&t.status));
+    for m in node.milestones.iter_mut() { m.tasks.retain(|t| goals::is_active(&t.status)); }
+    for c in node.children.iter_mut() { prune_closed(c); }
+}
+
+/// The goal tree (or the subtree under `root_goal_id`) with progress and time invested.
+/// `sessions` is usually `goal_time::attributed_sessions` for the workspace.
+pub fn goal_tree(conn: &Connection, sessions: &[AttributedSession], root_goal_id: Option<i64>, include_closed: bool) -> Result<Vec<GoalNode>, String> {
+    let data = TreeData {
+        goals: goals::list_goal_rows(conn, None)?,
+        milestones: list_milestone_rows(conn, None)?,
+        tasks: goals::list_task_rows(conn, None, None)?,
+        cards: card_links(conn)?,
+        sessions,
+    };
+    let ids: HashSet<i64> = data.goals.iter().map(|g| g.id).collect();
+    let roots: Vec<&GoalItem> = match root_goal_id {
+        Some(id) => vec![data.goals.iter().find(|g| g.id == id).ok_or_else(|| format!("goal {} not found", id))?],
+        None => data.goals.iter().filter(|g| g.parent_id.is_none_or(|p| !ids.contains(&p))).collect(),
+    };
+    let mut nodes: Vec<GoalNode> = roots.into_iter()
+        .filter(|g| include_closed || root_goal_id.is_some() || goals::is_active(&g.status))
+        .map(|g| build_node(&data, g, &mut Vec::new()))
+        .collect();
+    if !include_closed {
+        for n in nodes.iter_mut() { prune_closed(n); }
+    }
+    Ok(nodes)
+}
+
+#[tauri::command]
+pub fn get_goal_tree(workspace_dir: Option<String>, root_goal_id: Option<i64>, include_closed: Option<bool>) -> Result<Vec<GoalNode>, String> {
+    let conn = match goals::open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    let sessions = goal_time::attributed_sessions(&conn, workspace_dir.as_deref())?;
+    goal_tree(&conn, &sessions, root_goal_id, include_closed.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn set_goal_parent(workspace_dir: Option<String>, goal_id: i64, parent_id: Option<i64>) -> Result<GoalItem, String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    set_parent(&conn, goal_id, parent_id)
+}
+
+#[tauri::command]
+pub fn create_milestone(workspace_dir: Option<String>, goal_id: i64, title: String, due_date: Option<String>) -> Result<Milestone, String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    insert_milestone(&conn, goal_id, &title, due_date.as_deref())
+}
+
+#[tauri::command]
+pub fn set_milestone_status(workspace_dir: Option<String>, milestone_id: i64, status: String) -> Result<Milestone, String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    goals::set_status(&conn, "milestones", milestone_id, &status)?;
+    get_milestone(&conn, milestone_id)
+}
+
+/// Delete a milestone; its tasks stay on the goal.
+#[tauri::command]
+pub fn delete_milestone(workspace_dir: Option<String>, milestone_id: i64) -> Result<(), String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    goals::delete_row(&conn, "milestones", milestone_id)
+}
+
+#[tauri::command]
+pub fn list_milestones(workspace_dir: Option<String>, goal_id: Option<i64>) -> Result<Vec<Milestone>, String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    list_milestone_rows(&conn, goal_id)
+}
+
+#[tauri::command]
+pub fn link_goal_card(workspace_dir: Option<String>, goal_id: i64, rfid: String) -> Result<(), String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    link_card(&conn, goal_id, &rfid)
+}
+
+#[tauri::command]
+pub fn unlink_goal_card(workspace_dir: Option<String>, goal_id: i64, rfid: String) -> Result<(), String> {
+    let conn = goals::open_store(workspace_dir.as_deref())?;
+    unlink_card(&conn, goal_id, &rfid)
+}
@@ -0,0 +1,219 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use crate::backend::goal_hierarchy::{self, GoalNode};
+use crate::backend::{goal_time, goals, utility};
+
+// ---------- goal prioritization ----------
+//
+// Every open goal gets a 0-100 score from four signals: how close the deadline is, how much work is
+// left compared with the focus time available before it, the user's own priority, and how long it
+// has gone without a linked session. Goals also get an escalation state:
+//   overdue  - the deadline has passed
+//   at_risk  - due within `upcoming_days` and either the remaining work does not fit into
+//              `daily_capacity_minutes` per day, or the goal has been neglected
+//   upcoming - due within `upcoming_days`
+// A background watcher emits `goal_escalation` whenever a goal enters a new state.
+
+pub const GOAL_ESCALATION_EVENT: &str = "goal_escalation";
+const CHECK_SECONDS: u64 = 15 * 60;
+
+const DEADLINE_WEIGHT: f64 = 0.40;
+const EFFORT_WEIGHT: f64 = 0.25;
+const PRIORITY_WEIGHT: f64 = 0.20;
+const NEGLECT_WEIGHT: f64 = 0.15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriorityConfig {
+    /// A goal with no linked session for this many days counts as neglected.
+    pub neglect_days: i64,
+    /// Deadlines this close make a goal "upcoming".
+    pub upcoming_days: i64,
+    /// Focus minutes per day the user can realistically put toward one goal.
+    pub daily_capacity_minutes: i64,
+    /// Effort assumed per open task when a goal has no `target_minutes`.
+    pub default_task_minutes: i64,
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        PriorityConfig { neglect_days: 7, upcoming_days: 7, daily_capacity_minutes: 120, default_task_minutes: 60 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoalPriority {
+    pub goal_id: i64,
+    pub title: String,
+    pub deadline: Option<String>,
+    /// Negative once overdue.
+    pub days_left: Option<i64>,
+    pub priority: i64,
+    pub progress: f64,
+    pub remaining_minutes: i64,
+    /// Days since the last linked session (or since the goal was created, if it never had one).
+    pub days_since_session: i64,
+    pub neglected: bool,
+    pub score: f64,
+    /// "upcoming" | "at_risk" | "overdue"
+    pub escalation: Option<String>,
+    pub reasons: Vec<String>,
+}
+
+fn parse_day(s: &str) -> Option<NaiveDate> {
+    s.get(..10).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+fn flatten(nodes: Vec<GoalNode>, out: &mut Vec<GoalNode>) {
+    for mut n in nodes {
+        let children = std::mem::take(&mut n.children);
+        out.push(n);
+        flatten(children, out);
+    }
+}
+
+fn score_node(node: &GoalNode, today: NaiveDate, cfg: &PriorityConfig) -> GoalPriority {
+    let goal = &node.goal;
+    let days_left = goal.deadline.as_deref().and_then(parse_day).map(|d| (d - today).num_days());
+    let open_tasks = node.tasks.iter().chain(node.milestones.iter().flat_map(|m| m.tasks.iter()))
+        .filter(|t| goals::is_active(&t.status)).count() as i64;
+    let remaining_minutes = match goal.target_minutes {
+        Some(target) => (target - node.time_invested_minutes).max(0),
+        None => open_tasks * cfg.default_task_minutes,
+    };
+    let last_active = node.last_session_day.as_deref().or(Some(goal.created_at.as_str())).and_then(parse_day).unwrap_or(today);
+    let days_since_session = (today - last_active).num_days().max(0);
+    let neglected = days_since_session >= cfg.neglect_days;
+
+    let mut reasons = Vec::new();
+    let deadline_score = match days_left {
+        Some(d) if d < 0 => { reasons.push(format!("overdue by {} day(s)", -d)); 1.0 }
+        Some(d) => { reasons.push(format!("due in {} day(s)", d)); 1.0 / (1.0 + d as f64 / 7.0) }
+        None => 0.1,
+    };
+    // Share of the days left that the remaining work would fill at the daily capacity
+    let pressure = match days_left {
+        Some(d) if cfg.daily_capacity_minutes > 0 => remaining_minutes as f64 / ((d.max(1) * cfg.daily_capacity_minutes) as f64),
+        _ => 0.0,
+    };
+    if remaining_minutes > 0 { reasons.push(format!("about {} min of work left", remaining_minutes)); }
+    if neglected { reasons.push(format!("no linked session in {} days", days_since_session)); }
+    if goal.priority >= 4 { reasons.push(format!("priority {}", goal.priority)); }
+    let effort_score = if days_left.is_some() { pressure.min(1.0) } else { (remaining_minutes as f64 / (cfg.daily_capacity_minutes.max(1) * 7) as f64).min(1.0) * 0.5 };
+    let priority_score = (goal.priority.clamp(1, 5) - 1) as f64 / 4.0;
+    let neglect_score = if cfg.neglect_days > 0 { (days_since_session as f64 / (2 * cfg.neglect_days) as f64).min(1.0) } else { 0.0 };
+    let score = 100.0 * (DEADLINE_WEIGHT * deadline_score + EFFORT_WEIGHT * effort_score + PRIORITY_WEIGHT * priority_score + NEGLECT_WEIGHT * neglect_score);
+
+    let escalation = match days_left {
+        Some(d) if d < 0 => Some("overdue"),
+        Some(d) if d <= cfg.upcoming_days && (pressure > 1.0 || neglected) => Some("at_risk"),
+        Some(d) if d <= cfg.upcoming_days => Some("upcoming"),
+        _ => None,
+    };
+
+    GoalPriority {
+        goal_id: goal.id,
+        title: goal.title.clone(),
+        deadline: goal.deadline.clone(),
+        days_left,
+        priority: goal.priority,
+        progress: node.progress,
+        remaining_minutes,
+        days_since_session,
+        neglected,
+        score: (score * 10.0).round() / 10.0,
+        escalation: escalation.map(str::to_string),
+        reasons,
+    }
+}
+
+/// Open and in-progress goals, highest score first (earlier deadline breaks ties).
+pub fn score_goals(conn: &Connection, workspace_dir: Option<&str>, today: NaiveDate, cfg: &PriorityConfig) -> Result<Vec<GoalPriority>, String> {
+    let sessions = goal_time::attributed_sessions(conn, workspace_dir)?;
+    score_goals_with(conn, &sessions, today, cfg)
+}
+
+/// `score_goals` over sessions the caller already attributed.
+pub fn score_goals_with(conn: &Connection, sessions: &[goal_time::AttributedSession], today: NaiveDate, cfg: &PriorityConfig) -> Result<Vec<GoalPriority>, String> {
+    let mut nodes = Vec::new();
+    flatten(goal_hierarchy::goal_tree(conn, sessions, None, true)?, &mut nodes);
+    let mut out: Vec<GoalPriority> = nodes.iter().filter(|n| goals::is_active(&n.goal.status)).map(|n| score_node(n, today, cfg)).collect();
+    out.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.days_left.unwrap_or(i64::MAX).cmp(&b.days_left.unwrap_or(i64::MAX))).then(a.goal_id.cmp(&b.goal_id)));
+    Ok(out)
+}
+
+/// Escalated goals whose state differs from the last one notified, recording the new states.
+/// `scored` must hold every active goal. Goals that calmed down or are no longer active (completed,
+/// cancelled, deleted) are forgotten, so escalating again notifies again.
+pub fn take_new_escalations(conn: &Connection, scored: &[GoalPriority], now: NaiveDateTime) -> Result<Vec<GoalPriority>, String> {
+    let mut stmt = conn.prepare("SELECT goal_id, state FROM goal_escalations").map_err(|e| e.to_string())?;
+    let known: HashMap<i64, String> = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?))).map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+    for goal_id in known.keys().filter(|id| !scored.iter().any(|p| p.goal_id == **id)) {
+        conn.execute("DELETE FROM goal_escalations WHERE goal_id = ?", params![goal_id]).map_err(|e| e.to_string())?;
+    }
+    let now = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let mut fresh = Vec::new();
+    for item in scored {
+        match &item.escalation {
+            Some(state) if known.get(&item.goal_id) != Some(state) => {
+                conn.execute("INSERT OR REPLACE INTO goal_escalations (goal_id, state, notified_at) VALUES (?, ?, ?)", params![item.goal_id, state, now]).map_err(|e| e.to_string())?;
+                fresh.push(item.clone());
+            }
+            Some(_) => {}
+            None if known.contains_key(&item.goal_id) => {
+                conn.execute("DELETE FROM goal_escalations WHERE goal_id = ?", params![item.goal_id]).map_err(|e| e.to_string())?;
+            }
+            None => {}
+        }
+    }
+    Ok(fresh)
+}
+
+#[tauri::command]
+pub fn get_goal_priorities(workspace_dir: Option<String>, config: Option<PriorityConfig>) -> Result<Vec<GoalPriority>, String> {
+    let conn = match goals::open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    score_goals(&conn, workspace_dir.as_deref(), Local::now().date_naive(), &config.unwrap_or_default())
+}
+
+struct WatcherConfig {
+    workspace_dir: Option<String>,
+    config: PriorityConfig,
+}
+
+static WATCHER: Lazy<Mutex<Option<WatcherConfig>>> = Lazy::new(|| Mutex::new(None));
+
+/// Start the background check that emits `goal_escalation`. Calling it again only switches the
+/// workspace and thresholds it uses.
+#[tauri::command]
+pub fn start_goal_escalation_watcher(app: AppHandle, workspace_dir: Option<String>, config: Option<PriorityConfig>) -> Result<(), String> {
+    let mut cfg = WATCHER.lock().map_err(|e| e.to_string())?;
+    let already_running = cfg.is_some();
+    *cfg = Some(WatcherConfig { workspace_dir, config: config.unwrap_or_default() });
+    if already_running { return Ok(()); }
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let current = WATCHER.lock().ok().and_then(|c| c.as_ref().map(|c| (c.workspace_dir.clone(), c.config.clone())));
+            if let Some((workspace_dir, config)) = current {
+                let escalated = tokio::task::spawn_blocking(move || {
+                    let conn = match goals::open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+                    let now = Local::now().naive_local();
+                    let scored = score_goals(&conn, workspace_dir.as_deref(), now.date(), &config)?;
+                    take_new_escalations(&conn, &scored, now)
+                }).await;
+                match escalated {
+                    Ok(Ok(items)) => for item in items { let _ = app.emit(GOAL_ESCALATION_EVENT, item); },
+                    Ok(Err(e)) => utility::log_error("goal_escalation_watcher", &e),
+                    Err(e) => utility::log_error("goal_escalation_watcher", &e.to_string()),
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_SECONDS)).await;
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use std::collections::{HashMap, HashSet};
+use crate::backend::goal_hierarchy;
+use crate::backend::{goals, local_journal, utility};
+
+// ---------- focus time per goal ----------
+//
+// A finished session counts toward goals in this order of preference:
+//   1. goals it was tagged with (`tag_session_goals`, at start or stop)
+//   2. goals its session card declares, via `"goal_ids": [..]` in the card's metadata_json, or
+//      links made with `goal_hierarchy::link_goal_card`
+// Only net focus time is attributed: distraction intervals (rows with start_time and end_time)
+// overlapping the session are cut out. A session serving several goals is split evenly so the
+// per-goal totals add up to the time actually spent.
+
+/// One finished session on a session card and the goals its net time goes to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributedSession {
+    pub day: NaiveDate,
+    pub session_id: i64,
+    pub rfid: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub gross_minutes: i64,
+    pub net_minutes: i64,
+    pub goal_ids: Vec<i64>,
+    /// True when the goals came from a session tag rather than the card.
+    pub tagged: bool,
+}
+
+impl AttributedSession {
+    /// This session's minutes for one of its goals: an even split, with the remainder going to the
+    /// first goal so the shares add up to `net_minutes`.
+    pub fn minutes_for(&self, goal_id: i64) -> i64 {
+        let n = self.goal_ids.len() as i64;
+        match self.goal_ids.iter().position(|g| *g == goal_id) {
+            Some(0) => self.net_minutes / n + self.net_minutes % n,
+            Some(_) => self.net_minutes / n,
+            None => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoalTime {
+    pub goal_id: i64,
+    pub title: String,
+    pub status: String,
+    pub sessions: i64,
+    pub net_minutes: i64,
+    pub hours: f64,
+    pub last_session_day: Option<String>,
+}
+
+/// Goal ids declared in a card's metadata_json (`goal_ids` array or a single `goal_id`).
+pub fn card_goal_ids(metadata_json: Option<&str>) -> Vec<i64> {
+    let value: serde_json::Value = match metadata_json.and_then(|m| serde_json::from_str(m).ok()) { Some(v) => v, None => return vec![] };
+    let mut ids: Vec<i64> = value.get("goal_ids").and_then(|v| v.as_array()).map(|a| a.iter().filter_map(|x| x.as_i64()).collect()).unwrap_or_default();
+    if let Some(id) = value.get("goal_id").and_then(|v| v.as_i64()) { ids.push(id); }
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Minutes of `[start, end)` not covered by any of `gaps`.
+pub fn net_minutes(start: NaiveDateTime, end: NaiveDateTime, gaps: &[(NaiveDateTime, NaiveDateTime)]) -> i64 {
+    net_seconds(start, end, gaps) / 60
+}
+
+/// Seconds of `[start, end)` not covered by any of `gaps`.
+pub fn net_seconds(start: NaiveDateTime, end: NaiveDateTime, gaps: &[(NaiveDateTime, NaiveDateTime)]) -> i64 {
+    let mut clipped: Vec<(NaiveDateTime, NaiveDateTime)> = gaps.iter()
+        .map(|&(s, e)| (s.max(start), e.min(end)))
+        .filter(|(s, e)| s < e)
+        .collect();
+    clipped.sort();
+    let mut lost = 0;
+    let mut covered_to = start;
+    for (s, e) in clipped {
+        let s = s.max(covered_to);
+        if e > s {
+            lost += (e - s).num_seconds();
+            covered_to = e;
+        }
+    }
+    ((end - start).num_seconds() - lost).max(0)
+}
+
+pub(crate) fn distraction_intervals(conn: &Connection, day: NaiveDate) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut stmt = match conn.prepare("SELECT start_time, end_time FROM distraction WHERE start_time IS NOT NULL AND end_time IS NOT NULL") {
+        Ok(s) => s,
+        // Older daily DBs have no distraction intervals
+        Err(_) => return vec![],
+    };
+    let rows = match stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))) { Ok(r) => r, Err(_) => return vec![] };
+    rows.flatten()
+        .filter_map(|(s, e)| Some((local_journal::parse_timestamp(&s, day)?, local_journal::parse_timestamp(&e, day)?)))
+        .collect()
+}
+
+/// (day, session id) -> tagged goal ids.
+fn session_tags(store: &Connection) -> Result<HashMap<(String, i64), Vec<i64>>, String> {
+    let mut stmt = store.prepare("SELECT day, session_id, goal_id FROM session_goals ORDER BY goal_id").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?, r.get::<_, i64>(2)?))).map_err(|e| e.to_string())?;
+    let mut out: HashMap<(String, i64), Vec<i64>> = HashMap::new();
+    for row in rows {
+        let (day, session_id, goal_id) = row.map_err(|e| e.to_string())?;
+        out.entry((day, session_id)).or_default().push(goal_id);
+    }
+    Ok(out)
+}
+
+/// Every finished session on a session card across the workspace's daily DBs, with its net
+/// minutes and goals. Goal ids that no longer exist in the store are dropped.
+pub fn attributed_sessions(store: &Connection, workspace_dir: Option<&str>) -> Result<Vec<AttributedSession>, String> {
+    let tags = session_tags(store)?;
+    let mut linked: HashMap<String, Vec<i64>> = HashMap::new();
+    for (goal_id, rfids) in goal_hierarchy::card_links(store)? {
+        for rfid in rfids { linked.entry(rfid).or_default().push(goal_id); }
+    }
+    let mut stmt = store.prepare("SELECT id FROM goals").map_err(|e| e.to_string())?;
+    let known: HashSet<i64> = stmt.query_map([], |r| r.get(0)).map_err(|e| e.to_string())?.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for (day, path) in utility::list_daily_dbs(workspace_dir) {
+        let conn = match Connection::open(&path) { Ok(c) => c, Err(_) => continue };
+        let gaps = distraction_intervals(&conn, day);
+        let mut stmt = match conn.prepare("SELECT s.id, c.rfid, c.metadata_json, s.start_time, s.end_time FROM session s JOIN card c ON c.id = s.card_id WHERE c.type = 'session' AND s.end_time IS NOT NULL ORDER BY s.start_time") {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        let rows = match stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?, r.get::<_, String>(3)?, r.get::<_, String>(4)?))) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        let day_key = day.format("%Y-%m-%d").to_string();
+        for (session_id, rfid, metadata, start, end) in rows.flatten() {
+            let (start, end) = match (local_journal::parse_timestamp(&start, day), local_journal::parse_timestamp(&end, day)) {
+                (Some(s), Some(e)) if e > s => (s, e),
+                _ => continue,
+            };
+            let (mut goal_ids, tagged) = match tags.get(&(day_key.clone(), session_id)) {
+                Some(ids) => (ids.clone(), true),
+                None => {
+                    let mut ids = card_goal_ids(metadata.as_deref());
+                    ids.extend(linked.get(&rfid).cloned().unwrap_or_default());
+                    (ids, false)
+                }
+            };
+            goal_ids.retain(|id| known.contains(id));
+            goal_ids.sort_unstable();
+            goal_ids.dedup();
+            out.push(AttributedSession {
+                day,
+                session_id,
+                rfid,
+                start,
+                end,
+                gross_minutes: (end - start).num_minutes(),
+                net_minutes: net_minutes(start, end, &gaps),
+                goal_ids,
+                tagged,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Net focus time per goal from sessions between `from` and `to` (inclusive, either open-ended),
+/// most time first. Goals without any time are left out.
+pub fn goal_time(store: &Connection, sessions: &[AttributedSession], from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<GoalTime>, String> {
+    let mut out: Vec<GoalTime> = Vec::new();
+    for g in goals::list_goal_rows(store, None)? {
+        let mine: Vec<&AttributedSession> = sessions.iter()
+            .filter(|s| s.goal_ids.contains(&g.id) && from.is_none_or(|f| s.day >= f) && to.is_none_or(|t| s.day <= t))
+            .collect();
+        if mine.is_empty() { continue; }
+        let net: i64 = mine.iter().map(|s| s.minutes_for(g.id)).sum();
+        out.push(GoalTime {
+            goal_id: g.id,
+            title: g.title,
+            status: g.status,
+            sessions: mine.len() as i64,
+            net_minutes: net,
+            hours: (net as f64 / 60.0 * 10.0).round() / 10.0,
+            last_session_day: mine.iter().map(|s| s.day).max().map(|d| d.format("%Y-%m-%d").to_string()),
+        });
+    }
+    out.sort_by(|a, b| b.net_minutes.cmp(&a.net_minutes).then(a.goal_id.cmp(&b.goal_id)));
+    Ok(out)
+}
+
+/// Hours invested per goal, optionally limited to a date range (YYYY-MM-DD).
+#[tauri::command]
+pub fn get_goal_time(workspace_dir: Option<String>, from: Option<String>, to: Option<String>) -> Result<Vec<GoalTime>, String> {
+    let parse = |s: Option<String>| -> Result<Option<NaiveDate>, String> {
+        s.map(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))).transpose()
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    let store = match goals::open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    let sessions = attributed_sessions(&store, workspace_dir.as_deref())?;
+    goal_time(&store, &sessions, from, to)
+}
+
+/// Tag a session (by its id in the daily DB of `date`, default today) with the goals it served,
+/// replacing earlier tags. An empty list removes the tags so the card's goals apply again.
+#[tauri::command]
+pub fn tag_session_goals(workspace_dir: Option<String>, date: Option<String>, session_id: i64, goal_ids: Vec<i64>) -> Result<(), String> {
+    let day = match date {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))?,
+        None => Local::now().date_naive(),
+    };
+    let path = utility::daily_db_path(workspace_dir.as_deref(), day).ok_or_else(|| format!("No daily DB for {}", day))?;
+    let daily = Connection::open(path).map_err(|e| e.to_string())?;
+    let n: i64 = daily.query_row("SELECT COUNT(*) FROM session WHERE id = ?", params![session_id], |r| r.get(0)).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("session {} not found on {}", session_id, day)); }
+
+    let store = goals::open_store(workspace_dir.as_deref())?;
+    for id in &goal_ids { goals::require_row(&store, "goals", Some(*id))?; }
+    let day = day.format("%Y-%m-%d").to_string();
+    store.execute("DELETE FROM session_goals WHERE day = ? AND session_id = ?", params![day, session_id]).map_err(|e| e.to_string())?;
+    for id in goal_ids {
+        store.execute("INSERT OR IGNORE INTO session_goals (day, session_id, goal_id) VALUES (?, ?, ?)", params![day, session_id, id]).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
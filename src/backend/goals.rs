@@ -1,6 +1,9 @@
 use serde::{Serialize, Deserialize};
-use rusqlite::Connection;
-use crate::backend::utility;
+use rusqlite::{Connection, params, OptionalExtension};
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use crate::backend::{goal_hierarchy, local_journal, search, utility};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Goal {
@@ -23,135 +26,667 @@ pub struct Reminder {
     pub text: String,
 }
 
-#[tauri::command]
-pub fn get_pending_goals(workspace_dir: Option<String>) -> Result<Vec<Goal>, String> {
-    if let Some(path) = utility::find_daily_db(workspace_dir, None) {
-        if let Ok(conn) = Connection::open(path) {
-            // Inspect table columns to avoid referencing missing columns (which causes prepare errors)
-            let mut col_stmt = conn.prepare("PRAGMA table_info(goal)").ok();
-            let mut cols = std::collections::HashSet::new();
-            if let Some(mut s) = col_stmt {
-                if let Ok(mut rows) = s.query([]) {
-                    while let Ok(Some(r)) = rows.next() {
-                        if let Ok(name) = r.get::<_, String>(1) {
-                            cols.insert(name);
-                        }
-                    }
-                }
-            }
+// ---------- persistent store ----------
+//
+// Goals, tasks and reminders live in one workspace-level SQLite file next to the daily DBs, so
+// they outlive the day they were created on. Rows still found in older daily DBs (`goal`, `task`,
+// `reminder` tables) are imported once per day file; today's file is re-read on every access
+// because the planner and the .ics importer still write reminders there. Every imported row's
+// legacy key is recorded in `legacy_keys`, so a row the user deleted is not imported again. Goals nest (parent_id)
+// and carry milestones; see `goal_hierarchy` for the tree and progress roll-up.
+
+/// Workspace store holding goals, tasks, reminders and their links to cards and sessions.
+pub const STORE_FILE: &str = "goals.sqlite3";
+
+pub const GOAL_STATUSES: [&str; 4] = ["open", "in_progress", "completed", "cancelled"];
+pub const REMINDER_STATUSES: [&str; 3] = ["pending", "done", "dismissed"];
+pub const DEFAULT_PRIORITY: i64 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoalItem {
+    pub id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub deadline: Option<String>,
+    /// "open" | "in_progress" | "completed" | "cancelled"
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+    /// Parent goal when this is a sub-goal.
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    /// Estimated focus minutes the goal needs; linked sessions count toward it.
+    #[serde(default)]
+    pub target_minutes: Option<i64>,
+    /// User priority, 1 (low) to 5 (high).
+    #[serde(default = "default_priority")]
+    pub priority: i64,
+}
+
+fn default_priority() -> i64 {
+    DEFAULT_PRIORITY
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskItem {
+    pub id: i64,
+    pub goal_id: Option<i64>,
+    pub title: String,
+    pub notes: Option<String>,
+    pub deadline: Option<String>,
+    /// Same statuses as goals.
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub milestone_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReminderItem {
+    pub id: i64,
+    pub goal_id: Option<i64>,
+    pub task_id: Option<i64>,
+    pub text: String,
+    pub remind_at: Option<String>,
+    /// "pending" | "done" | "dismissed"
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// Partial update; `Some("")` clears an optional text field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub deadline: Option<String>,
+    pub goal_id: Option<i64>,
+    pub task_id: Option<i64>,
+    /// Tasks and reminders: unlink from the goal (a task also leaves its milestone).
+    #[serde(default)]
+    pub clear_goal: bool,
+    /// Reminders only: unlink from the task.
+    #[serde(default)]
+    pub clear_task: bool,
+    pub remind_at: Option<String>,
+    /// Tasks only: move the task under a milestone (and that milestone's goal).
+    pub milestone_id: Option<i64>,
+    /// Goals only: `Some(0)` clears the estimate.
+    pub target_minutes: Option<i64>,
+    /// Goals only: 1-5.
+    pub priority: Option<i64>,
+}
+
+pub fn store_path(workspace_dir: Option<&str>) -> PathBuf {
+    utility::workspace_store_path(workspace_dir, STORE_FILE)
+}
 
-            // Choose column expressions only from available columns, aliasing them consistently
-            let title_expr = if cols.contains("title") {
-                "title AS title".to_string()
-            } else if cols.contains("description") {
-                "description AS title".to_string()
-            } else {
-                "'' AS title".to_string()
-            };
-            let deadline_expr = if cols.contains("deadline") {
-                "deadline AS deadline".to_string()
-            } else if cols.contains("target_date") {
-                "target_date AS deadline".to_string()
-            } else {
-                "'' AS deadline".to_string()
-            };
-            let created_expr = if cols.contains("created_at") {
-                "created_at AS created".to_string()
-            } else {
-                "datetime('now') AS created".to_string()
-            };
-            let linked_expr = if cols.contains("linked_json") {
-                "linked_json AS linked_json".to_string()
-            } else {
-                "'[]' AS linked_json".to_string()
-            };
-
-            let order_expr = if cols.contains("deadline") {
-                "deadline".to_string()
-            } else if cols.contains("target_date") {
-                "target_date".to_string()
-            } else {
-                "created".to_string()
-            };
-
-            let sql = format!(
-                "SELECT {title}, {deadline}, {created}, {linked} FROM goal WHERE COALESCE(completed, 0) = 0 ORDER BY {order}",
-                title = title_expr,
-                deadline = deadline_expr,
-                created = created_expr,
-                linked = linked_expr,
-                order = order_expr
-            );
-
-            if let Ok(mut stmt) = conn.prepare(&sql) {
-                let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-                let mut out = Vec::new();
-                while let Ok(Some(r)) = rows.next() {
-                    let title: String = r.get(0).unwrap_or_default();
-                    let deadline: String = r.get::<_, Option<String>>(1).unwrap_or(None).unwrap_or_default();
-                    let created: String = r.get::<_, Option<String>>(2).unwrap_or(None).unwrap_or_default();
-                    let linked_json: String = r.get::<_, Option<String>>(3).unwrap_or(None).unwrap_or_default();
-                    let linked: Vec<String> = serde_json::from_str(&linked_json).unwrap_or_else(|_| vec![]);
-                    out.push(Goal { title, deadline, created, linked });
+pub fn ensure_store_schema(conn: &Connection) -> Result<(), String> {
+    let had_legacy_keys = !utility::table_columns(conn, "legacy_keys").is_empty();
+    conn.execute_batch(r#"
+        PRAGMA foreign_keys = ON;
+        CREATE TABLE IF NOT EXISTS goals (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            description TEXT,
+            deadline TEXT,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'in_progress', 'completed', 'cancelled')),
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            completed_at TEXT,
+            legacy_key TEXT UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            goal_id INTEGER REFERENCES goals(id) ON DELETE SET NULL,
+            title TEXT NOT NULL,
+            notes TEXT,
+            deadline TEXT,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'in_progress', 'completed', 'cancelled')),
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            completed_at TEXT,
+            legacy_key TEXT UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS reminders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            goal_id INTEGER REFERENCES goals(id) ON DELETE SET NULL,
+            task_id INTEGER REFERENCES tasks(id) ON DELETE SET NULL,
+            text TEXT NOT NULL,
+            remind_at TEXT,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'done', 'dismissed')),
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            completed_at TEXT,
+            legacy_key TEXT UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS legacy_imports (
+            day TEXT PRIMARY KEY,
+            imported_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS legacy_keys (
+            legacy_key TEXT PRIMARY KEY,
+            imported_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS milestones (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            goal_id INTEGER NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+            title TEXT NOT NULL,
+            due_date TEXT,
+            status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'in_progress', 'completed', 'cancelled')),
+            position INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            completed_at TEXT
+        );
+        CREATE TABLE IF NOT EXISTS goal_cards (
+            goal_id INTEGER NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+            rfid TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (goal_id, rfid)
+        );
+        CREATE TABLE IF NOT EXISTS session_goals (
+            day TEXT NOT NULL,
+            session_id INTEGER NOT NULL,
+            goal_id INTEGER NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+            PRIMARY KEY (day, session_id, goal_id)
+        );
+        CREATE TABLE IF NOT EXISTS goal_escalations (
+            goal_id INTEGER PRIMARY KEY REFERENCES goals(id) ON DELETE CASCADE,
+            state TEXT NOT NULL,
+            notified_at TEXT NOT NULL
+        );
+    "#).map_err(|e| e.to_string())?;
+    // Columns added after the store was introduced
+    if !utility::table_columns(conn, "goals").contains("parent_id") {
+        conn.execute("ALTER TABLE goals ADD COLUMN parent_id INTEGER REFERENCES goals(id) ON DELETE SET NULL", []).map_err(|e| e.to_string())?;
+    }
+    if !utility::table_columns(conn, "goals").contains("target_minutes") {
+        conn.execute("ALTER TABLE goals ADD COLUMN target_minutes INTEGER", []).map_err(|e| e.to_string())?;
+    }
+    if !utility::table_columns(conn, "goals").contains("priority") {
+        conn.execute("ALTER TABLE goals ADD COLUMN priority INTEGER NOT NULL DEFAULT 3", []).map_err(|e| e.to_string())?;
+    }
+    if !utility::table_columns(conn, "tasks").contains("milestone_id") {
+        conn.execute("ALTER TABLE tasks ADD COLUMN milestone_id INTEGER REFERENCES milestones(id) ON DELETE SET NULL", []).map_err(|e| e.to_string())?;
+    }
+    search::ensure_goal_index(conn)?;
+    if !had_legacy_keys {
+        conn.execute_batch("INSERT OR IGNORE INTO legacy_keys (legacy_key)
+            SELECT legacy_key FROM goals WHERE legacy_key IS NOT NULL
+            UNION SELECT legacy_key FROM tasks WHERE legacy_key IS NOT NULL
+            UNION SELECT legacy_key FROM reminders WHERE legacy_key IS NOT NULL").map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// First of `candidates` that exists in `cols`, else `fallback`.
+fn pick_column(cols: &HashSet<String>, candidates: &[&str], fallback: &str) -> String {
+    candidates.iter().find(|c| cols.contains(**c)).map(|c| c.to_string()).unwrap_or_else(|| fallback.to_string())
+}
+
+type LegacyRow = (i64, String, Option<String>, bool, Option<String>);
+
+fn legacy_rows(daily: &Connection, table: &str, title: &[&str], date: &[&str]) -> Vec<LegacyRow> {
+    let cols = utility::table_columns(daily, table);
+    if cols.is_empty() { return vec![]; }
+    let sql = format!(
+        "SELECT id, COALESCE({}, ''), {}, COALESCE({}, 0), {} FROM {}",
+        pick_column(&cols, title, "''"), pick_column(&cols, date, "NULL"), pick_column(&cols, &["completed"], "0"), pick_column(&cols, &["created_at"], "NULL"), table,
+    );
+    let mut stmt = match daily.prepare(&sql) { Ok(s) => s, Err(_) => return vec![] };
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get::<_, i64>(3)? != 0, r.get(4)?)));
+    match rows { Ok(rows) => rows.flatten().filter(|r: &LegacyRow| !r.1.trim().is_empty()).collect(), Err(_) => vec![] }
+}
+
+/// Whether `key` was imported before, recording it if not.
+fn first_import(conn: &Connection, key: &str) -> Result<bool, String> {
+    conn.execute("INSERT OR IGNORE INTO legacy_keys (legacy_key) VALUES (?)", params![key]).map(|n| n > 0).map_err(|e| e.to_string())
+}
+
+/// Copy goals, tasks and reminders out of the daily DBs. Past days are imported once; today's and
+/// future files are re-read, but only rows not imported before are copied. Rows without a
+/// `created_at` are dated to their file's day. A legacy goal with the
+/// same title and deadline as one already imported from another day is treated as the same goal,
+/// and a completed copy completes it.
+pub fn import_legacy(conn: &Connection, workspace_dir: Option<&str>, today: NaiveDate) -> Result<(), String> {
+    for (day, path) in utility::list_daily_dbs(workspace_dir) {
+        let done: bool = conn.query_row("SELECT COUNT(*) FROM legacy_imports WHERE day = ?", params![day.to_string()], |r| r.get::<_, i64>(0)).map_err(|e| e.to_string())? > 0;
+        if done { continue; }
+        let daily = match Connection::open(&path) { Ok(c) => c, Err(_) => continue };
+        let completed_at = |completed: bool| if completed { Some(day.to_string()) } else { None };
+        for (id, title, deadline, completed, created) in legacy_rows(&daily, "goal", &["title", "description"], &["deadline", "target_date"]) {
+            let key = format!("goal:{}:{}", day, id);
+            if !first_import(conn, &key)? { continue; }
+            let dup: Option<i64> = conn.query_row(
+                "SELECT id FROM goals WHERE legacy_key IS NOT NULL AND title = ? AND COALESCE(deadline, '') = COALESCE(?, '') ORDER BY id LIMIT 1",
+                params![title, deadline], |r| r.get(0),
+            ).optional().map_err(|e| e.to_string())?;
+            if let Some(dup) = dup {
+                if completed {
+                    conn.execute(
+                        "UPDATE goals SET status = 'completed', completed_at = ?, updated_at = datetime('now') WHERE id = ? AND status IN ('open', 'in_progress')",
+                        params![completed_at(true), dup],
+                    ).map_err(|e| e.to_string())?;
                 }
-                return Ok(out);
+                continue;
             }
+            conn.execute(
+                "INSERT OR IGNORE INTO goals (title, deadline, status, created_at, completed_at, legacy_key) VALUES (?, ?, ?, COALESCE(?, ?), ?, ?)",
+                params![title, deadline, if completed { "completed" } else { "open" }, created, day.to_string(), completed_at(completed), key],
+            ).map_err(|e| e.to_string())?;
+        }
+        for (id, title, deadline, completed, created) in legacy_rows(&daily, "task", &["title"], &["deadline"]) {
+            let key = format!("task:{}:{}", day, id);
+            if !first_import(conn, &key)? { continue; }
+            conn.execute(
+                "INSERT OR IGNORE INTO tasks (title, deadline, status, created_at, completed_at, legacy_key) VALUES (?, ?, ?, COALESCE(?, ?), ?, ?)",
+                params![title, deadline, if completed { "completed" } else { "open" }, created, day.to_string(), completed_at(completed), key],
+            ).map_err(|e| e.to_string())?;
+        }
+        for (id, text, remind_at, _, created) in legacy_rows(&daily, "reminder", &["text"], &["remind_at"]) {
+            let key = format!("reminder:{}:{}", day, id);
+            if !first_import(conn, &key)? { continue; }
+            conn.execute(
+                "INSERT OR IGNORE INTO reminders (text, remind_at, created_at, legacy_key) VALUES (?, ?, COALESCE(?, ?), ?)",
+                params![text, remind_at, created, day.to_string(), key],
+            ).map_err(|e| e.to_string())?;
+        }
+        if day < today {
+            conn.execute("INSERT OR IGNORE INTO legacy_imports (day) VALUES (?)", params![day.to_string()]).map_err(|e| e.to_string())?;
         }
     }
-    // fallback demo data
-    Ok(vec![Goal {
-        title: "Finish reading productivity book".to_string(),
-        deadline: "2025-09-10".to_string(),
-        created: "2025-08-20".to_string(),
-        linked: vec!["Task - Read 10 pages daily".to_string(), "Reminder - Schedule reading time".to_string()],
-    }])
+    Ok(())
 }
 
-#[tauri::command]
-pub fn get_pending_tasks(workspace_dir: Option<String>) -> Result<Vec<Task>, String> {
-    if let Some(path) = utility::find_daily_db(workspace_dir, None) {
-        if let Ok(conn) = Connection::open(path) {
-            // Task table may have similar naming variations
-            if let Ok(mut stmt) = conn.prepare("SELECT COALESCE(title, '') AS title, COALESCE(deadline, '') AS deadline, COALESCE(created_at, datetime('now')) AS created_at, COALESCE(linked_json, '[]') AS linked_json FROM task WHERE COALESCE(completed, 0) = 0 ORDER BY COALESCE(deadline, '')") {
-                let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-                let mut out = Vec::new();
-                while let Ok(Some(r)) = rows.next() {
-                    let title: String = r.get(0).unwrap_or_default();
-                    let deadline: String = r.get::<_, Option<String>>(1).unwrap_or(None).unwrap_or_default();
-                    let created: String = r.get::<_, Option<String>>(2).unwrap_or(None).unwrap_or_default();
-                    let linked_json: String = r.get::<_, Option<String>>(3).unwrap_or(None).unwrap_or_default();
-                    let linked: Vec<String> = serde_json::from_str(&linked_json).unwrap_or_else(|_| vec![]);
-                    out.push(Task { title, deadline, created, linked });
-                }
-                return Ok(out);
-            }
+/// Open (creating if needed) the workspace store with legacy rows imported.
+pub fn open_store(workspace_dir: Option<&str>) -> Result<Connection, String> {
+    let path = store_path(workspace_dir);
+    if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+    let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+    ensure_store_schema(&conn)?;
+    import_legacy(&conn, workspace_dir, chrono::Local::now().date_naive())?;
+    Ok(conn)
+}
+
+/// Like `open_store`, but `None` for a workspace with neither a store nor any daily DB, so plain
+/// reads never leave a store file behind.
+pub(crate) fn open_store_for_read(workspace_dir: Option<&str>) -> Result<Option<Connection>, String> {
+    if !store_path(workspace_dir).is_file() && utility::list_daily_dbs(workspace_dir).is_empty() {
+        return Ok(None);
+    }
+    open_store(workspace_dir).map(Some)
+}
+
+const GOAL_COLUMNS: &str = "id, title, description, deadline, status, created_at, updated_at, completed_at, parent_id, target_minutes, priority";
+const TASK_COLUMNS: &str = "id, goal_id, title, notes, deadline, status, created_at, updated_at, completed_at, milestone_id";
+const REMINDER_COLUMNS: &str = "id, goal_id, task_id, text, remind_at, status, created_at, updated_at, completed_at";
+
+fn goal_from_row(r: &rusqlite::Row) -> rusqlite::Result<GoalItem> {
+    Ok(GoalItem { id: r.get(0)?, title: r.get(1)?, description: r.get(2)?, deadline: r.get(3)?, status: r.get(4)?, created_at: r.get(5)?, updated_at: r.get(6)?, completed_at: r.get(7)?, parent_id: r.get(8)?, target_minutes: r.get(9)?, priority: r.get(10)? })
+}
+
+fn task_from_row(r: &rusqlite::Row) -> rusqlite::Result<TaskItem> {
+    Ok(TaskItem { id: r.get(0)?, goal_id: r.get(1)?, title: r.get(2)?, notes: r.get(3)?, deadline: r.get(4)?, status: r.get(5)?, created_at: r.get(6)?, updated_at: r.get(7)?, completed_at: r.get(8)?, milestone_id: r.get(9)? })
+}
+
+fn reminder_from_row(r: &rusqlite::Row) -> rusqlite::Result<ReminderItem> {
+    Ok(ReminderItem { id: r.get(0)?, goal_id: r.get(1)?, task_id: r.get(2)?, text: r.get(3)?, remind_at: r.get(4)?, status: r.get(5)?, created_at: r.get(6)?, updated_at: r.get(7)?, completed_at: r.get(8)? })
+}
+
+pub(crate) fn non_empty(s: Option<&str>) -> Option<String> {
+    s.map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.to_string())
+}
+
+pub(crate) fn validate_deadline(deadline: Option<&str>) -> Result<(), String> {
+    if let Some(d) = non_empty(deadline) {
+        if NaiveDate::parse_from_str(&d, "%Y-%m-%d").is_err() && local_journal::parse_timestamp(&d, chrono::Local::now().date_naive()).is_none() {
+            return Err(format!("Invalid deadline '{}'", d));
         }
     }
-    Ok(vec![Task {
-        title: "Reply to client emails".to_string(),
-        deadline: "2025-09-03".to_string(),
-        created: "2025-09-02".to_string(),
-        linked: vec!["Goal - Maintain client relationships".to_string()],
-    }])
+    Ok(())
 }
 
-#[tauri::command]
-pub fn get_reminders(workspace_dir: Option<String>) -> Result<Vec<Reminder>, String> {
-    if let Some(path) = utility::find_daily_db(workspace_dir, None) {
-        if let Ok(conn) = Connection::open(path) {
-            // Reminder should be stable, but protect against missing created_at
-            if let Ok(mut stmt) = conn.prepare("SELECT COALESCE(text, '') AS text FROM reminder ORDER BY COALESCE(created_at, datetime('now')) DESC") {
-                let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
-                let mut out = Vec::new();
-                while let Ok(Some(r)) = rows.next() {
-                    let text: String = r.get(0).unwrap_or_default();
-                    out.push(Reminder { text });
-                }
-                return Ok(out);
-            }
+/// Whether `from -> to` is an allowed status change. Finished items can only be reopened.
+pub fn can_transition(from: &str, to: &str, reminder: bool) -> bool {
+    if from == to { return false; }
+    if reminder {
+        matches!((from, to), ("pending", "done") | ("pending", "dismissed") | ("done", "pending") | ("dismissed", "pending"))
+    } else {
+        match from {
+            "open" => matches!(to, "in_progress" | "completed" | "cancelled"),
+            "in_progress" => matches!(to, "open" | "completed" | "cancelled"),
+            "completed" | "cancelled" => to == "open",
+            _ => false,
         }
     }
-    Ok(vec![Reminder { text: "Drink water regularly".to_string() }])
+}
+
+pub(crate) fn set_status(conn: &Connection, table: &str, id: i64, status: &str) -> Result<(), String> {
+    let reminder = table == "reminders";
+    let allowed: &[&str] = if reminder { &REMINDER_STATUSES } else { &GOAL_STATUSES };
+    if !allowed.contains(&status) { return Err(format!("Unknown status '{}'", status)); }
+    let current: String = conn.query_row(&format!("SELECT status FROM {} WHERE id = ?", table), params![id], |r| r.get(0))
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("{} {} not found", &table[..table.len() - 1], id))?;
+    if !can_transition(&current, status, reminder) {
+        return Err(format!("Cannot change status from {} to {}", current, status));
+    }
+    let finished = status == "completed" || status == "done";
+    conn.execute(
+        &format!("UPDATE {} SET status = ?, completed_at = CASE WHEN ? THEN datetime('now') ELSE NULL END, updated_at = datetime('now') WHERE id = ?", table),
+        params![status, finished, id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub(crate) fn require_row(conn: &Connection, table: &str, id: Option<i64>) -> Result<(), String> {
+    if let Some(id) = id {
+        let n: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM {} WHERE id = ?", table), params![id], |r| r.get(0)).map_err(|e| e.to_string())?;
+        if n == 0 { return Err(format!("{} {} not found", &table[..table.len() - 1], id)); }
+    }
+    Ok(())
+}
+
+pub fn get_goal(conn: &Connection, id: i64) -> Result<GoalItem, String> {
+    conn.query_row(&format!("SELECT {} FROM goals WHERE id = ?", GOAL_COLUMNS), params![id], goal_from_row)
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("goal {} not found", id))
+}
+
+pub fn get_task(conn: &Connection, id: i64) -> Result<TaskItem, String> {
+    conn.query_row(&format!("SELECT {} FROM tasks WHERE id = ?", TASK_COLUMNS), params![id], task_from_row)
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("task {} not found", id))
+}
+
+pub fn get_reminder(conn: &Connection, id: i64) -> Result<ReminderItem, String> {
+    conn.query_row(&format!("SELECT {} FROM reminders WHERE id = ?", REMINDER_COLUMNS), params![id], reminder_from_row)
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("reminder {} not found", id))
+}
+
+pub fn insert_goal(conn: &Connection, title: &str, description: Option<&str>, deadline: Option<&str>) -> Result<GoalItem, String> {
+    if title.trim().is_empty() { return Err("Title is required".to_string()); }
+    validate_deadline(deadline)?;
+    conn.execute("INSERT INTO goals (title, description, deadline) VALUES (?, ?, ?)", params![title.trim(), non_empty(description), non_empty(deadline)]).map_err(|e| e.to_string())?;
+    get_goal(conn, conn.last_insert_rowid())
+}
+
+pub fn insert_task(conn: &Connection, title: &str, goal_id: Option<i64>, notes: Option<&str>, deadline: Option<&str>) -> Result<TaskItem, String> {
+    if title.trim().is_empty() { return Err("Title is required".to_string()); }
+    validate_deadline(deadline)?;
+    require_row(conn, "goals", goal_id)?;
+    conn.execute("INSERT INTO tasks (title, goal_id, notes, deadline) VALUES (?, ?, ?, ?)", params![title.trim(), goal_id, non_empty(notes), non_empty(deadline)]).map_err(|e| e.to_string())?;
+    get_task(conn, conn.last_insert_rowid())
+}
+
+pub fn insert_reminder(conn: &Connection, text: &str, remind_at: Option<&str>, goal_id: Option<i64>, task_id: Option<i64>) -> Result<ReminderItem, String> {
+    if text.trim().is_empty() { return Err("Text is required".to_string()); }
+    validate_deadline(remind_at)?;
+    require_row(conn, "goals", goal_id)?;
+    require_row(conn, "tasks", task_id)?;
+    conn.execute("INSERT INTO reminders (text, remind_at, goal_id, task_id) VALUES (?, ?, ?, ?)", params![text.trim(), non_empty(remind_at), goal_id, task_id]).map_err(|e| e.to_string())?;
+    get_reminder(conn, conn.last_insert_rowid())
+}
+
+pub fn update_goal_row(conn: &Connection, id: i64, u: &ItemUpdate) -> Result<GoalItem, String> {
+    let current = get_goal(conn, id)?;
+    validate_deadline(u.deadline.as_deref())?;
+    let title = u.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(|t| t.to_string()).unwrap_or(current.title);
+    let description = match &u.description { Some(d) => non_empty(Some(d)), None => current.description };
+    let deadline = match &u.deadline { Some(d) => non_empty(Some(d)), None => current.deadline };
+    let target_minutes = match u.target_minutes {
+        Some(m) if m < 0 => return Err("target_minutes must not be negative".to_string()),
+        Some(0) => None,
+        Some(m) => Some(m),
+        None => current.target_minutes,
+    };
+    let priority = u.priority.unwrap_or(current.priority);
+    if !(1..=5).contains(&priority) { return Err("priority must be between 1 and 5".to_string()); }
+    conn.execute("UPDATE goals SET title = ?, description = ?, deadline = ?, target_minutes = ?, priority = ?, updated_at = datetime('now') WHERE id = ?", params![title, description, deadline, target_minutes, priority, id]).map_err(|e| e.to_string())?;
+    get_goal(conn, id)
+}
+
+pub fn update_task_row(conn: &Connection, id: i64, u: &ItemUpdate) -> Result<TaskItem, String> {
+    let current = get_task(conn, id)?;
+    validate_deadline(u.deadline.as_deref())?;
+    require_row(conn, "goals", u.goal_id)?;
+    let title = u.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(|t| t.to_string()).unwrap_or(current.title);
+    let notes = match &u.description { Some(d) => non_empty(Some(d)), None => current.notes };
+    let deadline = match &u.deadline { Some(d) => non_empty(Some(d)), None => current.deadline };
+    if u.clear_goal && (u.goal_id.is_some() || u.milestone_id.is_some()) {
+        return Err("clear_goal cannot be combined with goal_id or milestone_id".to_string());
+    }
+    let mut goal_id = if u.clear_goal { None } else { u.goal_id.or(current.goal_id) };
+    let mut milestone_id = current.milestone_id;
+    if let Some(mid) = u.milestone_id {
+        let milestone = goal_hierarchy::get_milestone(conn, mid)?;
+        if u.goal_id.is_some_and(|g| g != milestone.goal_id) {
+            return Err(format!("milestone {} belongs to goal {}", mid, milestone.goal_id));
+        }
+        goal_id = Some(milestone.goal_id);
+        milestone_id = Some(mid);
+    } else if goal_id != current.goal_id {
+        // A milestone never outlives a move to another goal
+        milestone_id = None;
+    }
+    conn.execute("UPDATE tasks SET title = ?, notes = ?, deadline = ?, goal_id = ?, milestone_id = ?, updated_at = datetime('now') WHERE id = ?", params![title, notes, deadline, goal_id, milestone_id, id]).map_err(|e| e.to_string())?;
+    get_task(conn, id)
+}
+
+pub fn update_reminder_row(conn: &Connection, id: i64, u: &ItemUpdate) -> Result<ReminderItem, String> {
+    let current = get_reminder(conn, id)?;
+    validate_deadline(u.remind_at.as_deref())?;
+    require_row(conn, "goals", u.goal_id)?;
+    require_row(conn, "tasks", u.task_id)?;
+    if (u.clear_goal && u.goal_id.is_some()) || (u.clear_task && u.task_id.is_some()) {
+        return Err("clear_goal/clear_task cannot be combined with goal_id/task_id".to_string());
+    }
+    let text = u.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(|t| t.to_string()).unwrap_or(current.text);
+    let remind_at = match &u.remind_at { Some(d) => non_empty(Some(d)), None => current.remind_at };
+    let goal_id = if u.clear_goal { None } else { u.goal_id.or(current.goal_id) };
+    let task_id = if u.clear_task { None } else { u.task_id.or(current.task_id) };
+    conn.execute(
+        "UPDATE reminders SET text = ?, remind_at = ?, goal_id = ?, task_id = ?, updated_at = datetime('now') WHERE id = ?",
+        params![text, remind_at, goal_id, task_id, id],
+    ).map_err(|e| e.to_string())?;
+    get_reminder(conn, id)
+}
+
+pub fn delete_row(conn: &Connection, table: &str, id: i64) -> Result<(), String> {
+    let n = conn.execute(&format!("DELETE FROM {} WHERE id = ?", table), params![id]).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("{} {} not found", &table[..table.len() - 1], id)); }
+    Ok(())
+}
+
+/// Goals, optionally only those with `status`, deadline first (undated last).
+pub fn list_goal_rows(conn: &Connection, status: Option<&str>) -> Result<Vec<GoalItem>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM goals WHERE ?1 IS NULL OR status = ?1 ORDER BY deadline IS NULL, deadline, id", GOAL_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![status], goal_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn list_task_rows(conn: &Connection, status: Option<&str>, goal_id: Option<i64>) -> Result<Vec<TaskItem>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM tasks WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR goal_id = ?2) ORDER BY deadline IS NULL, deadline, id", TASK_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![status, goal_id], task_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn list_reminder_rows(conn: &Connection, status: Option<&str>) -> Result<Vec<ReminderItem>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM reminders WHERE ?1 IS NULL OR status = ?1 ORDER BY remind_at IS NULL, remind_at, created_at DESC, id", REMINDER_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![status], reminder_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Day part of a stored `YYYY-MM-DD...` timestamp.
+pub(crate) fn day_of(ts: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(ts.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Whether `g` was open on `day`: created by then and not completed or cancelled before it.
+pub fn active_on(g: &GoalItem, day: NaiveDate) -> bool {
+    if day_of(&g.created_at).is_some_and(|c| c > day) { return false; }
+    let ended = match g.status.as_str() {
+        "completed" => g.completed_at.as_deref(),
+        "cancelled" => Some(g.updated_at.as_str()),
+        _ => None,
+    };
+    ended.and_then(day_of).is_none_or(|e| e >= day)
+}
+
+/// Whether `g` had been completed by the end of `day`.
+pub fn completed_by(g: &GoalItem, day: NaiveDate) -> bool {
+    g.status == "completed" && g.completed_at.as_deref().and_then(day_of).is_some_and(|c| c <= day)
+}
+
+pub(crate) fn is_active(status: &str) -> bool {
+    status == "open" || status == "in_progress"
+}
+
+/// Open and in-progress goals, highest user priority first and then by deadline, each linked to
+/// its milestones, unfinished tasks and pending reminders. Reads only the store; the session-aware
+/// ranking is `goal_priority::get_goal_priorities`.
+#[tauri::command]
+pub fn get_pending_goals(workspace_dir: Option<String>) -> Result<Vec<Goal>, String> {
+    let conn = match open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    let tasks = list_task_rows(&conn, None, None)?;
+    let reminders = list_reminder_rows(&conn, Some("pending"))?;
+    let milestones = goal_hierarchy::list_milestone_rows(&conn, None)?;
+    let mut active: Vec<GoalItem> = list_goal_rows(&conn, None)?.into_iter().filter(|g| is_active(&g.status)).collect();
+    active.sort_by_key(|g| std::cmp::Reverse(g.priority));
+    Ok(active.into_iter().map(|g| {
+        let mut linked: Vec<String> = milestones.iter().filter(|m| m.goal_id == g.id && is_active(&m.status)).map(|m| format!("Milestone - {}", m.title)).collect();
+        linked.extend(tasks.iter().filter(|t| t.goal_id == Some(g.id) && is_active(&t.status)).map(|t| format!("Task - {}", t.title)));
+        linked.extend(reminders.iter().filter(|r| r.goal_id == Some(g.id)).map(|r| format!("Reminder - {}", r.text)));
+        Goal { title: g.title, deadline: g.deadline.unwrap_or_default(), created: g.created_at, linked }
+    }).collect())
+}
+
+/// Open and in-progress tasks, linked to their goal.
+#[tauri::command]
+pub fn get_pending_tasks(workspace_dir: Option<String>) -> Result<Vec<Task>, String> {
+    let conn = match open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    let goals = list_goal_rows(&conn, None)?;
+    Ok(list_task_rows(&conn, None, None)?.into_iter().filter(|t| is_active(&t.status)).map(|t| {
+        let linked = goals.iter().filter(|g| Some(g.id) == t.goal_id).map(|g| format!("Goal - {}", g.title)).collect();
+        Task { title: t.title, deadline: t.deadline.unwrap_or_default(), created: t.created_at, linked }
+    }).collect())
+}
+
+/// Pending reminders.
+#[tauri::command]
+pub fn get_reminders(workspace_dir: Option<String>) -> Result<Vec<Reminder>, String> {
+    let conn = match open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    Ok(list_reminder_rows(&conn, Some("pending"))?.into_iter().map(|r| Reminder { text: r.text }).collect())
+}
+
+#[tauri::command]
+pub fn create_goal(workspace_dir: Option<String>, title: String, description: Option<String>, deadline: Option<String>) -> Result<GoalItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    insert_goal(&conn, &title, description.as_deref(), deadline.as_deref())
+}
+
+#[tauri::command]
+pub fn update_goal(workspace_dir: Option<String>, goal_id: i64, update: ItemUpdate) -> Result<GoalItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    update_goal_row(&conn, goal_id, &update)
+}
+
+#[tauri::command]
+pub fn set_goal_status(workspace_dir: Option<String>, goal_id: i64, status: String) -> Result<GoalItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    set_status(&conn, "goals", goal_id, &status)?;
+    get_goal(&conn, goal_id)
+}
+
+#[tauri::command]
+pub fn delete_goal(workspace_dir: Option<String>, goal_id: i64) -> Result<(), String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    delete_row(&conn, "goals", goal_id)
+}
+
+#[tauri::command]
+pub fn list_goals(workspace_dir: Option<String>, status: Option<String>) -> Result<Vec<GoalItem>, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    list_goal_rows(&conn, status.as_deref())
+}
+
+#[tauri::command]
+pub fn create_task(workspace_dir: Option<String>, title: String, goal_id: Option<i64>, notes: Option<String>, deadline: Option<String>) -> Result<TaskItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    insert_task(&conn, &title, goal_id, notes.as_deref(), deadline.as_deref())
+}
+
+#[tauri::command]
+pub fn update_task(workspace_dir: Option<String>, task_id: i64, update: ItemUpdate) -> Result<TaskItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    update_task_row(&conn, task_id, &update)
+}
+
+#[tauri::command]
+pub fn set_task_status(workspace_dir: Option<String>, task_id: i64, status: String) -> Result<TaskItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    set_status(&conn, "tasks", task_id, &status)?;
+    get_task(&conn, task_id)
+}
+
+#[tauri::command]
+pub fn delete_task(workspace_dir: Option<String>, task_id: i64) -> Result<(), String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    delete_row(&conn, "tasks", task_id)
+}
+
+#[tauri::command]
+pub fn list_tasks(workspace_dir: Option<String>, status: Option<String>, goal_id: Option<i64>) -> Result<Vec<TaskItem>, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    list_task_rows(&conn, status.as_deref(), goal_id)
+}
+
+#[tauri::command]
+pub fn create_reminder(workspace_dir: Option<String>, text: String, remind_at: Option<String>, goal_id: Option<i64>, task_id: Option<i64>) -> Result<ReminderItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    insert_reminder(&conn, &text, remind_at.as_deref(), goal_id, task_id)
+}
+
+#[tauri::command]
+pub fn update_reminder(workspace_dir: Option<String>, reminder_id: i64, update: ItemUpdate) -> Result<ReminderItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    update_reminder_row(&conn, reminder_id, &update)
+}
+
+#[tauri::command]
+pub fn set_reminder_status(workspace_dir: Option<String>, reminder_id: i64, status: String) -> Result<ReminderItem, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    set_status(&conn, "reminders", reminder_id, &status)?;
+    get_reminder(&conn, reminder_id)
+}
+
+#[tauri::command]
+pub fn delete_reminder(workspace_dir: Option<String>, reminder_id: i64) -> Result<(), String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    delete_row(&conn, "reminders", reminder_id)
+}
+
+#[tauri::command]
+pub fn list_reminders(workspace_dir: Option<String>, status: Option<String>) -> Result<Vec<ReminderItem>, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    list_reminder_rows(&conn, status.as_deref())
+}
+
+/// Minimal goal reference (with the row id) for features that need to point at a goal.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GoalRef {
+    pub id: i64,
+    pub title: String,
+    pub deadline: String,
+}
+
+/// Open and in-progress goals with their store ids (empty, never demo data, if there is no store).
+pub fn get_pending_goal_refs(workspace_dir: Option<String>) -> Result<Vec<GoalRef>, String> {
+    let conn = match open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    Ok(list_goal_rows(&conn, None)?.into_iter().filter(|g| is_active(&g.status))
+        .map(|g| GoalRef { id: g.id, title: g.title, deadline: g.deadline.unwrap_or_default() }).collect())
 }
 /// Goals module: Goal logic and CRUD.
 
@@ -0,0 +1,407 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params, OptionalExtension};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use std::path::PathBuf;
+use crate::backend::utility;
+
+// ---------- habits ----------
+//
+// Habits live in their own workspace-level store next to the daily DBs. Each habit has a schedule:
+//   daily    - every day
+//   weekdays - only on the listed ISO weekdays (1 = Monday … 7 = Sunday)
+//   weekly   - any `times_per_week` days of the ISO week
+// A day counts as done once it has `target_per_day` check-ins. Check-ins come from the
+// `check_in_habit` command or from event-card taps tagged with the habit (`{"habit": "<tag>"}` in
+// details_json, or an event_type of `habit:<tag>`), which are copied in once per event.
+//
+// Streaks are counted in scheduled days (weeks for weekly habits). Up to `allowed_skips_per_week`
+// missed scheduled days per ISO week are forgiven: they neither break nor extend the streak. A
+// day (or week) still in progress never breaks a streak.
+
+/// Workspace store holding habits and their check-ins.
+pub const STORE_FILE: &str = "habits.sqlite3";
+
+const COMPLETION_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HabitSchedule {
+    /// "daily" | "weekdays" | "weekly"
+    pub kind: String,
+    /// ISO weekdays, for "weekdays".
+    #[serde(default)]
+    pub weekdays: Vec<u32>,
+    /// For "weekly".
+    #[serde(default)]
+    pub times_per_week: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Habit {
+    pub id: i64,
+    pub name: String,
+    /// Lowercase tag matched against event-card taps.
+    pub tag: String,
+    pub schedule: HabitSchedule,
+    pub target_per_day: i64,
+    pub allowed_skips_per_week: i64,
+    /// First day the habit is tracked (YYYY-MM-DD).
+    pub start_date: String,
+    pub archived: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HabitCheckin {
+    pub id: i64,
+    pub habit_id: i64,
+    pub day: String,
+    pub count: i64,
+    /// "command" | "event"
+    pub source: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HabitStatus {
+    pub habit: Habit,
+    pub due_today: bool,
+    pub today_count: i64,
+    pub done_today: bool,
+    /// For weekly habits: days done so far this ISO week.
+    pub week_count: i64,
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    /// "days" | "weeks"
+    pub streak_unit: String,
+    /// Share of scheduled days (weeks) done over the last 30 days, 0.0-1.0.
+    pub completion_rate: f64,
+    pub skips_left_this_week: i64,
+}
+
+pub fn store_path(workspace_dir: Option<&str>) -> PathBuf {
+    utility::workspace_store_path(workspace_dir, STORE_FILE)
+}
+
+pub fn ensure_store_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(r#"
+        PRAGMA foreign_keys = ON;
+        CREATE TABLE IF NOT EXISTS habits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            tag TEXT NOT NULL UNIQUE,
+            schedule_kind TEXT NOT NULL CHECK (schedule_kind IN ('daily', 'weekdays', 'weekly')),
+            weekdays TEXT,
+            times_per_week INTEGER,
+            target_per_day INTEGER NOT NULL DEFAULT 1,
+            allowed_skips_per_week INTEGER NOT NULL DEFAULT 0,
+            start_date TEXT NOT NULL,
+            archived INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS habit_checkins (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            habit_id INTEGER NOT NULL REFERENCES habits(id) ON DELETE CASCADE,
+            day TEXT NOT NULL,
+            count INTEGER NOT NULL DEFAULT 1,
+            source TEXT NOT NULL DEFAULT 'command',
+            event_ref TEXT UNIQUE,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_habit_checkins_day ON habit_checkins(habit_id, day);
+    "#).map_err(|e| e.to_string())
+}
+
+pub fn open_store(workspace_dir: Option<&str>) -> Result<Connection, String> {
+    let path = store_path(workspace_dir);
+    if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+    let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+    ensure_store_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Like `open_store`, but `None` when the workspace has no habit store yet.
+fn open_store_for_read(workspace_dir: Option<&str>) -> Result<Option<Connection>, String> {
+    if !store_path(workspace_dir).is_file() { return Ok(None); }
+    open_store(workspace_dir).map(Some)
+}
+
+fn slug(name: &str) -> String {
+    let s: String = name.trim().to_lowercase().chars().map(|c| if c.is_alphanumeric() { c } else { '-' }).collect();
+    s.split('-').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+fn parse_day(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", s, e))
+}
+
+fn validate_schedule(s: &HabitSchedule) -> Result<(), String> {
+    match s.kind.as_str() {
+        "daily" => Ok(()),
+        "weekdays" if s.weekdays.is_empty() => Err("A weekdays schedule needs at least one weekday".to_string()),
+        "weekdays" if s.weekdays.iter().any(|d| !(1..=7).contains(d)) => Err("Weekdays are 1 (Monday) to 7 (Sunday)".to_string()),
+        "weekdays" => Ok(()),
+        "weekly" => match s.times_per_week {
+            Some(n) if (1..=7).contains(&n) => Ok(()),
+            _ => Err("A weekly schedule needs times_per_week between 1 and 7".to_string()),
+        },
+        other => Err(format!("Unknown schedule '{}'", other)),
+    }
+}
+
+const HABIT_COLUMNS: &str = "id, name, tag, schedule_kind, weekdays, times_per_week, target_per_day, allowed_skips_per_week, start_date, archived, created_at";
+
+fn habit_from_row(r: &rusqlite::Row) -> rusqlite::Result<Habit> {
+    let weekdays: Option<String> = r.get(4)?;
+    let weekdays = weekdays.unwrap_or_default().split(',').filter_map(|d| d.trim().parse().ok()).collect();
+    Ok(Habit {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        tag: r.get(2)?,
+        schedule: HabitSchedule { kind: r.get(3)?, weekdays, times_per_week: r.get(5)? },
+        target_per_day: r.get(6)?,
+        allowed_skips_per_week: r.get(7)?,
+        start_date: r.get(8)?,
+        archived: r.get::<_, i64>(9)? != 0,
+        created_at: r.get(10)?,
+    })
+}
+
+pub fn get_habit(conn: &Connection, id: i64) -> Result<Habit, String> {
+    conn.query_row(&format!("SELECT {} FROM habits WHERE id = ?", HABIT_COLUMNS), params![id], habit_from_row)
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("habit {} not found", id))
+}
+
+pub fn list_habit_rows(conn: &Connection, include_archived: bool) -> Result<Vec<Habit>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM habits WHERE ?1 OR archived = 0 ORDER BY name, id", HABIT_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![include_archived], habit_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn insert_habit(conn: &Connection, name: &str, tag: Option<&str>, schedule: &HabitSchedule, target_per_day: i64, allowed_skips_per_week: i64, start_date: NaiveDate) -> Result<Habit, String> {
+    if name.trim().is_empty() { return Err("Name is required".to_string()); }
+    validate_schedule(schedule)?;
+    if target_per_day < 1 { return Err("target_per_day must be at least 1".to_string()); }
+    if !(0..=6).contains(&allowed_skips_per_week) { return Err("allowed_skips_per_week must be between 0 and 6".to_string()); }
+    let tag = slug(tag.unwrap_or(name));
+    if tag.is_empty() { return Err("Tag is required".to_string()); }
+    let weekdays = schedule.weekdays.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",");
+    conn.execute(
+        "INSERT INTO habits (name, tag, schedule_kind, weekdays, times_per_week, target_per_day, allowed_skips_per_week, start_date) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![name.trim(), tag, schedule.kind, weekdays, schedule.times_per_week, target_per_day, allowed_skips_per_week, start_date.format("%Y-%m-%d").to_string()],
+    ).map_err(|e| if e.to_string().contains("UNIQUE") { format!("A habit tagged '{}' already exists", tag) } else { e.to_string() })?;
+    get_habit(conn, conn.last_insert_rowid())
+}
+
+pub fn add_checkin(conn: &Connection, habit_id: i64, day: NaiveDate, count: i64) -> Result<HabitCheckin, String> {
+    if count < 1 { return Err("count must be at least 1".to_string()); }
+    get_habit(conn, habit_id)?;
+    conn.execute("INSERT INTO habit_checkins (habit_id, day, count) VALUES (?, ?, ?)", params![habit_id, day.format("%Y-%m-%d").to_string(), count]).map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    conn.query_row("SELECT id, habit_id, day, count, source, created_at FROM habit_checkins WHERE id = ?", params![id], |r| {
+        Ok(HabitCheckin { id: r.get(0)?, habit_id: r.get(1)?, day: r.get(2)?, count: r.get(3)?, source: r.get(4)?, created_at: r.get(5)? })
+    }).map_err(|e| e.to_string())
+}
+
+/// The habit tag an event row carries, if any.
+fn event_habit_tag(event_type: &str, details_json: Option<&str>) -> Option<String> {
+    if let Some(tag) = event_type.strip_prefix("habit:") { return Some(slug(tag)); }
+    let details: serde_json::Value = serde_json::from_str(details_json?).ok()?;
+    details.get("habit").and_then(|v| v.as_str()).map(slug)
+}
+
+/// Copy habit-tagged event-card taps from the daily DBs into check-ins (each event once).
+pub fn sync_event_checkins(conn: &Connection, workspace_dir: Option<&str>) -> Result<usize, String> {
+    let habits = list_habit_rows(conn, true)?;
+    if habits.is_empty() { return Ok(0); }
+    let mut added = 0;
+    for (day, path) in utility::list_daily_dbs(workspace_dir) {
+        let daily = match Connection::open(&path) { Ok(c) => c, Err(_) => continue };
+        let mut stmt = match daily.prepare("SELECT id, event_type, details_json FROM event") { Ok(s) => s, Err(_) => continue };
+        let rows = match stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?))) { Ok(r) => r, Err(_) => continue };
+        let day_key = day.format("%Y-%m-%d").to_string();
+        for (event_id, event_type, details) in rows.flatten() {
+            let tag = match event_habit_tag(&event_type, details.as_deref()) { Some(t) => t, None => continue };
+            let habit = match habits.iter().find(|h| h.tag == tag) { Some(h) => h, None => continue };
+            added += conn.execute(
+                "INSERT OR IGNORE INTO habit_checkins (habit_id, day, count, source, event_ref) VALUES (?, ?, 1, 'event', ?)",
+                params![habit.id, day_key, format!("{}:{}", day_key, event_id)],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(added)
+}
+
+/// Check-in counts per day for one habit.
+fn daily_counts(conn: &Connection, habit_id: i64) -> Result<std::collections::HashMap<NaiveDate, i64>, String> {
+    let mut stmt = conn.prepare("SELECT day, SUM(count) FROM habit_checkins WHERE habit_id = ? GROUP BY day").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![habit_id], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))).map_err(|e| e.to_string())?;
+    let mut out = std::collections::HashMap::new();
+    for row in rows {
+        let (day, n) = row.map_err(|e| e.to_string())?;
+        if let Ok(d) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") { out.insert(d, n); }
+    }
+    Ok(out)
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday() as i64)
+}
+
+fn is_scheduled(habit: &Habit, day: NaiveDate) -> bool {
+    match habit.schedule.kind.as_str() {
+        "weekdays" => habit.schedule.weekdays.contains(&day.weekday().number_from_monday()),
+        _ => true,
+    }
+}
+
+/// Outcome of one scheduled unit (a day, or a week for weekly habits).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Done,
+    Skipped,
+    Missed,
+    Pending,
+}
+
+/// Scheduled units from the habit's start through `today`, oldest first, with the first day of each.
+fn units(habit: &Habit, counts: &std::collections::HashMap<NaiveDate, i64>, today: NaiveDate) -> Vec<(NaiveDate, Unit)> {
+    let start = match NaiveDate::parse_from_str(&habit.start_date, "%Y-%m-%d") { Ok(d) => d, Err(_) => return vec![] };
+    let done = |d: NaiveDate| counts.get(&d).copied().unwrap_or(0) >= habit.target_per_day;
+    let mut out = Vec::new();
+    if habit.schedule.kind == "weekly" {
+        let needed = habit.schedule.times_per_week.unwrap_or(1) as i64;
+        let mut week = week_start(start);
+        while week <= today {
+            let days: Vec<NaiveDate> = (0..7).map(|i| week + Duration::days(i)).filter(|d| *d >= start && *d <= today).collect();
+            let n = days.iter().filter(|d| done(**d)).count() as i64;
+            let unit = if n >= needed { Unit::Done } else if week + Duration::days(6) >= today { Unit::Pending } else { Unit::Missed };
+            out.push((week, unit));
+            week += Duration::days(7);
+        }
+        return out;
+    }
+    let mut skips_used: std::collections::HashMap<NaiveDate, i64> = std::collections::HashMap::new();
+    let mut day = start;
+    while day <= today {
+        if is_scheduled(habit, day) {
+            let unit = if done(day) {
+                Unit::Done
+            } else if day == today {
+                Unit::Pending
+            } else {
+                let used = skips_used.entry(week_start(day)).or_insert(0);
+                if *used < habit.allowed_skips_per_week { *used += 1; Unit::Skipped } else { Unit::Missed }
+            };
+            out.push((day, unit));
+        }
+        day += Duration::days(1);
+    }
+    out
+}
+
+/// (current, longest) streak in units. Skipped and pending units are neutral.
+fn streaks(units: &[(NaiveDate, Unit)]) -> (i64, i64) {
+    let (mut current, mut longest) = (0, 0);
+    for (_, u) in units {
+        match u {
+            Unit::Done => { current += 1; longest = longest.max(current); }
+            Unit::Missed => current = 0,
+            Unit::Skipped | Unit::Pending => {}
+        }
+    }
+    (current, longest)
+}
+
+pub fn habit_status(conn: &Connection, habit: &Habit, today: NaiveDate) -> Result<HabitStatus, String> {
+    let counts = daily_counts(conn, habit.id)?;
+    let units = units(habit, &counts, today);
+    let (current_streak, longest_streak) = streaks(&units);
+    let window_start = today - Duration::days(COMPLETION_WINDOW_DAYS - 1);
+    let recent: Vec<Unit> = units.iter()
+        .filter(|(d, _)| *d >= window_start || (habit.schedule.kind == "weekly" && *d + Duration::days(6) >= window_start))
+        .map(|(_, u)| *u)
+        .filter(|u| *u != Unit::Pending && *u != Unit::Skipped)
+        .collect();
+    let completion_rate = if recent.is_empty() { 0.0 } else {
+        let rate = recent.iter().filter(|u| **u == Unit::Done).count() as f64 / recent.len() as f64;
+        (rate * 1000.0).round() / 1000.0
+    };
+    let today_count = counts.get(&today).copied().unwrap_or(0);
+    let this_week = week_start(today);
+    let week_count = (0..7).map(|i| this_week + Duration::days(i)).filter(|d| counts.get(d).copied().unwrap_or(0) >= habit.target_per_day).count() as i64;
+    let skips_used = units.iter().filter(|(d, u)| *u == Unit::Skipped && week_start(*d) == this_week).count() as i64;
+    Ok(HabitStatus {
+        habit: habit.clone(),
+        due_today: is_scheduled(habit, today),
+        today_count,
+        done_today: today_count >= habit.target_per_day,
+        week_count,
+        current_streak,
+        longest_streak,
+        streak_unit: if habit.schedule.kind == "weekly" { "weeks" } else { "days" }.to_string(),
+        completion_rate,
+        skips_left_this_week: if habit.schedule.kind == "weekly" { 0 } else { (habit.allowed_skips_per_week - skips_used).max(0) },
+    })
+}
+
+/// Status of every active habit after pulling in tagged event-card taps.
+pub fn habit_statuses(conn: &Connection, workspace_dir: Option<&str>, today: NaiveDate) -> Result<Vec<HabitStatus>, String> {
+    sync_event_checkins(conn, workspace_dir)?;
+    list_habit_rows(conn, false)?.iter().map(|h| habit_status(conn, h, today)).collect()
+}
+
+#[tauri::command]
+pub fn create_habit(workspace_dir: Option<String>, name: String, schedule: HabitSchedule, target_per_day: Option<i64>, allowed_skips_per_week: Option<i64>, tag: Option<String>, start_date: Option<String>) -> Result<Habit, String> {
+    let start = match start_date { Some(d) => parse_day(&d)?, None => Local::now().date_naive() };
+    let conn = open_store(workspace_dir.as_deref())?;
+    insert_habit(&conn, &name, tag.as_deref(), &schedule, target_per_day.unwrap_or(1), allowed_skips_per_week.unwrap_or(0), start)
+}
+
+#[tauri::command]
+pub fn list_habits(workspace_dir: Option<String>, include_archived: Option<bool>) -> Result<Vec<Habit>, String> {
+    let conn = match open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    list_habit_rows(&conn, include_archived.unwrap_or(false))
+}
+
+/// Archive (or restore) a habit; its check-ins are kept.
+#[tauri::command]
+pub fn set_habit_archived(workspace_dir: Option<String>, habit_id: i64, archived: bool) -> Result<Habit, String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    get_habit(&conn, habit_id)?;
+    conn.execute("UPDATE habits SET archived = ? WHERE id = ?", params![archived, habit_id]).map_err(|e| e.to_string())?;
+    get_habit(&conn, habit_id)
+}
+
+#[tauri::command]
+pub fn delete_habit(workspace_dir: Option<String>, habit_id: i64) -> Result<(), String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    let n = conn.execute("DELETE FROM habits WHERE id = ?", params![habit_id]).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("habit {} not found", habit_id)); }
+    Ok(())
+}
+
+/// Record a check-in for `date` (default today).
+#[tauri::command]
+pub fn check_in_habit(workspace_dir: Option<String>, habit_id: i64, date: Option<String>, count: Option<i64>) -> Result<HabitCheckin, String> {
+    let day = match date { Some(d) => parse_day(&d)?, None => Local::now().date_naive() };
+    let conn = open_store(workspace_dir.as_deref())?;
+    add_checkin(&conn, habit_id, day, count.unwrap_or(1))
+}
+
+/// Remove a check-in. Ones copied from an event tap are zeroed instead, so the next sync does not
+/// bring them back.
+#[tauri::command]
+pub fn delete_habit_checkin(workspace_dir: Option<String>, checkin_id: i64) -> Result<(), String> {
+    let conn = open_store(workspace_dir.as_deref())?;
+    let n = conn.execute("UPDATE habit_checkins SET count = 0 WHERE id = ? AND event_ref IS NOT NULL", params![checkin_id]).map_err(|e| e.to_string())?
+        + conn.execute("DELETE FROM habit_checkins WHERE id = ? AND event_ref IS NULL", params![checkin_id]).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("check-in {} not found", checkin_id)); }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_habit_status(workspace_dir: Option<String>, date: Option<String>) -> Result<Vec<HabitStatus>, String> {
+    let today = match date { Some(d) => parse_day(&d)?, None => Local::now().date_naive() };
+    let conn = match open_store_for_read(workspace_dir.as_deref())? { Some(c) => c, None => return Ok(vec![]) };
+    habit_statuses(&conn, workspace_dir.as_deref(), today)
+}
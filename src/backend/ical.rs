@@ -0,0 +1,674 @@
+//! iCalendar (RFC 5545) import and export for the calendar orchestrator.
+//!
+//! Export covers a date range: sessions, events and alarms as VEVENT, reminders and goal deadlines
+//! as VTODO, journal entries as VJOURNAL. Items are tagged with `X-FOCUSD-KIND` so re-importing an
+//! export does not turn sessions or goals into events.
+//!
+//! Import maps VEVENT to `event` rows, VTODO to `reminder` rows and VALARM to `alarm` rows, each in
+//! the daily DB of the day it falls on. Imported UIDs are remembered per workspace in
+//! `ical_imports` (personality DB) so re-importing a file updates rows instead of duplicating them.
+//! UIDs of exported rows carry the row and the workspace id; only UIDs from this workspace are
+//! matched to local rows, anything else is imported as a foreign item. Times with a TZID are treated
+//! as local time; UTC times are converted to local.
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params, OptionalExtension};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Duration as ChronoDuration, TimeZone};
+
+use crate::backend::{goals, journals};
+use crate::backend::local_journal::parse_timestamp;
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+use crate::backend::utility;
+
+const PRODID: &str = "-//focusd//focusd calendar//EN";
+const UID_DOMAIN: &str = "@focusd";
+pub const KIND_PROP: &str = "X-FOCUSD-KIND";
+
+// ---------- parsing ----------
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IcsProperty {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl IcsProperty {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IcsComponent {
+    pub name: String,
+    pub props: Vec<IcsProperty>,
+    pub children: Vec<IcsComponent>,
+}
+
+impl IcsComponent {
+    pub fn prop(&self, name: &str) -> Option<&IcsProperty> {
+        self.props.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Unescaped TEXT value of a property.
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.prop(name).map(|p| unescape_text(&p.value)).filter(|s| !s.is_empty())
+    }
+}
+
+/// A DATE or DATE-TIME value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcsTime {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl IcsTime {
+    pub fn date(&self) -> NaiveDate {
+        match self { IcsTime::Date(d) => *d, IcsTime::DateTime(dt) => dt.date() }
+    }
+
+    pub fn start_of(&self) -> NaiveDateTime {
+        match self { IcsTime::Date(d) => d.and_time(NaiveTime::MIN), IcsTime::DateTime(dt) => *dt }
+    }
+
+    /// Value stored in the daily DB: `YYYY-MM-DD` for all-day values, `YYYY-MM-DDTHH:MM:SS` otherwise.
+    pub fn to_db(&self) -> String {
+        match self {
+            IcsTime::Date(d) => d.format("%Y-%m-%d").to_string(),
+            IcsTime::DateTime(dt) => dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }
+    }
+}
+
+pub fn unescape_text(v: &str) -> String {
+    let mut out = String::with_capacity(v.len());
+    let mut chars = v.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn escape_text(v: &str) -> String {
+    v.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace("\r\n", "\\n").replace('\n', "\\n")
+}
+
+fn parse_property(line: &str) -> Option<IcsProperty> {
+    // The value starts at the first ':' outside a quoted parameter value.
+    let mut in_quotes = false;
+    let mut split = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => { split = Some(i); break; }
+            _ => {}
+        }
+    }
+    let split = split?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    if name.is_empty() { return None; }
+    let params = parts.filter_map(|p| {
+        let (k, v) = p.split_once('=')?;
+        Some((k.trim().to_ascii_uppercase(), v.trim().trim_matches('"').to_string()))
+    }).collect();
+    Some(IcsProperty { name, params, value: value.to_string() })
+}
+
+/// Parse an iCalendar stream into its top-level components (the children of VCALENDAR).
+pub fn parse_ics(text: &str) -> Result<Vec<IcsComponent>, String> {
+    // Unfold: a line starting with a space or tab continues the previous one.
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        if (raw.starts_with(' ') || raw.starts_with('\t')) && !lines.is_empty() {
+            if let Some(last) = lines.last_mut() { last.push_str(&raw[1..]); }
+        } else if !raw.is_empty() {
+            lines.push(raw.to_string());
+        }
+    }
+    let mut stack: Vec<IcsComponent> = Vec::new();
+    let mut top: Vec<IcsComponent> = Vec::new();
+    for (n, line) in lines.iter().enumerate() {
+        let prop = parse_property(line).ok_or_else(|| format!("line {}: malformed content line", n + 1))?;
+        match prop.name.as_str() {
+            "BEGIN" => stack.push(IcsComponent { name: prop.value.trim().to_ascii_uppercase(), ..Default::default() }),
+            "END" => {
+                let comp = stack.pop().ok_or_else(|| format!("line {}: END without BEGIN", n + 1))?;
+                if !comp.name.eq_ignore_ascii_case(prop.value.trim()) {
+                    return Err(format!("line {}: END:{} does not close {}", n + 1, prop.value.trim(), comp.name));
+                }
+                let depth = stack.len();
+                match stack.last_mut() {
+                    Some(parent) if parent.name == "VCALENDAR" && depth == 1 => top.push(comp),
+                    Some(parent) => parent.children.push(comp),
+                    None if comp.name == "VCALENDAR" => {}
+                    None => top.push(comp),
+                }
+            }
+            _ => match stack.last_mut() {
+                Some(c) => c.props.push(prop),
+                None => return Err(format!("line {}: property outside a component", n + 1)),
+            },
+        }
+    }
+    if let Some(open) = stack.last() {
+        return Err(format!("unterminated component {}", open.name));
+    }
+    Ok(top)
+}
+
+/// Parse a DATE / DATE-TIME property value. UTC (`Z`) values are converted to local time.
+pub fn parse_ics_time(prop: &IcsProperty) -> Option<IcsTime> {
+    let v = prop.value.trim();
+    if prop.param("VALUE").map(|x| x.eq_ignore_ascii_case("DATE")).unwrap_or(false) || v.len() == 8 {
+        return NaiveDate::parse_from_str(v, "%Y%m%d").ok().map(IcsTime::Date);
+    }
+    if let Some(utc) = v.strip_suffix('Z') {
+        let dt = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(IcsTime::DateTime(chrono::Utc.from_utc_datetime(&dt).with_timezone(&chrono::Local).naive_local()));
+    }
+    NaiveDateTime::parse_from_str(v, "%Y%m%dT%H%M%S").ok().map(IcsTime::DateTime)
+}
+
+/// Parse an RFC 5545 DURATION such as `-PT15M`, `P1D` or `P1W`.
+pub fn parse_duration(v: &str) -> Option<ChronoDuration> {
+    let v = v.trim();
+    let (sign, rest) = match v.as_bytes().first()? {
+        b'-' => (-1, &v[1..]),
+        b'+' => (1, &v[1..]),
+        _ => (1, v),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let mut total = ChronoDuration::zero();
+    let mut num = String::new();
+    let mut in_time = false;
+    let mut any = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => num.push(c),
+            'T' => in_time = true,
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = num.parse().ok()?;
+                num.clear();
+                any = true;
+                total += match (c, in_time) {
+                    ('W', false) => ChronoDuration::weeks(n),
+                    ('D', false) => ChronoDuration::days(n),
+                    ('H', true) => ChronoDuration::hours(n),
+                    ('M', true) => ChronoDuration::minutes(n),
+                    ('S', true) => ChronoDuration::seconds(n),
+                    _ => return None,
+                };
+            }
+            _ => return None,
+        }
+    }
+    if !any || !num.is_empty() { return None; }
+    Some(total * sign)
+}
+
+// ---------- writing ----------
+
+/// Fold a content line at 75 octets (RFC 5545 §3.1) without splitting UTF-8 sequences.
+pub fn fold_line(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out.push_str("\r\n");
+    out
+}
+
+fn fmt_time(t: &IcsTime) -> (String, &'static str) {
+    match t {
+        IcsTime::Date(d) => (d.format("%Y%m%d").to_string(), ";VALUE=DATE"),
+        IcsTime::DateTime(dt) => (dt.format("%Y%m%dT%H%M%S").to_string(), ""),
+    }
+}
+
+struct Writer {
+    out: String,
+    stamp: String,
+}
+
+impl Writer {
+    fn line(&mut self, s: &str) {
+        self.out.push_str(&fold_line(s));
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        if !value.is_empty() { self.line(&format!("{}:{}", name, escape_text(value))); }
+    }
+
+    fn time(&mut self, name: &str, t: &IcsTime) {
+        let (v, param) = fmt_time(t);
+        self.line(&format!("{}{}:{}", name, param, v));
+    }
+
+    fn begin(&mut self, comp: &str, uid: &str, kind: &str) {
+        self.line(&format!("BEGIN:{}", comp));
+        self.line(&format!("UID:{}", uid));
+        let stamp = self.stamp.clone();
+        self.line(&format!("DTSTAMP:{}", stamp));
+        self.line(&format!("{}:{}", KIND_PROP, kind));
+    }
+}
+
+fn db_time(s: &str, day: NaiveDate) -> Option<IcsTime> {
+    let s = s.trim();
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") { return Some(IcsTime::Date(d)); }
+    parse_timestamp(s, day).map(IcsTime::DateTime)
+}
+
+fn native_uid(kind: &str, day: NaiveDate, id: i64, workspace: &str) -> String {
+    format!("{}-{}-{}.{}{}", kind, day, id, workspace, UID_DOMAIN)
+}
+
+/// (kind, day, row id) encoded in a UID produced by `native_uid` for `workspace`.
+fn parse_native_uid(uid: &str, workspace: &str) -> Option<(String, NaiveDate, i64)> {
+    let (body, ws) = uid.strip_suffix(UID_DOMAIN)?.rsplit_once('.')?;
+    if ws != workspace { return None; }
+    let (kind, rest) = body.split_once('-')?;
+    let (date, id) = rest.rsplit_once('-')?;
+    Some((kind.to_string(), NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?, id.parse().ok()?))
+}
+
+fn export_day(w: &mut Writer, conn: &Connection, day: NaiveDate, workspace: &str) {
+    // Sessions (card label as the title when the card table is there)
+    let sql = if conn.prepare("SELECT label FROM card LIMIT 0").is_ok() {
+        "SELECT s.id, s.start_time, COALESCE(s.end_time, ''), COALESCE(s.notes, ''), COALESCE(c.label, '') FROM session s LEFT JOIN card c ON c.id = s.card_id ORDER BY s.start_time"
+    } else {
+        "SELECT id, start_time, COALESCE(end_time, ''), COALESCE(notes, ''), '' FROM session ORDER BY start_time"
+    };
+    if let Ok(mut stmt) = conn.prepare(sql) {
+        let rows: Vec<(i64, String, String, String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get::<_, Option<String>>(1)?.unwrap_or_default(), r.get(2)?, r.get(3)?, r.get(4)?)))
+            .map(|rows| rows.flatten().collect()).unwrap_or_default();
+        for (id, start, end, notes, label) in rows {
+            let start = match db_time(&start, day) { Some(t) => t, None => continue };
+            w.begin("VEVENT", &native_uid("session", day, id, workspace), "session");
+            w.time("DTSTART", &start);
+            if let Some(end) = db_time(&end, day) { w.time("DTEND", &end); }
+            w.text("SUMMARY", if label.is_empty() { "Focus session" } else { &label });
+            w.text("DESCRIPTION", &notes);
+            w.line("END:VEVENT");
+        }
+    }
+
+    // Events (imported ones keep their original UID and fields)
+    if let Ok(mut stmt) = conn.prepare("SELECT id, event_type, event_time, COALESCE(details_json, '') FROM event ORDER BY event_time") {
+        let rows: Vec<(i64, String, String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get::<_, Option<String>>(1)?.unwrap_or_default(), r.get::<_, Option<String>>(2)?.unwrap_or_default(), r.get(3)?)))
+            .map(|rows| rows.flatten().collect()).unwrap_or_default();
+        for (id, event_type, time, details) in rows {
+            let start = match db_time(&time, day) { Some(t) => t, None => continue };
+            let d: serde_json::Value = serde_json::from_str(&details).unwrap_or(serde_json::Value::Null);
+            let field = |k: &str| d.get(k).and_then(|v| v.as_str()).map(str::to_string);
+            let uid = field("ical_uid").unwrap_or_else(|| native_uid("event", day, id, workspace));
+            w.begin("VEVENT", &uid, "event");
+            w.time("DTSTART", &start);
+            if let Some(end) = field("end").and_then(|e| db_time(&e, day)) { w.time("DTEND", &end); }
+            w.text("SUMMARY", &field("summary").unwrap_or(event_type));
+            match field("ical_uid") {
+                Some(_) => {
+                    w.text("DESCRIPTION", &field("description").unwrap_or_default());
+                    w.text("LOCATION", &field("location").unwrap_or_default());
+                }
+                None if details.trim() != "{}" => w.text("DESCRIPTION", &details),
+                None => {}
+            }
+            w.line("END:VEVENT");
+        }
+    }
+
+    // Alarms: a VEVENT at the alarm time carrying a display VALARM
+    if let Ok(mut stmt) = conn.prepare("SELECT id, alarm_time, COALESCE(label, '') FROM alarm ORDER BY alarm_time") {
+        let rows: Vec<(i64, String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get::<_, Option<String>>(1)?.unwrap_or_default(), r.get(2)?)))
+            .map(|rows| rows.flatten().collect()).unwrap_or_default();
+        for (id, time, label) in rows {
+            let at = match db_time(&time, day) { Some(t) => t, None => continue };
+            let label = if label.is_empty() { "Alarm".to_string() } else { label };
+            w.begin("VEVENT", &native_uid("alarm", day, id, workspace), "alarm");
+            w.time("DTSTART", &at);
+            w.text("SUMMARY", &label);
+            w.line("BEGIN:VALARM");
+            w.line("ACTION:DISPLAY");
+            w.line("TRIGGER:PT0S");
+            w.text("DESCRIPTION", &label);
+            w.line("END:VALARM");
+            w.line("END:VEVENT");
+        }
+    }
+
+    // Reminders
+    if let Ok(mut stmt) = conn.prepare("SELECT id, text, COALESCE(remind_at, '') FROM reminder ORDER BY id") {
+        let rows: Vec<(i64, String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get::<_, Option<String>>(1)?.unwrap_or_default(), r.get(2)?)))
+            .map(|rows| rows.flatten().collect()).unwrap_or_default();
+        for (id, text, remind_at) in rows {
+            w.begin("VTODO", &native_uid("reminder", day, id, workspace), "reminder");
+            w.text("SUMMARY", &text);
+            if let Some(due) = db_time(&remind_at, day) { w.time("DUE", &due); }
+            w.line("END:VTODO");
+        }
+    }
+}
+
+/// Goals from the workspace store whose deadline falls in `start..=end`. Cancelled goals are left out.
+fn export_goal_deadlines(w: &mut Writer, workspace_dir: Option<&str>, workspace: &str, start: NaiveDate, end: NaiveDate) -> Result<(), String> {
+    let store = match goals::open_store_for_read(workspace_dir)? { Some(c) => c, None => return Ok(()) };
+    for g in goals::list_goal_rows(&store, None)? {
+        if g.status == "cancelled" { continue; }
+        let due = match g.deadline.as_deref().and_then(|d| db_time(d, start)) { Some(t) => t, None => continue };
+        if due.date() < start || due.date() > end { continue; }
+        w.begin("VTODO", &native_uid("goal", due.date(), g.id, workspace), "goal");
+        w.text("SUMMARY", &g.title);
+        w.time("DUE", &due);
+        if g.status == "completed" { w.line("STATUS:COMPLETED"); }
+        w.line("END:VTODO");
+    }
+    Ok(())
+}
+
+/// Build a VCALENDAR for `start..=end` from the daily DBs in `workspace_dir` and the user's journals.
+pub fn export_ics(personality: &Connection, user_id: i64, workspace_dir: Option<&str>, start: NaiveDate, end: NaiveDate) -> Result<String, String> {
+    let mut w = Writer { out: String::new(), stamp: chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string() };
+    w.line("BEGIN:VCALENDAR");
+    w.line("VERSION:2.0");
+    w.line(&format!("PRODID:{}", PRODID));
+    w.line("CALSCALE:GREGORIAN");
+    let workspace = utility::workspace_id(workspace_dir)?;
+    for (day, path) in utility::list_daily_dbs(workspace_dir) {
+        if day < start || day > end { continue; }
+        let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+        export_day(&mut w, &conn, day, &workspace);
+    }
+    export_goal_deadlines(&mut w, workspace_dir, &workspace, start, end)?;
+    for e in journals::entries_between(personality, user_id, start, end)? {
+        let day = match NaiveDate::parse_from_str(&e.entry_date, "%Y-%m-%d") { Ok(d) => d, Err(_) => continue };
+        w.line("BEGIN:VJOURNAL");
+        w.line(&format!("UID:journal-{}.{}{}", e.id, workspace, UID_DOMAIN));
+        let stamp = w.stamp.clone();
+        w.line(&format!("DTSTAMP:{}", stamp));
+        w.line(&format!("{}:journal", KIND_PROP));
+        w.time("DTSTART", &IcsTime::Date(day));
+        w.text("SUMMARY", &format!("Journal {}", day));
+        w.text("DESCRIPTION", &e.content);
+        if !e.tags.is_empty() { w.line(&format!("CATEGORIES:{}", e.tags.iter().map(|t| escape_text(t)).collect::<Vec<_>>().join(","))); }
+        w.line("END:VJOURNAL");
+    }
+    w.line("END:VCALENDAR");
+    Ok(w.out)
+}
+
+// ---------- import ----------
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IcsImportReport {
+    pub events_inserted: usize,
+    pub events_updated: usize,
+    pub alarms_inserted: usize,
+    pub alarms_updated: usize,
+    pub reminders_inserted: usize,
+    pub reminders_updated: usize,
+    /// Components that were not imported, with the reason.
+    pub skipped: Vec<String>,
+}
+
+impl IcsImportReport {
+    fn count(&mut self, kind: &str, inserted: bool) {
+        let slot = match (kind, inserted) {
+            ("event", true) => &mut self.events_inserted,
+            ("event", false) => &mut self.events_updated,
+            ("alarm", true) => &mut self.alarms_inserted,
+            ("alarm", false) => &mut self.alarms_updated,
+            (_, true) => &mut self.reminders_inserted,
+            (_, false) => &mut self.reminders_updated,
+        };
+        *slot += 1;
+    }
+}
+
+pub fn ensure_import_table(conn: &Connection) -> Result<(), String> {
+    // Rows from before the workspace column cannot be attributed to a workspace, so they are
+    // dropped; re-importing those feeds inserts fresh rows.
+    let cols = utility::table_columns(conn, "ical_imports");
+    if !cols.is_empty() && !cols.contains("workspace") {
+        conn.execute("DROP TABLE ical_imports", []).map_err(|e| e.to_string())?;
+    }
+    conn.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS ical_imports (
+            workspace TEXT NOT NULL,
+            uid TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            entry_date TEXT NOT NULL,
+            row_id INTEGER NOT NULL,
+            imported_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (workspace, uid, kind)
+        );
+    "#).map_err(|e| e.to_string())
+}
+
+/// A row to write for one imported UID.
+#[derive(Clone)]
+enum ImportRow {
+    Event { summary: String, time: String, details: serde_json::Value },
+    Alarm { time: String, label: String },
+    Reminder { text: String, remind_at: Option<String> },
+}
+
+/// `new` merged over the stored details, so keys the calendar knows nothing about survive. A
+/// round-tripped native event carries its details as DESCRIPTION; that and the UID are not stored.
+fn merge_details(existing: Option<&str>, new: &serde_json::Value, native: bool) -> serde_json::Value {
+    let mut merged = existing.and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok()).filter(|v| v.is_object()).unwrap_or_else(|| serde_json::json!({}));
+    if let Some(fields) = new.as_object() {
+        for (k, v) in fields {
+            if native && (k == "ical_uid" || k == "description") { continue; }
+            merged[k] = v.clone();
+        }
+    }
+    merged
+}
+
+impl ImportRow {
+    fn kind(&self) -> &'static str {
+        match self { ImportRow::Event { .. } => "event", ImportRow::Alarm { .. } => "alarm", ImportRow::Reminder { .. } => "reminder" }
+    }
+
+    fn insert(&self, conn: &Connection) -> Result<i64, String> {
+        match self {
+            ImportRow::Event { summary, time, details } => conn.execute("INSERT INTO event (event_type, event_time, details_json) VALUES (?, ?, ?)", params![summary, time, details.to_string()]),
+            ImportRow::Alarm { time, label } => conn.execute("INSERT INTO alarm (alarm_time, label) VALUES (?, ?)", params![time, label]),
+            ImportRow::Reminder { text, remind_at } => conn.execute("INSERT INTO reminder (text, remind_at) VALUES (?, ?)", params![text, remind_at]),
+        }.map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn update(&self, conn: &Connection, id: i64) -> Result<usize, String> {
+        match self {
+            ImportRow::Event { summary, time, details } => conn.execute("UPDATE event SET event_type = ?, event_time = ?, details_json = ? WHERE id = ?", params![summary, time, details.to_string(), id]),
+            ImportRow::Alarm { time, label } => conn.execute("UPDATE alarm SET alarm_time = ?, label = ? WHERE id = ?", params![time, label, id]),
+            ImportRow::Reminder { text, remind_at } => conn.execute("UPDATE reminder SET text = ?, remind_at = ? WHERE id = ?", params![text, remind_at, id]),
+        }.map_err(|e| e.to_string())
+    }
+
+    /// This row combined with the stored row `id`, or `None` if that row is gone. Native events
+    /// keep their event type; the exported title only lands in `summary` when it was changed.
+    fn merged_with(&self, conn: &Connection, id: i64, native: bool) -> Result<Option<ImportRow>, String> {
+        let ImportRow::Event { summary, time, details } = self else {
+            let exists = conn.query_row(&format!("SELECT 1 FROM {} WHERE id = ?", self.kind()), params![id], |_| Ok(())).optional().map_err(|e| e.to_string())?;
+            return Ok(exists.map(|_| self.clone()));
+        };
+        let stored: Option<(Option<String>, Option<String>)> = conn.query_row(
+            "SELECT event_type, details_json FROM event WHERE id = ?", params![id], |r| Ok((r.get(0)?, r.get(1)?)),
+        ).optional().map_err(|e| e.to_string())?;
+        let (event_type, old) = match stored { Some(s) => s, None => return Ok(None) };
+        let mut merged = merge_details(old.as_deref(), details, native);
+        let summary = if native {
+            let event_type = event_type.unwrap_or_default();
+            let had_summary = old.as_deref().and_then(|o| serde_json::from_str::<serde_json::Value>(o).ok()).map(|v| v.get("summary").is_some()).unwrap_or(false);
+            if !had_summary && merged.get("summary").and_then(|v| v.as_str()) == Some(event_type.as_str()) {
+                if let Some(m) = merged.as_object_mut() { m.remove("summary"); }
+            }
+            event_type
+        } else {
+            summary.clone()
+        };
+        Ok(Some(ImportRow::Event { summary, time: time.clone(), details: merged }))
+    }
+}
+
+/// Insert or update the row for `uid`, moving it to another daily DB if its day changed.
+/// Returns true when a row was inserted.
+fn upsert(personality: &Connection, workspace_dir: Option<&str>, workspace: &str, uid: &str, day: NaiveDate, row: &ImportRow) -> Result<bool, String> {
+    let kind = row.kind();
+    let known: Option<(String, i64)> = personality.query_row(
+        "SELECT entry_date, row_id FROM ical_imports WHERE workspace = ? AND uid = ? AND kind = ?", params![workspace, uid, kind], |r| Ok((r.get(0)?, r.get(1)?)),
+    ).optional().map_err(|e| e.to_string())?;
+    // Items exported from this workspace carry their row in the UID.
+    let native = parse_native_uid(uid, workspace).filter(|(k, _, _)| k == kind);
+    let is_native = native.is_some();
+    let known = known.or_else(|| native.map(|(_, d, id)| (d.to_string(), id)));
+
+    let mut to_insert = row.clone();
+    if let Some((old_day, id)) = known {
+        let old_path = NaiveDate::parse_from_str(&old_day, "%Y-%m-%d").ok().and_then(|d| utility::daily_db_path(workspace_dir, d));
+        if let Some(old_path) = old_path {
+            let conn = Connection::open(old_path).map_err(|e| e.to_string())?;
+            if let Some(merged) = row.merged_with(&conn, id, is_native)? {
+                if old_day == day.to_string() && merged.update(&conn, id)? > 0 { return Ok(false); }
+                conn.execute(&format!("DELETE FROM {} WHERE id = ?", kind), params![id]).map_err(|e| e.to_string())?;
+                to_insert = merged;
+            }
+        }
+    }
+    let conn = Connection::open(utility::ensure_daily_db(workspace_dir, day)?).map_err(|e| e.to_string())?;
+    let id = to_insert.insert(&conn)?;
+    personality.execute(
+        "INSERT INTO ical_imports (workspace, uid, kind, entry_date, row_id) VALUES (?, ?, ?, ?, ?) \
+         ON CONFLICT(workspace, uid, kind) DO UPDATE SET entry_date = excluded.entry_date, row_id = excluded.row_id, imported_at = datetime('now')",
+        params![workspace, uid, kind, day.to_string(), id],
+    ).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Alarm time for a VALARM relative to its parent's start (or end, with RELATED=END).
+fn alarm_time(alarm: &IcsComponent, start: Option<IcsTime>, end: Option<IcsTime>) -> Option<NaiveDateTime> {
+    let trigger = alarm.prop("TRIGGER")?;
+    if trigger.param("VALUE").map(|v| v.eq_ignore_ascii_case("DATE-TIME")).unwrap_or(false) {
+        return parse_ics_time(trigger).map(|t| t.start_of());
+    }
+    let base = if trigger.param("RELATED").map(|v| v.eq_ignore_ascii_case("END")).unwrap_or(false) { end.or(start) } else { start }?;
+    Some(base.start_of() + parse_duration(&trigger.value)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn import_alarms(report: &mut IcsImportReport, personality: &Connection, workspace_dir: Option<&str>, workspace: &str, parent: &IcsComponent, uid: &str, start: Option<IcsTime>, end: Option<IcsTime>, fallback_label: &str) -> Result<(), String> {
+    for (n, alarm) in parent.children.iter().filter(|c| c.name == "VALARM").enumerate() {
+        let at = match alarm_time(alarm, start, end) {
+            Some(t) => t,
+            None => { report.skipped.push(format!("{}: VALARM {} has no usable TRIGGER", uid, n + 1)); continue; }
+        };
+        let label = alarm.text("DESCRIPTION").or_else(|| alarm.text("SUMMARY")).unwrap_or_else(|| fallback_label.to_string());
+        let row = ImportRow::Alarm { time: IcsTime::DateTime(at).to_db(), label };
+        let inserted = upsert(personality, workspace_dir, workspace, &format!("{}#alarm{}", uid, n + 1), at.date(), &row)?;
+        report.count("alarm", inserted);
+    }
+    Ok(())
+}
+
+/// Import the components of an iCalendar stream into the daily DBs of `workspace_dir`.
+pub fn import_ics(personality: &Connection, workspace_dir: Option<&str>, text: &str, today: NaiveDate) -> Result<IcsImportReport, String> {
+    ensure_import_table(personality)?;
+    let workspace = utility::workspace_id(workspace_dir)?;
+    let mut report = IcsImportReport::default();
+    for comp in parse_ics(text)? {
+        let uid = match comp.text("UID") {
+            Some(u) => u,
+            None => { report.skipped.push(format!("{} without UID", comp.name)); continue; }
+        };
+        let kind = comp.text(KIND_PROP).unwrap_or_default();
+        let summary = comp.text("SUMMARY").unwrap_or_default();
+        match comp.name.as_str() {
+            "VEVENT" if kind == "alarm" => {
+                let at = match comp.prop("DTSTART").and_then(parse_ics_time) { Some(t) => t, None => { report.skipped.push(format!("{}: no DTSTART", uid)); continue; } };
+                let row = ImportRow::Alarm { time: IcsTime::DateTime(at.start_of()).to_db(), label: summary };
+                let inserted = upsert(personality, workspace_dir, &workspace, &uid, at.date(), &row)?;
+                report.count("alarm", inserted);
+            }
+            "VEVENT" if kind == "session" => report.skipped.push(format!("{}: focus sessions are not imported", uid)),
+            "VEVENT" => {
+                let start = match comp.prop("DTSTART").and_then(parse_ics_time) { Some(t) => t, None => { report.skipped.push(format!("{}: no DTSTART", uid)); continue; } };
+                let end = comp.prop("DTEND").and_then(parse_ics_time)
+                    .or_else(|| comp.prop("DURATION").and_then(|d| parse_duration(&d.value)).map(|d| IcsTime::DateTime(start.start_of() + d)));
+                let mut details = serde_json::json!({ "ical_uid": uid, "summary": summary });
+                for (key, prop) in [("description", "DESCRIPTION"), ("location", "LOCATION")] {
+                    if let Some(v) = comp.text(prop) { details[key] = serde_json::Value::String(v); }
+                }
+                if let Some(e) = end { details["end"] = serde_json::Value::String(e.to_db()); }
+                let title = if summary.is_empty() { "calendar_event".to_string() } else { summary.clone() };
+                let row = ImportRow::Event { summary: title, time: start.to_db(), details };
+                let inserted = upsert(personality, workspace_dir, &workspace, &uid, start.date(), &row)?;
+                report.count("event", inserted);
+                import_alarms(&mut report, personality, workspace_dir, &workspace, &comp, &uid, Some(start), end, &summary)?;
+            }
+            "VTODO" if kind == "goal" => report.skipped.push(format!("{}: goal deadlines are not imported", uid)),
+            "VTODO" => {
+                let due = comp.prop("DUE").and_then(parse_ics_time).or_else(|| comp.prop("DTSTART").and_then(parse_ics_time));
+                let day = due.map(|d| d.date()).unwrap_or(today);
+                let row = ImportRow::Reminder { text: if summary.is_empty() { "Reminder".to_string() } else { summary.clone() }, remind_at: due.map(|d| d.to_db()) };
+                let inserted = upsert(personality, workspace_dir, &workspace, &uid, day, &row)?;
+                report.count("reminder", inserted);
+                let start = comp.prop("DTSTART").and_then(parse_ics_time).or(due);
+                import_alarms(&mut report, personality, workspace_dir, &workspace, &comp, &uid, start, due, &summary)?;
+            }
+            other => report.skipped.push(format!("{}: {} is not imported", uid, other)),
+        }
+    }
+    Ok(report)
+}
+
+fn parse_range_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(s).map(|dt| dt.date_naive()))
+        .map_err(|_| format!("Invalid date '{}'", s))
+}
+
+/// Export `start_iso..=end_iso` as iCalendar text; also writes it to `path` when given.
+#[tauri::command]
+pub fn export_calendar_ics(user_id: i64, workspace_dir: Option<String>, start_iso: String, end_iso: String, path: Option<String>) -> Result<String, String> {
+    let (start, end) = (parse_range_date(&start_iso)?, parse_range_date(&end_iso)?);
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    let ics = export_ics(&conn, user_id, workspace_dir.as_deref(), start, end)?;
+    if let Some(p) = path {
+        std::fs::write(&p, &ics).map_err(|e| format!("Failed to write {}: {}", p, e))?;
+    }
+    Ok(ics)
+}
+
+/// Import an `.ics` file (`path`) or iCalendar text (`content`).
+#[tauri::command]
+pub fn import_calendar_ics(workspace_dir: Option<String>, path: Option<String>, content: Option<String>) -> Result<IcsImportReport, String> {
+    let text = match (content, path) {
+        (Some(c), _) => c,
+        (None, Some(p)) => std::fs::read_to_string(&p).map_err(|e| format!("Failed to read {}: {}", p, e))?,
+        (None, None) => return Err("Either path or content is required".to_string()),
+    };
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    import_ics(&conn, workspace_dir.as_deref(), &text, chrono::Local::now().date_naive())
+}
@@ -1,7 +1,29 @@
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, params, OptionalExtension};
 use serde::{Serialize, Deserialize};
+use chrono::NaiveDate;
 use crate::backend::personality_db::PERSONALITY_DB_PATH;
 
+/// Revision sources recorded in `journal_revisions.source`.
+pub const SOURCE_GENERATED: &str = "generated";
+pub const SOURCE_REGENERATED: &str = "regenerated";
+pub const SOURCE_EDIT: &str = "edit";
+pub const SOURCE_RESTORE: &str = "restore";
+
+/// Generated text for a day that already has an entry. It is kept apart from the entry until the
+/// user accepts it, so regeneration never overwrites the user's own edits.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalDraft {
+    pub id: i64,
+    pub entry_id: i64,
+    pub provider: String,
+    pub model: Option<String>,
+    pub content: String,
+    pub tokens: Option<i64>,
+    /// Line diff against the entry's content when the draft was saved.
+    pub diff: String,
+    pub created_at: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JournalEntry {
     pub id: i64,
@@ -11,12 +33,98 @@ pub struct JournalEntry {
     pub model: Option<String>,
     pub content: String,
     pub tokens: Option<i64>,
+    /// Day the entry is about (`YYYY-MM-DD`); one entry per user and day.
+    #[serde(default)]
+    pub entry_date: String,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub current_revision: i64,
+    /// User-written reflection, kept separate from the generated text so regeneration never touches it.
+    #[serde(default)]
+    pub reflection: Option<String>,
+    /// 1-5 ratings.
+    #[serde(default)]
+    pub mood: Option<i64>,
+    #[serde(default)]
+    pub energy: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
-/// Ensure journals table exists
-#[tauri::command]
-pub fn init_journals_table() -> Result<(), String> {
-    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+/// One stored version of an entry's content with the line diff against the previous revision.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JournalRevision {
+    pub entry_id: i64,
+    pub revision: i64,
+    pub source: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub content: String,
+    /// Unified-style line diff (` `, `-`, `+` prefixes); empty for the first revision.
+    pub diff: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JournalAnnotations {
+    pub reflection: Option<String>,
+    pub mood: Option<i64>,
+    pub energy: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffOp { Same, Removed, Added }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Line diff from the longest common subsequence of `old` and `new`.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let (n, m) = (a.len(), b.len());
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let mut out = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            out.push(DiffLine { op: DiffOp::Same, text: a[i].to_string() });
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(DiffLine { op: DiffOp::Removed, text: a[i].to_string() });
+            i += 1;
+        } else {
+            out.push(DiffLine { op: DiffOp::Added, text: b[j].to_string() });
+            j += 1;
+        }
+    }
+    out.extend(a[i..].iter().map(|t| DiffLine { op: DiffOp::Removed, text: t.to_string() }));
+    out.extend(b[j..].iter().map(|t| DiffLine { op: DiffOp::Added, text: t.to_string() }));
+    out
+}
+
+pub fn format_diff(lines: &[DiffLine]) -> String {
+    lines.iter().map(|l| {
+        let prefix = match l.op { DiffOp::Same => ' ', DiffOp::Removed => '-', DiffOp::Added => '+' };
+        format!("{}{}", prefix, l.text)
+    }).collect::<Vec<_>>().join("\n")
+}
+
+/// Create the journal tables and bring older `journal_entries` tables up to date.
+pub fn ensure_journal_schema(conn: &Connection) -> Result<(), String> {
     conn.execute_batch(r#"
         CREATE TABLE IF NOT EXISTS journal_entries (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -27,69 +135,351 @@ pub fn init_journals_table() -> Result<(), String> {
             content TEXT NOT NULL,
             tokens INTEGER DEFAULT NULL
         );
+        CREATE TABLE IF NOT EXISTS journal_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL,
+            revision INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            provider TEXT,
+            model TEXT,
+            content TEXT NOT NULL,
+            diff TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(entry_id, revision)
+        );
+        CREATE TABLE IF NOT EXISTS journal_drafts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT,
+            content TEXT NOT NULL,
+            tokens INTEGER,
+            diff TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+    "#).map_err(|e| e.to_string())?;
+
+    let cols = crate::backend::utility::table_columns(conn, "journal_entries");
+    for (col, decl) in [
+        ("entry_date", "TEXT"),
+        ("updated_at", "TEXT"),
+        ("current_revision", "INTEGER NOT NULL DEFAULT 1"),
+        ("reflection", "TEXT"),
+        ("mood", "INTEGER"),
+        ("energy", "INTEGER"),
+        ("tags_json", "TEXT"),
+    ] {
+        if !cols.contains(col) {
+            conn.execute(&format!("ALTER TABLE journal_entries ADD COLUMN {} {}", col, decl), []).map_err(|e| e.to_string())?;
+        }
+    }
+    conn.execute_batch(r#"
+        UPDATE journal_entries SET entry_date = COALESCE(date(created_at, 'localtime'), substr(created_at, 1, 10)) WHERE entry_date IS NULL;
+        CREATE INDEX IF NOT EXISTS idx_journal_entries_user_date ON journal_entries(user_id, entry_date);
+        INSERT INTO journal_revisions (entry_id, revision, source, provider, model, content, diff, created_at)
+            SELECT j.id, 1, 'generated', j.provider, j.model, j.content, '', j.created_at FROM journal_entries j
+            WHERE NOT EXISTS (SELECT 1 FROM journal_revisions r WHERE r.entry_id = j.id);
     "#).map_err(|e| e.to_string())?;
+    crate::backend::search::ensure_journal_index(conn)?;
     Ok(())
 }
 
+/// Ensure journals table exists
 #[tauri::command]
-pub fn save_journal_entry(user_id: i64, provider: String, model: Option<String>, content: String, tokens: Option<i64>) -> Result<i64, String> {
+pub fn init_journals_table() -> Result<(), String> {
     let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    ensure_journal_schema(&conn)
+}
+
+const ENTRY_COLUMNS: &str = "id, user_id, created_at, provider, model, content, tokens, entry_date, updated_at, current_revision, reflection, mood, energy, tags_json";
+
+fn entry_from_row(r: &rusqlite::Row) -> rusqlite::Result<JournalEntry> {
+    let tags_json: Option<String> = r.get(13)?;
+    Ok(JournalEntry {
+        id: r.get(0)?,
+        user_id: r.get(1)?,
+        created_at: r.get(2)?,
+        provider: r.get(3)?,
+        model: r.get(4)?,
+        content: r.get(5)?,
+        tokens: r.get(6)?,
+        entry_date: r.get::<_, Option<String>>(7)?.unwrap_or_default(),
+        updated_at: r.get(8)?,
+        current_revision: r.get(9)?,
+        reflection: r.get(10)?,
+        mood: r.get(11)?,
+        energy: r.get(12)?,
+        tags: tags_json.and_then(|t| serde_json::from_str(&t).ok()).unwrap_or_default(),
+    })
+}
+
+pub fn read_entry(conn: &Connection, entry_id: i64) -> Result<Option<JournalEntry>, String> {
+    conn.query_row(&format!("SELECT {} FROM journal_entries WHERE id = ?", ENTRY_COLUMNS), params![entry_id], entry_from_row)
+        .optional().map_err(|e| e.to_string())
+}
+
+pub fn read_entries(conn: &Connection, user_id: i64, limit: i64) -> Result<Vec<JournalEntry>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM journal_entries WHERE user_id = ? ORDER BY entry_date DESC, created_at DESC LIMIT ?", ENTRY_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![user_id, limit], entry_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Entries whose `entry_date` falls in `start..=end`, oldest first.
+pub fn entries_between(conn: &Connection, user_id: i64, start: NaiveDate, end: NaiveDate) -> Result<Vec<JournalEntry>, String> {
+    ensure_journal_schema(conn)?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM journal_entries WHERE user_id = ? AND entry_date BETWEEN ? AND ? ORDER BY entry_date, id", ENTRY_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![user_id, start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string()], entry_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn add_revision(conn: &Connection, entry_id: i64, source: &str, provider: Option<&str>, model: Option<&str>, content: &str) -> Result<i64, String> {
+    let (current, previous): (i64, String) = conn.query_row(
+        "SELECT current_revision, content FROM journal_entries WHERE id = ?", params![entry_id], |r| Ok((r.get(0)?, r.get(1)?)),
+    ).optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("Journal entry {} not found", entry_id))?;
+    let revision = current + 1;
+    let diff = format_diff(&line_diff(&previous, content));
+    conn.execute(
+        "INSERT INTO journal_revisions (entry_id, revision, source, provider, model, content, diff) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![entry_id, revision, source, provider, model, content, diff],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE journal_entries SET content = ?, current_revision = ?, updated_at = datetime('now') WHERE id = ?",
+        params![content, revision, entry_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(revision)
+}
+
+/// Save generated content for `user_id` on `day`. The first save creates the entry; later saves for
+/// the same day (regeneration) are stored as drafts that become a revision only once accepted.
+/// Returns (entry id, draft id).
+pub fn save_generated(conn: &Connection, user_id: i64, day: NaiveDate, provider: &str, model: Option<&str>, content: &str, tokens: Option<i64>) -> Result<(i64, Option<i64>), String> {
+    ensure_journal_schema(conn)?;
+    let date = day.format("%Y-%m-%d").to_string();
+    let existing: Option<i64> = conn.query_row(
+        "SELECT id FROM journal_entries WHERE user_id = ? AND entry_date = ? ORDER BY id LIMIT 1", params![user_id, date], |r| r.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+    if let Some(id) = existing {
+        let current: String = conn.query_row("SELECT content FROM journal_entries WHERE id = ?", params![id], |r| r.get(0)).map_err(|e| e.to_string())?;
+        if current == content { return Ok((id, None)); }
+        conn.execute(
+            "INSERT INTO journal_drafts (entry_id, provider, model, content, tokens, diff) VALUES (?, ?, ?, ?, ?, ?)",
+            params![id, provider, model, content, tokens, format_diff(&line_diff(&current, content))],
+        ).map_err(|e| e.to_string())?;
+        return Ok((id, Some(conn.last_insert_rowid())));
+    }
     conn.execute(
-        "INSERT INTO journal_entries (user_id, created_at, provider, model, content, tokens) VALUES (?, datetime('now'), ?, ?, ?, ?)",
-        params![user_id, provider, model, content, tokens],
+        "INSERT INTO journal_entries (user_id, created_at, provider, model, content, tokens, entry_date, current_revision) VALUES (?, datetime('now'), ?, ?, ?, ?, ?, 1)",
+        params![user_id, provider, model, content, tokens, date],
     ).map_err(|e| e.to_string())?;
     let id = conn.last_insert_rowid();
-    Ok(id)
+    conn.execute(
+        "INSERT INTO journal_revisions (entry_id, revision, source, provider, model, content) VALUES (?, 1, ?, ?, ?, ?)",
+        params![id, SOURCE_GENERATED, provider, model, content],
+    ).map_err(|e| e.to_string())?;
+    Ok((id, None))
+}
+
+pub fn list_drafts(conn: &Connection, entry_id: i64) -> Result<Vec<JournalDraft>, String> {
+    ensure_journal_schema(conn)?;
+    let mut stmt = conn.prepare("SELECT id, entry_id, provider, model, content, tokens, diff, created_at FROM journal_drafts WHERE entry_id = ? ORDER BY id").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![entry_id], |r| Ok(JournalDraft {
+        id: r.get(0)?, entry_id: r.get(1)?, provider: r.get(2)?, model: r.get(3)?, content: r.get(4)?,
+        tokens: r.get(5)?, diff: r.get(6)?, created_at: r.get(7)?,
+    })).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn draft_entry(conn: &Connection, draft_id: i64) -> Result<i64, String> {
+    ensure_journal_schema(conn)?;
+    conn.query_row("SELECT entry_id FROM journal_drafts WHERE id = ?", params![draft_id], |r| r.get(0))
+        .optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("Journal draft {} not found", draft_id))
+}
+
+/// Make a draft the entry's content as a `regenerated` revision. Returns the new revision number.
+pub fn accept_draft(conn: &Connection, draft_id: i64) -> Result<i64, String> {
+    let entry_id = draft_entry(conn, draft_id)?;
+    let draft = list_drafts(conn, entry_id)?.into_iter().find(|d| d.id == draft_id).ok_or_else(|| format!("Journal draft {} not found", draft_id))?;
+    let revision = add_revision(conn, entry_id, SOURCE_REGENERATED, Some(&draft.provider), draft.model.as_deref(), &draft.content)?;
+    conn.execute("UPDATE journal_entries SET provider = ?, model = ?, tokens = ? WHERE id = ?", params![draft.provider, draft.model, draft.tokens, entry_id]).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM journal_drafts WHERE id = ?", params![draft_id]).map_err(|e| e.to_string())?;
+    Ok(revision)
+}
+
+pub fn discard_draft(conn: &Connection, draft_id: i64) -> Result<(), String> {
+    draft_entry(conn, draft_id)?;
+    conn.execute("DELETE FROM journal_drafts WHERE id = ?", params![draft_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Fails unless `entry_id` exists and belongs to `user_id`.
+pub fn check_owner(conn: &Connection, user_id: i64, entry_id: i64) -> Result<(), String> {
+    ensure_journal_schema(conn)?;
+    match read_entry(conn, entry_id)? {
+        Some(e) if e.user_id == user_id => Ok(()),
+        _ => Err(format!("Journal entry {} not found", entry_id)),
+    }
+}
+
+/// Replace an entry's content with the user's edit. Unchanged content does not create a revision.
+pub fn edit_entry(conn: &Connection, entry_id: i64, content: &str) -> Result<i64, String> {
+    ensure_journal_schema(conn)?;
+    let entry = read_entry(conn, entry_id)?.ok_or_else(|| format!("Journal entry {} not found", entry_id))?;
+    if entry.content == content { return Ok(entry.current_revision); }
+    if content.trim().is_empty() { return Err("Journal content cannot be empty".to_string()); }
+    add_revision(conn, entry_id, SOURCE_EDIT, None, None, content)
+}
+
+/// Make an earlier revision current again (recorded as a new revision).
+pub fn restore_revision(conn: &Connection, entry_id: i64, revision: i64) -> Result<i64, String> {
+    ensure_journal_schema(conn)?;
+    let content: String = conn.query_row(
+        "SELECT content FROM journal_revisions WHERE entry_id = ? AND revision = ?", params![entry_id, revision], |r| r.get(0),
+    ).optional().map_err(|e| e.to_string())?.ok_or_else(|| format!("Revision {} of entry {} not found", revision, entry_id))?;
+    add_revision(conn, entry_id, SOURCE_RESTORE, None, None, &content)
+}
+
+pub fn list_revisions(conn: &Connection, entry_id: i64) -> Result<Vec<JournalRevision>, String> {
+    ensure_journal_schema(conn)?;
+    let mut stmt = conn.prepare("SELECT entry_id, revision, source, provider, model, content, diff, created_at FROM journal_revisions WHERE entry_id = ? ORDER BY revision").map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![entry_id], |r| Ok(JournalRevision {
+        entry_id: r.get(0)?, revision: r.get(1)?, source: r.get(2)?, provider: r.get(3)?, model: r.get(4)?,
+        content: r.get(5)?, diff: r.get(6)?, created_at: r.get(7)?,
+    })).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for t in tags {
+        let t = t.trim().trim_start_matches('#').trim().to_lowercase();
+        if !t.is_empty() && !out.contains(&t) { out.push(t); }
+    }
+    out
+}
+
+/// Store the user's reflection, ratings and tags. These never create content revisions.
+pub fn set_annotations(conn: &Connection, entry_id: i64, a: &JournalAnnotations) -> Result<JournalEntry, String> {
+    ensure_journal_schema(conn)?;
+    for (name, v) in [("mood", a.mood), ("energy", a.energy)] {
+        if let Some(v) = v {
+            if !(1..=5).contains(&v) { return Err(format!("{} must be between 1 and 5", name)); }
+        }
+    }
+    let reflection = a.reflection.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let tags = serde_json::to_string(&normalize_tags(&a.tags)).map_err(|e| e.to_string())?;
+    let n = conn.execute(
+        "UPDATE journal_entries SET reflection = ?, mood = ?, energy = ?, tags_json = ?, updated_at = datetime('now') WHERE id = ?",
+        params![reflection, a.mood, a.energy, tags, entry_id],
+    ).map_err(|e| e.to_string())?;
+    if n == 0 { return Err(format!("Journal entry {} not found", entry_id)); }
+    read_entry(conn, entry_id)?.ok_or_else(|| format!("Journal entry {} not found", entry_id))
+}
+
+/// Outcome of saving generated text: the day's entry, and the draft the text went to when the
+/// entry already existed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedJournal {
+    pub entry_id: i64,
+    pub draft_id: Option<i64>,
+}
+
+impl SavedJournal {
+    /// `AiResult` message and code. A draft leaves the visible entry unchanged, so it is reported
+    /// with code `draft` for the UI to offer accepting it.
+    pub fn status(&self) -> (String, Option<String>) {
+        match self.draft_id {
+            Some(draft) => (format!("draft: {} (entry {} unchanged)", draft, self.entry_id), Some("draft".to_string())),
+            None => (format!("saved: {}", self.entry_id), None),
+        }
+    }
+}
+
+/// Saves today's entry; if one already exists the content is kept as a draft (see `save_generated`).
+#[tauri::command]
+pub fn save_journal_entry(user_id: i64, provider: String, model: Option<String>, content: String, tokens: Option<i64>) -> Result<SavedJournal, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    let (entry_id, draft_id) = save_generated(&conn, user_id, chrono::Local::now().date_naive(), &provider, model.as_deref(), &content, tokens)?;
+    Ok(SavedJournal { entry_id, draft_id })
 }
 
 #[tauri::command]
 pub fn list_journal_entries(user_id: i64, limit: Option<i64>) -> Result<Vec<JournalEntry>, String> {
     let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
-    let lim = limit.unwrap_or(50);
-    let mut stmt = conn.prepare("SELECT id, user_id, created_at, provider, model, content, tokens FROM journal_entries WHERE user_id = ? ORDER BY created_at DESC LIMIT ?").map_err(|e| e.to_string())?;
-    let mut rows = stmt.query(params![user_id, lim]).map_err(|e| e.to_string())?;
-    let mut res = Vec::new();
-    while let Some(r) = rows.next().map_err(|e| e.to_string())? {
-        let id: i64 = r.get(0).map_err(|e| e.to_string())?;
-        let user_id: i64 = r.get(1).map_err(|e| e.to_string())?;
-        let created_at: String = r.get(2).map_err(|e| e.to_string())?;
-        let provider: String = r.get(3).map_err(|e| e.to_string())?;
-        let model: Option<String> = r.get(4).map_err(|e| e.to_string())?;
-        let content: String = r.get(5).map_err(|e| e.to_string())?;
-        let tokens: Option<i64> = r.get(6).map_err(|e| e.to_string())?;
-        res.push(JournalEntry { id, user_id, created_at, provider, model, content, tokens });
-    }
-    Ok(res)
+    ensure_journal_schema(&conn)?;
+    read_entries(&conn, user_id, limit.unwrap_or(50))
 }
 
 #[tauri::command]
 pub fn get_journal_entry(entry_id: i64) -> Result<Option<JournalEntry>, String> {
     let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT id, user_id, created_at, provider, model, content, tokens FROM journal_entries WHERE id = ?").map_err(|e| e.to_string())?;
-    let mut rows = stmt.query(params![entry_id]).map_err(|e| e.to_string())?;
-    if let Some(r) = rows.next().map_err(|e| e.to_string())? {
-        let id: i64 = r.get(0).map_err(|e| e.to_string())?;
-        let user_id: i64 = r.get(1).map_err(|e| e.to_string())?;
-        let created_at: String = r.get(2).map_err(|e| e.to_string())?;
-        let provider: String = r.get(3).map_err(|e| e.to_string())?;
-        let model: Option<String> = r.get(4).map_err(|e| e.to_string())?;
-        let content: String = r.get(5).map_err(|e| e.to_string())?;
-        let tokens: Option<i64> = r.get(6).map_err(|e| e.to_string())?;
-        Ok(Some(JournalEntry { id, user_id, created_at, provider, model, content, tokens }))
-    } else {
-        Ok(None)
-    }
+    ensure_journal_schema(&conn)?;
+    read_entry(&conn, entry_id)
+}
+
+/// Save a user edit of the entry text. Returns the new revision number.
+#[tauri::command]
+pub fn edit_journal_entry(user_id: i64, entry_id: i64, content: String) -> Result<i64, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, entry_id)?;
+    edit_entry(&conn, entry_id, &content)
+}
+
+#[tauri::command]
+pub fn list_journal_revisions(user_id: i64, entry_id: i64) -> Result<Vec<JournalRevision>, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, entry_id)?;
+    list_revisions(&conn, entry_id)
+}
+
+#[tauri::command]
+pub fn restore_journal_revision(user_id: i64, entry_id: i64, revision: i64) -> Result<i64, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, entry_id)?;
+    restore_revision(&conn, entry_id, revision)
+}
+
+#[tauri::command]
+pub fn annotate_journal_entry(user_id: i64, entry_id: i64, annotations: JournalAnnotations) -> Result<JournalEntry, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, entry_id)?;
+    set_annotations(&conn, entry_id, &annotations)
+}
+
+#[tauri::command]
+pub fn list_journal_drafts(user_id: i64, entry_id: i64) -> Result<Vec<JournalDraft>, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, entry_id)?;
+    list_drafts(&conn, entry_id)
+}
+
+/// Accept a regenerated draft as the entry's new content. Returns the new revision number.
+#[tauri::command]
+pub fn accept_journal_draft(user_id: i64, draft_id: i64) -> Result<i64, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, draft_entry(&conn, draft_id)?)?;
+    accept_draft(&conn, draft_id)
+}
+
+#[tauri::command]
+pub fn discard_journal_draft(user_id: i64, draft_id: i64) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    check_owner(&conn, user_id, draft_entry(&conn, draft_id)?)?;
+    discard_draft(&conn, draft_id)
+}
+
+/// Day number for the entry being written on `today`: distinct earlier days with an entry, plus one.
+/// Regenerating today's entry therefore keeps its day number.
+pub fn day_count_on(conn: &Connection, user_id: i64, today: NaiveDate) -> Result<i64, String> {
+    ensure_journal_schema(conn)?;
+    let cnt: i64 = conn.query_row(
+        "SELECT COUNT(DISTINCT entry_date) FROM journal_entries WHERE user_id = ? AND entry_date < ?",
+        params![user_id, today.format("%Y-%m-%d").to_string()], |r| r.get(0),
+    ).map_err(|e| e.to_string())?;
+    Ok(cnt + 1)
 }
 
 // Helper: compute day count for a user from journal entries
 pub fn compute_day_count(user_id: i64) -> Result<i64, String> {
     let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT COUNT(*) FROM journal_entries WHERE user_id = ?").map_err(|e| e.to_string())?;
-    let mut rows = stmt.query(params![user_id]).map_err(|e| e.to_string())?;
-    if let Some(r) = rows.next().map_err(|e| e.to_string())? {
-        let cnt: i64 = r.get(0).map_err(|e| e.to_string())?;
-        Ok(cnt + 1) // next day count
-    } else {
-        Ok(1)
-    }
+    day_count_on(&conn, user_id, chrono::Local::now().date_naive())
 }
@@ -0,0 +1,190 @@
+//! Offline journal generator: a deterministic, rule-based entry built from the same daily data the
+//! prompt assembler collects. Used when the user has not opted into AI or the provider fails, so
+//! the journal history has no gaps.
+use serde::{Serialize, Deserialize};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Duration as ChronoDuration};
+use std::collections::HashMap;
+
+use crate::backend::distractions::{self, DistractionLog};
+use crate::backend::goals::{self, GoalRef};
+use crate::backend::sessions::{self, SessionLog};
+
+/// Provider name recorded on locally generated journal entries.
+pub const LOCAL_PROVIDER: &str = "local";
+/// Goals due within this many days are called out.
+const DEADLINE_WINDOW_DAYS: i64 = 3;
+const TOP_REASONS: usize = 3;
+
+/// Totals for one day.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DayStats {
+    pub focus_minutes: i64,
+    pub session_count: usize,
+    /// (label, minutes, start) of the longest session.
+    pub longest_session: Option<(String, i64, String)>,
+    pub distraction_count: usize,
+    /// Most common distraction reasons with their counts, most frequent first.
+    pub top_reasons: Vec<(String, usize)>,
+}
+
+/// Parse the timestamp layouts found in daily DBs (RFC 3339, `YYYY-MM-DD[T ]HH:MM[:SS]`, `HH:MM`).
+pub fn parse_timestamp(s: &str, day: NaiveDate) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if s.is_empty() { return None; }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) { return Some(dt.naive_local()); }
+    for fmt in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) { return Some(dt); }
+    }
+    for fmt in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(t) = NaiveTime::parse_from_str(s, fmt) { return Some(day.and_time(t)); }
+    }
+    None
+}
+
+fn session_minutes(s: &SessionLog, day: NaiveDate) -> Option<i64> {
+    let start = parse_timestamp(&s.start, day)?;
+    let end = parse_timestamp(&s.end, day)?;
+    let mins = (end - start).num_minutes();
+    if mins > 0 { Some(mins) } else { None }
+}
+
+fn session_name(s: &SessionLog) -> String {
+    let name = if !s.label.trim().is_empty() { s.label.trim() } else { s.description.trim() };
+    if name.is_empty() { "Untitled session".to_string() } else { name.to_string() }
+}
+
+fn clock(s: &str, day: NaiveDate) -> String {
+    parse_timestamp(s, day).map(|t| t.format("%H:%M").to_string()).unwrap_or_else(|| s.to_string())
+}
+
+pub fn day_stats(day: NaiveDate, sessions: &[SessionLog], distractions: &[DistractionLog]) -> DayStats {
+    let mut stats = DayStats { session_count: sessions.len(), distraction_count: distractions.len(), ..DayStats::default() };
+    for s in sessions {
+        if let Some(m) = session_minutes(s, day) {
+            stats.focus_minutes += m;
+            if stats.longest_session.as_ref().map(|(_, best, _)| m > *best).unwrap_or(true) {
+                stats.longest_session = Some((session_name(s), m, clock(&s.start, day)));
+            }
+        }
+    }
+    let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+    for d in distractions {
+        let reason = if !d.reason.trim().is_empty() { d.reason.trim() } else { d.label.trim() };
+        if reason.is_empty() { continue; }
+        let e = counts.entry(reason.to_lowercase()).or_insert_with(|| (reason.to_string(), 0));
+        e.1 += 1;
+    }
+    let mut reasons: Vec<(String, usize)> = counts.into_values().collect();
+    reasons.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    reasons.truncate(TOP_REASONS);
+    stats.top_reasons = reasons;
+    stats
+}
+
+fn hm(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Goals with a deadline between `day` and `day + DEADLINE_WINDOW_DAYS` (overdue ones included).
+pub fn goals_near_deadline(goals: &[GoalRef], day: NaiveDate) -> Vec<(GoalRef, i64)> {
+    let mut out: Vec<(GoalRef, i64)> = goals.iter().filter_map(|g| {
+        let deadline = parse_timestamp(&g.deadline, day).map(|d| d.date())
+            .or_else(|| NaiveDate::parse_from_str(g.deadline.trim(), "%Y-%m-%d").ok())?;
+        let days = (deadline - day).num_days();
+        if days <= DEADLINE_WINDOW_DAYS { Some((g.clone(), days)) } else { None }
+    }).collect();
+    out.sort_by_key(|(_, d)| *d);
+    out
+}
+
+/// Render the journal entry in the same section layout the AI journal uses.
+pub fn render_local_journal(day_count: i64, day: NaiveDate, today: &DayStats, yesterday: &DayStats, goals_due: &[(GoalRef, i64)]) -> String {
+    let mut out = format!("## Day {}\n\n## Journal Entry\n", day_count);
+    if today.session_count == 0 && today.distraction_count == 0 {
+        out.push_str(&format!("I didn't log any focus sessions or distractions on {}.", day.format("%A, %B %-d")));
+    } else {
+        out.push_str(&format!("On {} I focused for {} across {} session{}.", day.format("%A, %B %-d"), hm(today.focus_minutes), today.session_count, if today.session_count == 1 { "" } else { "s" }));
+        if let Some((name, mins, start)) = &today.longest_session {
+            out.push_str(&format!(" My longest stretch was \"{}\" at {} ({}).", name, start, hm(*mins)));
+        }
+        match today.distraction_count {
+            0 => out.push_str(" I didn't log any distractions."),
+            n => {
+                let reasons: Vec<String> = today.top_reasons.iter().map(|(r, c)| if *c > 1 { format!("{} ({}×)", r, c) } else { r.clone() }).collect();
+                out.push_str(&format!(" I was distracted {} time{}", n, if n == 1 { "" } else { "s" }));
+                if reasons.is_empty() { out.push('.'); } else { out.push_str(&format!(", mostly by {}.", reasons.join(", "))); }
+            }
+        }
+    }
+
+    out.push_str("\n\n## Feedback\n");
+    let delta = today.focus_minutes - yesterday.focus_minutes;
+    if yesterday.session_count == 0 && yesterday.focus_minutes == 0 {
+        out.push_str("There is no data from yesterday to compare against.");
+    } else if delta > 0 {
+        out.push_str(&format!("That is {} more focus time than yesterday ({}).", hm(delta), hm(yesterday.focus_minutes)));
+    } else if delta < 0 {
+        out.push_str(&format!("That is {} less focus time than yesterday ({}).", hm(-delta), hm(yesterday.focus_minutes)));
+    } else {
+        out.push_str(&format!("Focus time matched yesterday ({}).", hm(yesterday.focus_minutes)));
+    }
+    let dd = today.distraction_count as i64 - yesterday.distraction_count as i64;
+    if dd != 0 && (today.distraction_count > 0 || yesterday.distraction_count > 0) {
+        out.push_str(&format!(" Distractions went {} from {} to {}.", if dd > 0 { "up" } else { "down" }, yesterday.distraction_count, today.distraction_count));
+    }
+
+    out.push_str("\n\n## Advice for Tomorrow\n");
+    if goals_due.is_empty() {
+        out.push_str("- No goals are due in the next few days.\n");
+    } else {
+        for (g, days) in goals_due {
+            let when = match *days {
+                d if d < 0 => format!("was due {} day{} ago", -d, if d == -1 { "" } else { "s" }),
+                0 => "is due today".to_string(),
+                1 => "is due tomorrow".to_string(),
+                d => format!("is due in {} days", d),
+            };
+            out.push_str(&format!("- \"{}\" {} ({}). Block time for it first.\n", g.title, when, g.deadline));
+        }
+    }
+    if let Some((reason, _)) = today.top_reasons.first() {
+        out.push_str(&format!("- Plan around \"{}\" before your first session.\n", reason));
+    }
+    if today.session_count > 0 {
+        if let Some((_, mins, _)) = &today.longest_session {
+            out.push_str(&format!("- Aim for at least one uninterrupted session of {} again.\n", hm(*mins)));
+        }
+    } else {
+        out.push_str("- Start with one short focus session to get back into tracking.\n");
+    }
+    out.push_str("\n_Generated offline from your tracked data._\n");
+    out
+}
+
+/// Build the local journal for `day` from the daily DBs in `workspace_dir`.
+pub fn build_local_journal(day_count: i64, workspace_dir: Option<&str>, day: NaiveDate) -> Result<String, String> {
+    let yesterday = day - ChronoDuration::days(1);
+    let today_stats = day_stats(day, &sessions::get_sessions_for_date(workspace_dir, day)?, &distractions::get_distractions_for_date(workspace_dir, day)?);
+    let yesterday_stats = day_stats(yesterday, &sessions::get_sessions_for_date(workspace_dir, yesterday)?, &distractions::get_distractions_for_date(workspace_dir, yesterday)?);
+    let goals = goals::get_pending_goal_refs(workspace_dir.map(|s| s.to_string()))?;
+    Ok(render_local_journal(day_count, day, &today_stats, &yesterday_stats, &goals_near_deadline(&goals, day)))
+}
+
+/// Generate today's local journal and save it with provider `local`. Returns (saved entry or draft, content).
+pub fn save_local_journal(user_id: i64, workspace_dir: Option<&str>) -> Result<(crate::backend::journals::SavedJournal, String), String> {
+    let day_count = crate::backend::journals::compute_day_count(user_id)?;
+    let content = build_local_journal(day_count, workspace_dir, chrono::Local::now().date_naive())?;
+    let saved = crate::backend::journals::save_journal_entry(user_id, LOCAL_PROVIDER.to_string(), None, content.clone(), None)?;
+    Ok((saved, content))
+}
+
+#[tauri::command]
+pub fn generate_local_journal_entry(user_id: i64, workspace_dir: Option<String>) -> Result<crate::backend::ai_provider::AiResult, String> {
+    let (saved, content) = save_local_journal(user_id, workspace_dir.as_deref())?;
+    let (message, code) = saved.status();
+    Ok(crate::backend::ai_provider::AiResult { success: true, message: Some(message), content: Some(content), code, safety: None })
+}
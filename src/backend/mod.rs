@@ -1,20 +1,38 @@
 pub mod prompt_assembler;
+pub mod prompt_guard;
+pub mod coach_chat;
+pub mod ai_tools;
+pub mod planner;
+pub mod local_journal;
+pub mod search;
+pub mod retrospective;
+pub mod vault_export;
+pub mod ical;
+pub mod recurrence;
 pub mod personality_db;
 pub mod personality_questions;
 pub mod migration;
 
 pub mod journals;
 pub mod safety;
+pub mod privacy;
 
 /// Backend module: exposes all domain modules for cards, sessions, events, distractions, AI, analytics, alarms, goals, settings, and utility.
 pub mod cards;
 pub mod sessions;
 pub mod events;
 pub mod distractions;
+pub mod distraction_analytics;
 pub mod ai;
 pub mod analytics;
+pub mod forecast;
 pub mod alarms;
 pub mod goals;
+pub mod goal_hierarchy;
+pub mod goal_priority;
+pub mod goal_time;
+pub mod habits;
+pub mod streaks;
 pub mod settings;
 pub mod utility;
 pub mod ai_provider;
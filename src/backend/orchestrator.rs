@@ -2,21 +2,61 @@ use serde::{Serialize, Deserialize};
 use rusqlite::{Connection, params};
 use crate::backend::utility;
 use crate::backend::journals;
-use chrono::{NaiveDate, Duration as ChronoDuration};
+use crate::backend::recurrence;
+use crate::backend::local_journal;
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Duration as ChronoDuration};
 use std::path::PathBuf;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CalendarItem {
-    pub kind: String, // "event" | "alarm" | "reminder" | "journal"
+    pub kind: String, // "event" | "alarm" | "reminder" | "journal" | "session" | "scheduled_session"
     pub time: String,
     pub title: String,
     pub details: Option<String>,
+    /// End time for items that take up time (sessions, events with an end or duration).
+    #[serde(default)]
+    pub end: Option<String>,
 }
 
 /// Return events, alarms, and reminders for a date range (inclusive).
 /// Scans each daily DB file between start_iso and end_iso (both inclusive) and aggregates results.
+/// Recurring items are included for `user_id` when given.
 #[tauri::command]
-pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_iso: String) -> Result<Vec<CalendarItem>, String> {
+pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_iso: String, user_id: Option<i64>) -> Result<Vec<CalendarItem>, String> {
+    let mut out = match collect_calendar_items(workspace_dir, user_id, &start_iso, &end_iso)? {
+        Some(items) => items,
+        // If no DBs found, return demo fallback
+        None => return Ok(vec![
+            CalendarItem { kind: "event".to_string(), time: "2025-09-10T10:00:00".to_string(), title: "Client meeting".to_string(), details: Some("Discuss roadmap".to_string()), end: Some("2025-09-10T11:00:00".to_string()) },
+            CalendarItem { kind: "alarm".to_string(), time: "2025-09-10T07:30:00".to_string(), title: "Wake up".to_string(), details: None, end: None },
+            CalendarItem { kind: "reminder".to_string(), time: "".to_string(), title: "Pay bills".to_string(), details: None, end: None },
+        ]),
+    };
+
+    // Optionally include latest few journal entries once (from personality DB)
+    if let Ok(entries) = journals::list_journal_entries(0, Some(5)) {
+        for e in entries {
+            out.push(CalendarItem { kind: "journal".to_string(), time: e.created_at.clone(), title: format!("Journal: {}", e.provider), details: Some(e.content), end: None });
+        }
+    }
+
+    Ok(out)
+}
+
+/// End of an event from its details JSON: an explicit `end`, or `duration_minutes` after the start.
+fn event_end(time: &str, details: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(details).ok()?;
+    if let Some(end) = v.get("end").and_then(|e| e.as_str()) { return Some(end.to_string()); }
+    let minutes = v.get("duration_minutes").and_then(|m| m.as_i64())?;
+    let start = local_journal::parse_timestamp(time, chrono::Local::now().date_naive())?;
+    Some((start + ChronoDuration::minutes(minutes)).format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+/// Everything scheduled in the range: daily-DB events, alarms, reminders, sessions and scheduled
+/// sessions plus `user_id`'s recurring occurrences (none without a user). `None` when there is no
+/// daily DB and nothing recurs.
+pub fn collect_calendar_items(workspace_dir: Option<String>, user_id: Option<i64>, start_iso: &str, end_iso: &str) -> Result<Option<Vec<CalendarItem>>, String> {
     // Parse start and end into NaiveDate. Accept YYYY-MM-DD or RFC3339 timestamps.
     let parse_date = |s: &str| -> Option<NaiveDate> {
         if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") { return Some(d); }
@@ -24,8 +64,8 @@ pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_
         None
     };
 
-    let start_date = parse_date(&start_iso);
-    let end_date = parse_date(&end_iso);
+    let start_date = parse_date(start_iso);
+    let end_date = parse_date(end_iso);
 
     // If parsing fails, fall back to single-db behaviour using utility::find_daily_db(None)
     let db_paths: Vec<PathBuf> = if let (Some(start), Some(end)) = (start_date, end_date) {
@@ -58,13 +98,17 @@ pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_
         }
     };
 
-    // If no DBs found, return demo fallback
-    if db_paths.is_empty() {
-        return Ok(vec![
-            CalendarItem { kind: "event".to_string(), time: "2025-09-10T10:00:00".to_string(), title: "Client meeting".to_string(), details: Some("Discuss roadmap".to_string()) },
-            CalendarItem { kind: "alarm".to_string(), time: "2025-09-10T07:30:00".to_string(), title: "Wake up".to_string(), details: None },
-            CalendarItem { kind: "reminder".to_string(), time: "".to_string(), title: "Pay bills".to_string(), details: None },
-        ]);
+    // Recurring alarms/reminders/events are defined in the personality DB and expanded for the range.
+    let recurring: Vec<recurrence::Occurrence> = match (start_date, end_date, user_id) {
+        (Some(start), Some(end), Some(user_id)) => Connection::open(PERSONALITY_DB_PATH).ok()
+            .filter(recurrence::has_tables)
+            .and_then(|conn| recurrence::occurrences_between(&conn, Some(user_id), start.and_time(chrono::NaiveTime::MIN), end.and_hms_opt(23, 59, 59)?).ok())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    if db_paths.is_empty() && recurring.is_empty() {
+        return Ok(None);
     }
 
     let mut out: Vec<CalendarItem> = Vec::new();
@@ -90,7 +134,8 @@ pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_
                     let time: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
                     let title: String = r.get::<_, Option<String>>(1).unwrap_or(None).unwrap_or_default();
                     let details: String = r.get::<_, Option<String>>(2).unwrap_or(None).unwrap_or_default();
-                    out.push(CalendarItem { kind: "event".to_string(), time, title, details: Some(details) });
+                    let end = event_end(&time, &details);
+                    out.push(CalendarItem { kind: "event".to_string(), time, title, details: Some(details), end });
                 }
             }
 
@@ -112,7 +157,7 @@ pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_
                 while let Ok(Some(r)) = rows.next() {
                     let time: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
                     let label: String = r.get::<_, Option<String>>(1).unwrap_or(None).unwrap_or_default();
-                    out.push(CalendarItem { kind: "alarm".to_string(), time, title: label, details: None });
+                    out.push(CalendarItem { kind: "alarm".to_string(), time, title: label, details: None, end: None });
                 }
             }
 
@@ -121,18 +166,168 @@ pub fn get_calendar_range(workspace_dir: Option<String>, start_iso: String, end_
                 let mut rows = stmt.query(params![start_iso, end_iso]).map_err(|e| e.to_string())?;
                 while let Ok(Some(r)) = rows.next() {
                     let text: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
-                    out.push(CalendarItem { kind: "reminder".to_string(), time: "".to_string(), title: text, details: None });
+                    out.push(CalendarItem { kind: "reminder".to_string(), time: "".to_string(), title: text, details: None, end: None });
+                }
+            }
+
+            // SESSIONS (recorded) and SCHEDULED SESSIONS (planned)
+            if let Ok(mut stmt) = conn.prepare("SELECT s.start_time, s.end_time, COALESCE(c.label, 'Focus session'), s.notes FROM session s LEFT JOIN card c ON c.id = s.card_id WHERE DATE(s.start_time) BETWEEN DATE(?) AND DATE(?) ORDER BY s.start_time") {
+                let mut rows = stmt.query(params![start_iso, end_iso]).map_err(|e| e.to_string())?;
+                while let Ok(Some(r)) = rows.next() {
+                    let time: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
+                    let end: Option<String> = r.get(1).unwrap_or(None);
+                    let title: String = r.get(2).unwrap_or_default();
+                    out.push(CalendarItem { kind: "session".to_string(), time, title, details: r.get(3).unwrap_or(None), end });
+                }
+            }
+            if let Ok(mut stmt) = conn.prepare("SELECT start_time, end_time, planned_minutes, title FROM scheduled_session WHERE DATE(start_time) BETWEEN DATE(?) AND DATE(?) AND COALESCE(status, 'scheduled') != 'cancelled' ORDER BY start_time") {
+                let mut rows = stmt.query(params![start_iso, end_iso]).map_err(|e| e.to_string())?;
+                while let Ok(Some(r)) = rows.next() {
+                    let time: String = r.get::<_, Option<String>>(0).unwrap_or(None).unwrap_or_default();
+                    let planned: Option<i64> = r.get(2).unwrap_or(None);
+                    let end: Option<String> = r.get::<_, Option<String>>(1).unwrap_or(None).or_else(|| {
+                        let start = local_journal::parse_timestamp(&time, chrono::Local::now().date_naive())?;
+                        Some((start + ChronoDuration::minutes(planned?)).format("%Y-%m-%dT%H:%M:%S").to_string())
+                    });
+                    out.push(CalendarItem { kind: "scheduled_session".to_string(), time, title: r.get(3).unwrap_or_default(), details: None, end });
                 }
             }
         }
     }
 
-    // Optionally include latest few journal entries once (from personality DB)
-    if let Ok(entries) = journals::list_journal_entries(0, Some(5)) {
-        for e in entries {
-            out.push(CalendarItem { kind: "journal".to_string(), time: e.created_at.clone(), title: format!("Journal: {}", e.provider), details: Some(e.content) });
+    for occ in recurring {
+        out.push(CalendarItem { kind: occ.kind, time: occ.start, title: occ.title, details: occ.details, end: occ.end });
+    }
+
+    Ok(Some(out))
+}
+
+/// Kinds that occupy time on the calendar; alarms, reminders and journals do not.
+pub const BLOCKING_KINDS: [&str; 3] = ["event", "session", "scheduled_session"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CalendarConflict {
+    pub first: CalendarItem,
+    pub second: CalendarItem,
+    pub overlap_start: String,
+    pub overlap_end: String,
+    pub overlap_minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkingHours {
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+    /// ISO weekdays to include (1 = Monday … 7 = Sunday); every day when absent.
+    #[serde(default)]
+    pub weekdays: Option<Vec<u32>>,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        WorkingHours { start: "09:00".to_string(), end: "17:00".to_string(), weekdays: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FreeSlot {
+    pub start: String,
+    pub end: String,
+    pub minutes: i64,
+}
+
+fn fmt_ts(t: NaiveDateTime) -> String {
+    t.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn parse_range_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+        .or_else(|| chrono::DateTime::parse_from_rfc3339(s).ok().map(|dt| dt.date_naive()))
+        .ok_or_else(|| format!("Invalid date '{}'", s))
+}
+
+/// Start and end of an item that occupies time, if it has both.
+pub fn item_span(item: &CalendarItem) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    if !BLOCKING_KINDS.contains(&item.kind.as_str()) { return None; }
+    let today = chrono::Local::now().date_naive();
+    let start = local_journal::parse_timestamp(&item.time, today)?;
+    let end = local_journal::parse_timestamp(item.end.as_deref()?, start.date())?;
+    if end > start { Some((start, end)) } else { None }
+}
+
+/// Every pair of time-occupying items that overlap, in start order.
+pub fn find_conflicts(items: &[CalendarItem]) -> Vec<CalendarConflict> {
+    let mut spans: Vec<(NaiveDateTime, NaiveDateTime, &CalendarItem)> = items.iter().filter_map(|i| item_span(i).map(|(s, e)| (s, e, i))).collect();
+    spans.sort_by_key(|(s, e, _)| (*s, *e));
+    let mut out = Vec::new();
+    for (i, (s1, e1, a)) in spans.iter().enumerate() {
+        for (s2, e2, b) in spans.iter().skip(i + 1) {
+            if s2 >= e1 { break; }
+            let (start, end) = (*s1.max(s2), *e1.min(e2));
+            out.push(CalendarConflict {
+                first: (*a).clone(), second: (*b).clone(),
+                overlap_start: fmt_ts(start), overlap_end: fmt_ts(end),
+                overlap_minutes: (end - start).num_minutes(),
+            });
         }
     }
+    out
+}
 
+/// Gaps of at least `min_minutes` inside working hours on each day of `start..=end` that no busy
+/// interval covers, never earlier than `not_before`.
+pub fn free_slots(busy: &[(NaiveDateTime, NaiveDateTime)], start: NaiveDate, end: NaiveDate, min_minutes: i64, hours: &WorkingHours, not_before: Option<NaiveDateTime>) -> Result<Vec<FreeSlot>, String> {
+    let open = NaiveTime::parse_from_str(hours.start.trim(), "%H:%M").map_err(|_| format!("Invalid working hours start '{}'", hours.start))?;
+    let close = NaiveTime::parse_from_str(hours.end.trim(), "%H:%M").map_err(|_| format!("Invalid working hours end '{}'", hours.end))?;
+    if close <= open { return Err("Working hours must end after they start".to_string()); }
+    if min_minutes <= 0 { return Err("Minimum duration must be positive".to_string()); }
+    let mut busy = busy.to_vec();
+    busy.sort();
+    let mut out = Vec::new();
+    let mut day = start;
+    while day <= end {
+        let included = hours.weekdays.as_ref().map(|w| w.contains(&day.weekday().number_from_monday())).unwrap_or(true);
+        if included {
+            let window_end = day.and_time(close);
+            let mut cursor = day.and_time(open).max(not_before.unwrap_or(NaiveDateTime::MIN));
+            let mut push_gap = |from: NaiveDateTime, to: NaiveDateTime| {
+                if (to - from).num_minutes() >= min_minutes {
+                    out.push(FreeSlot { start: fmt_ts(from), end: fmt_ts(to), minutes: (to - from).num_minutes() });
+                }
+            };
+            let window_start = cursor;
+            for (b_start, b_end) in busy.iter().filter(|(s, e)| *e > window_start && *s < window_end) {
+                if *b_start > cursor { push_gap(cursor, *b_start); }
+                cursor = cursor.max(*b_end);
+            }
+            if cursor < window_end { push_gap(cursor, window_end); }
+        }
+        day = match day.succ_opt() { Some(d) => d, None => break };
+    }
     Ok(out)
 }
+
+/// Free slots computed from everything on the calendar in `start..=end`.
+pub fn find_slots(workspace_dir: Option<String>, user_id: Option<i64>, start: NaiveDate, end: NaiveDate, min_minutes: i64, hours: &WorkingHours, not_before: Option<NaiveDateTime>) -> Result<Vec<FreeSlot>, String> {
+    let items = collect_calendar_items(workspace_dir, user_id, &start.to_string(), &end.to_string())?.unwrap_or_default();
+    let busy: Vec<(NaiveDateTime, NaiveDateTime)> = items.iter().filter_map(item_span).collect();
+    free_slots(&busy, start, end, min_minutes, hours, not_before)
+}
+
+/// Overlapping events, sessions and scheduled sessions in the range.
+#[tauri::command]
+pub fn get_calendar_conflicts(workspace_dir: Option<String>, start_iso: String, end_iso: String, user_id: Option<i64>) -> Result<Vec<CalendarConflict>, String> {
+    let items = collect_calendar_items(workspace_dir, user_id, &start_iso, &end_iso)?.unwrap_or_default();
+    Ok(find_conflicts(&items))
+}
+
+/// Open slots of at least `min_duration_minutes` within working hours (default 09:00–17:00),
+/// starting from now at the earliest.
+#[tauri::command]
+pub fn find_free_slots(workspace_dir: Option<String>, start_iso: String, end_iso: String, min_duration_minutes: i64, working_hours: Option<WorkingHours>, user_id: Option<i64>) -> Result<Vec<FreeSlot>, String> {
+    let start = parse_range_date(&start_iso)?;
+    let end = parse_range_date(&end_iso)?;
+    find_slots(workspace_dir, user_id, start, end, min_duration_minutes, &working_hours.unwrap_or_default(), Some(chrono::Local::now().naive_local()))
+}
@@ -73,7 +73,11 @@ pub fn aggregate_all_time_stats(workspace_dir: Option<String>) -> Result<(i64, f
     use std::path::Path;
 
     let dir = workspace_dir.unwrap_or_else(|| "./".to_string());
-    let mut total_goals = 0i64;
+    // goals completed, from the workspace store
+    let total_goals = match goals::open_store_for_read(Some(&dir))? {
+        Some(store) => goals::list_goal_rows(&store, Some("completed"))?.len() as i64,
+        None => 0,
+    };
     let mut total_session_hours = 0.0f64;
     let mut sleep_hours_accum: Vec<f64> = Vec::new();
 
@@ -87,15 +91,6 @@ pub fn aggregate_all_time_stats(workspace_dir: Option<String>) -> Result<(i64, f
                 if !(fname.ends_with(".sqlite3") || fname.ends_with(".sqlite") || fname.ends_with(".db")) { continue; }
                 // open daily DB
                 if let Ok(conn) = Connection::open(&path) {
-                    // goals completed count (if table exists)
-                    if let Ok(mut stmt) = conn.prepare("SELECT COUNT(*) FROM goal WHERE completed = 1") {
-                        if let Ok(mut rows) = stmt.query([]) {
-                            if let Ok(Some(r)) = rows.next() {
-                                let cnt: i64 = r.get(0).unwrap_or(0);
-                                total_goals += cnt;
-                            }
-                        }
-                    }
                     // session hours sum
                     if let Ok(mut stmt) = conn.prepare("SELECT SUM((julianday(end_time) - julianday(start_time)) * 24.0) FROM session WHERE end_time IS NOT NULL") {
                         if let Ok(mut rows) = stmt.query([]) {
@@ -213,6 +208,7 @@ pub fn generate_ai_prompt() -> Result<String, String> {
 
 use rusqlite::{Connection, params};
 use serde::{Serialize, Deserialize};
+use crate::backend::goals;
 
 /// Path to the persistent personality database (not rotated daily)
 pub const PERSONALITY_DB_PATH: &str = "focusd_personality.db";
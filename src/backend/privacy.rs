@@ -0,0 +1,181 @@
+//! Outbound privacy filter: masks personal data in prompts before they are sent to an AI provider
+//! and restores the original values in the provider's response, locally.
+use rusqlite::{Connection, params};
+use serde::{Serialize, Deserialize};
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+use crate::backend::safety::{EMAIL_RE, PHONE_RE};
+
+/// Per-user configuration for the outbound filter (stored as JSON in `privacy_settings`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacySettings {
+    pub enabled: bool,
+    pub mask_emails: bool,
+    pub mask_phones: bool,
+    /// Names (people, clients, companies) that should never leave the machine.
+    pub names: Vec<String>,
+    /// Additional regular expressions whose matches are masked.
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        PrivacySettings { enabled: true, mask_emails: true, mask_phones: true, names: vec![], custom_patterns: vec![] }
+    }
+}
+
+/// Masks matches with stable placeholders (`[EMAIL_1]`, `[NAME_2]`, ...) and remembers the mapping
+/// so the same value always gets the same placeholder and responses can be re-hydrated.
+pub struct PrivacyFilter {
+    rules: Vec<(&'static str, Regex)>,
+    placeholders: HashMap<(String, String), String>,
+    originals: HashMap<String, String>,
+    counters: HashMap<&'static str, usize>,
+}
+
+impl PrivacyFilter {
+    /// Build a filter from settings. Fails if a custom pattern is not a valid regex.
+    pub fn new(settings: &PrivacySettings) -> Result<Self, String> {
+        let mut rules: Vec<(&'static str, Regex)> = Vec::new();
+        if settings.enabled {
+            for name in settings.names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+                let re = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(name))).map_err(|e| e.to_string())?;
+                rules.push(("NAME", re));
+            }
+            for pat in settings.custom_patterns.iter().filter(|p| !p.trim().is_empty()) {
+                let re = Regex::new(pat).map_err(|e| format!("invalid custom pattern {}: {}", pat, e))?;
+                rules.push(("CUSTOM", re));
+            }
+            if settings.mask_emails { rules.push(("EMAIL", EMAIL_RE.clone())); }
+            if settings.mask_phones { rules.push(("PHONE", PHONE_RE.clone())); }
+        }
+        Ok(PrivacyFilter { rules, placeholders: HashMap::new(), originals: HashMap::new(), counters: HashMap::new() })
+    }
+
+    /// Replace every match with its placeholder. Overlapping matches are resolved in favour of the
+    /// earliest (then longest) one, and the text is rewritten in a single pass so placeholders are
+    /// never matched again by a later rule.
+    pub fn mask(&mut self, text: &str) -> String {
+        let mut spans: Vec<(usize, usize, &'static str)> = Vec::new();
+        for (label, re) in &self.rules {
+            for m in re.find_iter(text) {
+                if m.start() < m.end() { spans.push((m.start(), m.end(), *label)); }
+            }
+        }
+        spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (start, end, label) in spans {
+            if start < pos { continue; }
+            out.push_str(&text[pos..start]);
+            let placeholder = self.placeholder_for(label, &text[start..end]);
+            out.push_str(&placeholder);
+            pos = end;
+        }
+        out.push_str(&text[pos..]);
+        out
+    }
+
+    /// Put the original values back in place of any placeholders found in `text`.
+    pub fn rehydrate(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (placeholder, original) in &self.originals {
+            out = out.replace(placeholder, original);
+        }
+        out
+    }
+
+    /// Number of distinct values masked so far.
+    pub fn masked_count(&self) -> usize {
+        self.originals.len()
+    }
+
+    fn placeholder_for(&mut self, label: &'static str, value: &str) -> String {
+        let key = (label.to_string(), value.to_lowercase());
+        if let Some(p) = self.placeholders.get(&key) {
+            return p.clone();
+        }
+        let n = self.counters.entry(label).or_insert(0);
+        *n += 1;
+        let placeholder = format!("[{}_{}]", label, n);
+        self.placeholders.insert(key, placeholder.clone());
+        self.originals.insert(placeholder.clone(), value.to_string());
+        placeholder
+    }
+}
+
+fn ensure_privacy_table(conn: &Connection) -> Result<(), String> {
+    conn.execute("CREATE TABLE IF NOT EXISTS privacy_settings (user_id INTEGER PRIMARY KEY, settings_json TEXT NOT NULL, updated_at TEXT DEFAULT (datetime('now')))", []).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Read `user.data_sharing_opt_in`; a missing column, row or NULL counts as "not shared".
+pub fn data_sharing_opt_in(conn: &Connection, user_id: i64) -> bool {
+    conn.query_row("SELECT data_sharing_opt_in FROM user WHERE id = ?", params![user_id], |r| r.get::<_, Option<bool>>(0))
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
+
+/// Load the stored settings for a user (defaults if none were saved).
+pub fn load_privacy_settings(conn: &Connection, user_id: i64) -> Result<PrivacySettings, String> {
+    ensure_privacy_table(conn)?;
+    let mut stmt = conn.prepare("SELECT settings_json FROM privacy_settings WHERE user_id = ?").map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params![user_id]).map_err(|e| e.to_string())?;
+    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let json: String = row.get(0).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    } else {
+        Ok(PrivacySettings::default())
+    }
+}
+
+/// Settings actually applied to outbound calls. Without data-sharing consent the filter is always
+/// on and always masks emails and phone numbers, whatever the stored settings say.
+pub fn effective_privacy_settings(conn: &Connection, user_id: i64) -> Result<PrivacySettings, String> {
+    let mut settings = load_privacy_settings(conn, user_id)?;
+    if !data_sharing_opt_in(conn, user_id) {
+        settings.enabled = true;
+        settings.mask_emails = true;
+        settings.mask_phones = true;
+    }
+    Ok(settings)
+}
+
+/// Build the filter that must be applied to every prompt sent on behalf of `user_id`.
+pub fn outbound_filter_for_user(user_id: i64) -> Result<PrivacyFilter, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    let settings = effective_privacy_settings(&conn, user_id)?;
+    PrivacyFilter::new(&settings)
+}
+
+#[tauri::command]
+pub fn get_privacy_settings(user_id: i64) -> Result<PrivacySettings, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    load_privacy_settings(&conn, user_id)
+}
+
+#[tauri::command]
+pub fn set_privacy_settings(user_id: i64, settings: PrivacySettings) -> Result<(), String> {
+    // Validate patterns up front so a bad regex can't silently disable filtering later
+    PrivacyFilter::new(&settings)?;
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    ensure_privacy_table(&conn)?;
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO privacy_settings (user_id, settings_json, updated_at) VALUES (?, ?, datetime('now'))",
+        params![user_id, json],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Show exactly what would be sent to the provider for `prompt`.
+#[tauri::command]
+pub fn preview_outbound_prompt(user_id: i64, prompt: String) -> Result<String, String> {
+    let mut filter = outbound_filter_for_user(user_id)?;
+    Ok(filter.mask(&prompt))
+}
@@ -0,0 +1,116 @@
+//! Prompt-injection guard: user-recorded text (event descriptions, session notes, distraction
+//! reasons, ...) is data, never instructions. Fields that look like instructions are fenced as
+//! quoted data before they reach the prompt, and model output is checked for leaked instructions.
+use regex::Regex;
+use once_cell::sync::Lazy;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+
+/// Opening / closing markers used to fence suspicious user text in the prompt.
+pub const FENCE_OPEN: &str = "⟦quoted user data: ";
+pub const FENCE_CLOSE: &str = "⟧";
+
+static INJECTION_PATTERNS: Lazy<Vec<(&'static str, Regex)>> = Lazy::new(|| {
+    let rules: [(&str, &str); 9] = [
+        ("override_instructions", r"(?i)\b(?:ignore|disregard|forget|override|skip)\b(?:\s+\w+){0,3}?\s+(?:previous|prior|above|earlier|preceding|all|your|system)\s+(?:instructions?|prompts?|rules|directions)"),
+        ("new_instructions", r"(?i)\b(?:new|updated|real|actual)\s+(?:instructions?|system\s+prompt|rules)\s*:"),
+        ("role_reassignment", r"(?i)\b(?:you\s+are\s+now|from\s+now\s+on,?\s+you|pretend\s+(?:to\s+be|you\s+are)|act\s+as\s+(?:an?\s+)?(?:ai|assistant|system|developer|dan)\b)"),
+        ("role_marker", r"(?im)^\s*(?:system|assistant|developer)\s*:|<\|?(?:im_start|im_end|system|endoftext)\|?>|\[/?(?:INST|SYS)\]"),
+        ("prompt_exfiltration", r"(?i)\b(?:reveal|print|repeat|show|output|leak)\b(?:\s+\w+){0,3}?\s+(?:system\s+prompt|instructions|prompt\s+above|initial\s+prompt)"),
+        ("output_hijack", r"(?i)\binstead,?\s+(?:write|respond|reply|output|say)\b"),
+        ("jailbreak", r"(?i)\b(?:jailbreak|developer\s+mode|do\s+anything\s+now)\b"),
+        ("fence_escape", r"(?m)^\s*(?:#{1,6}\s|---\s*$|```)"),
+        ("fence_marker", r"[⟦⟧]"),
+    ];
+    rules.iter().map(|(name, pat)| (*name, Regex::new(pat).unwrap())).collect()
+});
+
+/// A user field after guarding: the text to place in the prompt and why it was fenced (if it was).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardedField {
+    pub text: String,
+    pub reasons: Vec<String>,
+}
+
+impl GuardedField {
+    pub fn flagged(&self) -> bool {
+        !self.reasons.is_empty()
+    }
+}
+
+/// Names of the injection patterns matched by `text` (empty when it looks like plain data).
+pub fn detect_injection(text: &str) -> Vec<String> {
+    INJECTION_PATTERNS.iter()
+        .filter(|(_, re)| re.is_match(text))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Flatten a field so it stays on one prompt line inside its quotes and cannot close the fence.
+fn sanitize(text: &str) -> String {
+    let flat: String = text.replace("\r\n", "\n")
+        .split('\n')
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" / ");
+    flat.replace('"', "'").replace(['⟦', '⟧'], "").replace("```", "'''")
+}
+
+/// Sanitize a user field and, if it looks like instructions, fence it as quoted data.
+pub fn guard_field(text: &str) -> GuardedField {
+    let reasons = detect_injection(text);
+    let clean = sanitize(text);
+    let text = if reasons.is_empty() { clean } else { format!("{}{}{}", FENCE_OPEN, clean, FENCE_CLOSE) };
+    GuardedField { text, reasons }
+}
+
+/// Result of comparing model output against the system instructions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeakageReport {
+    pub leaked: bool,
+    /// Share of the instructions' word 8-grams that re-appear in the output (0.0..1.0).
+    pub overlap: f64,
+    pub fragments: Vec<String>,
+}
+
+const SHINGLE: usize = 8;
+const LEAK_MIN_SHINGLES: usize = 3;
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Detect output that reproduces the system instructions: any run of words long enough to cover
+/// several 8-word shingles from `instructions` counts as a leak.
+pub fn check_output_leakage(output: &str, instructions: &[&str]) -> LeakageReport {
+    let mut known: HashSet<Vec<String>> = HashSet::new();
+    for text in instructions {
+        for w in words(text).windows(SHINGLE) { known.insert(w.to_vec()); }
+    }
+    let out_words = words(output);
+    let mut matched: HashSet<Vec<String>> = HashSet::new();
+    let mut fragments = Vec::new();
+    let mut run_start: Option<usize> = None;
+    let windows: Vec<&[String]> = out_words.windows(SHINGLE).collect();
+    for (i, w) in windows.iter().enumerate() {
+        let hit = known.contains(*w);
+        if hit { matched.insert(w.to_vec()); }
+        match (hit, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= LEAK_MIN_SHINGLES { fragments.push(out_words[s..i - 1 + SHINGLE].join(" ")); }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = run_start {
+        if windows.len() - s >= LEAK_MIN_SHINGLES { fragments.push(out_words[s..].join(" ")); }
+    }
+    let overlap = if known.is_empty() { 0.0 } else { matched.len() as f64 / known.len() as f64 };
+    LeakageReport { leaked: !fragments.is_empty(), overlap, fragments }
+}
@@ -0,0 +1,546 @@
+//! Recurring alarms, reminders and events with RFC 5545 RRULE semantics.
+//!
+//! Definitions and per-occurrence exceptions live in the personality DB (not the per-day files),
+//! and occurrences are expanded lazily for whatever range is asked for. Supported rule parts:
+//! FREQ (DAILY/WEEKLY/MONTHLY/YEARLY), INTERVAL, COUNT, UNTIL, BYDAY (with ordinals for monthly
+//! and yearly rules), BYMONTHDAY, BYMONTH, BYSETPOS and WKST. Occurrences keep the time of day of
+//! DTSTART.
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params, OptionalExtension};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday, Duration as ChronoDuration};
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+
+pub const KINDS: [&str; 3] = ["alarm", "reminder", "event"];
+/// Give up on a rule after this many consecutive periods without an occurrence
+/// (e.g. `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`).
+const MAX_EMPTY_PERIODS: u32 = 1000;
+/// Hard cap on occurrences walked for one range query.
+const MAX_SCAN: usize = 100_000;
+const TS_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Freq { Daily, Weekly, Monthly, Yearly }
+
+/// One BYDAY entry: a weekday, optionally the nth (negative: from the end) in the month or year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub nth: Option<i32>,
+    pub weekday: Weekday,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Freq,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>,
+    pub by_day: Vec<ByDay>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub wkst: Weekday,
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    Some(match s {
+        "MO" => Weekday::Mon, "TU" => Weekday::Tue, "WE" => Weekday::Wed, "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri, "SA" => Weekday::Sat, "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn weekday_code(w: Weekday) -> &'static str {
+    match w {
+        Weekday::Mon => "MO", Weekday::Tue => "TU", Weekday::Wed => "WE", Weekday::Thu => "TH",
+        Weekday::Fri => "FR", Weekday::Sat => "SA", Weekday::Sun => "SU",
+    }
+}
+
+fn parse_int_list<T: std::str::FromStr>(key: &str, v: &str) -> Result<Vec<T>, String> {
+    v.split(',').map(|x| x.trim().parse::<T>().map_err(|_| format!("{}: invalid value '{}'", key, x))).collect()
+}
+
+fn parse_until(v: &str) -> Result<NaiveDateTime, String> {
+    let v = v.trim();
+    if v.len() == 8 {
+        // A date-only UNTIL includes the whole day.
+        let d = NaiveDate::parse_from_str(v, "%Y%m%d").map_err(|_| format!("UNTIL: invalid date '{}'", v))?;
+        return d.and_hms_opt(23, 59, 59).ok_or_else(|| "UNTIL: invalid date".to_string());
+    }
+    let (body, utc) = match v.strip_suffix('Z') { Some(b) => (b, true), None => (v, false) };
+    let dt = NaiveDateTime::parse_from_str(body, "%Y%m%dT%H%M%S").map_err(|_| format!("UNTIL: invalid date-time '{}'", v))?;
+    if utc {
+        use chrono::TimeZone;
+        Ok(chrono::Utc.from_utc_datetime(&dt).with_timezone(&chrono::Local).naive_local())
+    } else {
+        Ok(dt)
+    }
+}
+
+impl RRule {
+    /// Parse an RRULE value (with or without the `RRULE:` prefix).
+    pub fn parse(s: &str) -> Result<RRule, String> {
+        let body = s.trim();
+        let body = body.strip_prefix("RRULE:").unwrap_or(body);
+        let mut freq = None;
+        let mut rule = RRule { freq: Freq::Daily, interval: 1, count: None, until: None, by_day: vec![], by_month_day: vec![], by_month: vec![], by_set_pos: vec![], wkst: Weekday::Mon };
+        for part in body.split(';').filter(|p| !p.trim().is_empty()) {
+            let (k, v) = part.split_once('=').ok_or_else(|| format!("Malformed rule part '{}'", part))?;
+            let k = k.trim().to_ascii_uppercase();
+            let v = v.trim().to_ascii_uppercase();
+            match k.as_str() {
+                "FREQ" => freq = Some(match v.as_str() {
+                    "DAILY" => Freq::Daily, "WEEKLY" => Freq::Weekly, "MONTHLY" => Freq::Monthly, "YEARLY" => Freq::Yearly,
+                    other => return Err(format!("FREQ={} is not supported", other)),
+                }),
+                "INTERVAL" => {
+                    rule.interval = v.parse().map_err(|_| format!("INTERVAL: invalid value '{}'", v))?;
+                    if rule.interval == 0 { return Err("INTERVAL must be at least 1".to_string()); }
+                }
+                "COUNT" => rule.count = Some(v.parse().map_err(|_| format!("COUNT: invalid value '{}'", v))?),
+                "UNTIL" => rule.until = Some(parse_until(&v)?),
+                "BYDAY" => {
+                    for d in v.split(',') {
+                        let d = d.trim();
+                        let split = d.len().checked_sub(2).ok_or_else(|| format!("BYDAY: invalid value '{}'", d))?;
+                        let weekday = parse_weekday(&d[split..]).ok_or_else(|| format!("BYDAY: invalid weekday '{}'", d))?;
+                        let nth = if split == 0 { None } else {
+                            let n: i32 = d[..split].trim_start_matches('+').parse().map_err(|_| format!("BYDAY: invalid ordinal '{}'", d))?;
+                            if n == 0 || n.abs() > 53 { return Err(format!("BYDAY: ordinal out of range in '{}'", d)); }
+                            Some(n)
+                        };
+                        rule.by_day.push(ByDay { nth, weekday });
+                    }
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = parse_int_list(&k, &v)?;
+                    if rule.by_month_day.iter().any(|d| *d == 0 || d.abs() > 31) { return Err("BYMONTHDAY values must be 1..31 or -31..-1".to_string()); }
+                }
+                "BYMONTH" => {
+                    rule.by_month = parse_int_list(&k, &v)?;
+                    if rule.by_month.iter().any(|m| !(1..=12).contains(m)) { return Err("BYMONTH values must be 1..12".to_string()); }
+                }
+                "BYSETPOS" => {
+                    rule.by_set_pos = parse_int_list(&k, &v)?;
+                    if rule.by_set_pos.iter().any(|p| *p == 0 || p.abs() > 366) { return Err("BYSETPOS values must be 1..366 or -366..-1".to_string()); }
+                }
+                "WKST" => rule.wkst = parse_weekday(&v).ok_or_else(|| format!("WKST: invalid weekday '{}'", v))?,
+                other => return Err(format!("{} is not supported", other)),
+            }
+        }
+        rule.freq = freq.ok_or("FREQ is required")?;
+        if rule.count.is_some() && rule.until.is_some() { return Err("COUNT and UNTIL cannot both be set".to_string()); }
+        if rule.by_day.iter().any(|d| d.nth.is_some()) && matches!(rule.freq, Freq::Daily | Freq::Weekly) {
+            return Err("BYDAY ordinals are only allowed with MONTHLY or YEARLY".to_string());
+        }
+        if rule.freq == Freq::Weekly && !rule.by_month_day.is_empty() {
+            return Err("BYMONTHDAY is not allowed with WEEKLY".to_string());
+        }
+        Ok(rule)
+    }
+
+    /// Occurrences starting at `dtstart`, in order. DTSTART itself is the first occurrence only
+    /// when it matches the rule, as in RFC 5545.
+    pub fn iter(&self, dtstart: NaiveDateTime) -> Occurrences<'_> {
+        Occurrences { rule: self, dtstart, period: 0, buffer: VecDeque::new(), emitted: 0, empty_periods: 0, done: false }
+    }
+
+    /// Occurrences in `from..=to`.
+    pub fn between(&self, dtstart: NaiveDateTime, from: NaiveDateTime, to: NaiveDateTime) -> Vec<NaiveDateTime> {
+        self.iter(dtstart).take(MAX_SCAN).skip_while(|t| *t < from).take_while(|t| *t <= to).collect()
+    }
+}
+
+impl fmt::Display for RRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.freq { Freq::Daily => "DAILY", Freq::Weekly => "WEEKLY", Freq::Monthly => "MONTHLY", Freq::Yearly => "YEARLY" };
+        write!(f, "FREQ={}", freq)?;
+        if self.interval != 1 { write!(f, ";INTERVAL={}", self.interval)?; }
+        if let Some(c) = self.count { write!(f, ";COUNT={}", c)?; }
+        if let Some(u) = self.until { write!(f, ";UNTIL={}", u.format("%Y%m%dT%H%M%S"))?; }
+        let join = |v: Vec<String>| v.join(",");
+        if !self.by_day.is_empty() {
+            write!(f, ";BYDAY={}", join(self.by_day.iter().map(|d| format!("{}{}", d.nth.map(|n| n.to_string()).unwrap_or_default(), weekday_code(d.weekday))).collect()))?;
+        }
+        if !self.by_month_day.is_empty() { write!(f, ";BYMONTHDAY={}", join(self.by_month_day.iter().map(|d| d.to_string()).collect()))?; }
+        if !self.by_month.is_empty() { write!(f, ";BYMONTH={}", join(self.by_month.iter().map(|d| d.to_string()).collect()))?; }
+        if !self.by_set_pos.is_empty() { write!(f, ";BYSETPOS={}", join(self.by_set_pos.iter().map(|d| d.to_string()).collect()))?; }
+        if self.wkst != Weekday::Mon { write!(f, ";WKST={}", weekday_code(self.wkst))?; }
+        Ok(())
+    }
+}
+
+fn days_in_month(y: i32, m: u32) -> u32 {
+    let next = if m == 12 { NaiveDate::from_ymd_opt(y + 1, 1, 1) } else { NaiveDate::from_ymd_opt(y, m + 1, 1) };
+    next.and_then(|n| n.pred_opt()).map(|d| d.day()).unwrap_or(28)
+}
+
+/// Days in `days` that fall on `weekday`, or only the nth of them (negative counts from the end).
+fn pick_weekdays(days: &[NaiveDate], bd: &ByDay) -> Vec<NaiveDate> {
+    let matching: Vec<NaiveDate> = days.iter().copied().filter(|d| d.weekday() == bd.weekday).collect();
+    match bd.nth {
+        None => matching,
+        Some(n) if n > 0 => matching.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => matching.len().checked_sub(n.unsigned_abs() as usize).and_then(|i| matching.get(i)).copied().into_iter().collect(),
+    }
+}
+
+/// Candidate days of one month under BYMONTHDAY / BYDAY (both: intersection; neither: `default_day`).
+fn month_days(rule: &RRule, y: i32, m: u32, default_day: u32) -> Vec<NaiveDate> {
+    let last = days_in_month(y, m);
+    let all: Vec<NaiveDate> = (1..=last).filter_map(|d| NaiveDate::from_ymd_opt(y, m, d)).collect();
+    let by_md: Vec<NaiveDate> = rule.by_month_day.iter().filter_map(|d| {
+        let day = if *d > 0 { *d } else { last as i32 + 1 + d };
+        if day >= 1 && day <= last as i32 { NaiveDate::from_ymd_opt(y, m, day as u32) } else { None }
+    }).collect();
+    let by_wd: Vec<NaiveDate> = rule.by_day.iter().flat_map(|bd| pick_weekdays(&all, bd)).collect();
+    match (rule.by_month_day.is_empty(), rule.by_day.is_empty()) {
+        (false, false) => by_md.into_iter().filter(|d| by_wd.contains(d)).collect(),
+        (false, true) => by_md,
+        (true, false) => by_wd,
+        (true, true) => NaiveDate::from_ymd_opt(y, m, default_day).into_iter().collect(),
+    }
+}
+
+fn apply_set_pos(days: Vec<NaiveDate>, set_pos: &[i32]) -> Vec<NaiveDate> {
+    if set_pos.is_empty() { return days; }
+    let mut out: Vec<NaiveDate> = set_pos.iter().filter_map(|p| {
+        let idx = if *p > 0 { (*p as usize).checked_sub(1) } else { days.len().checked_sub(p.unsigned_abs() as usize) };
+        idx.and_then(|i| days.get(i)).copied()
+    }).collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+pub struct Occurrences<'a> {
+    rule: &'a RRule,
+    dtstart: NaiveDateTime,
+    period: i64,
+    buffer: VecDeque<NaiveDateTime>,
+    emitted: u32,
+    empty_periods: u32,
+    done: bool,
+}
+
+impl Occurrences<'_> {
+    /// Candidate days for the current period, sorted, before BYSETPOS.
+    fn period_days(&self) -> Option<Vec<NaiveDate>> {
+        let r = self.rule;
+        let start = self.dtstart.date();
+        let step = self.period * r.interval as i64;
+        let mut days: Vec<NaiveDate> = match r.freq {
+            Freq::Daily => {
+                let d = start.checked_add_signed(ChronoDuration::days(step))?;
+                let keep = (r.by_month.is_empty() || r.by_month.contains(&d.month()))
+                    && (r.by_day.is_empty() || r.by_day.iter().any(|b| b.weekday == d.weekday()))
+                    && (r.by_month_day.is_empty() || month_days(&RRule { by_day: vec![], ..r.clone() }, d.year(), d.month(), 0).contains(&d));
+                if keep { vec![d] } else { vec![] }
+            }
+            Freq::Weekly => {
+                let offset = (7 + start.weekday().num_days_from_monday() as i64 - r.wkst.num_days_from_monday() as i64) % 7;
+                let week_start = start.checked_sub_signed(ChronoDuration::days(offset))?.checked_add_signed(ChronoDuration::weeks(step))?;
+                (0..7).filter_map(|i| week_start.checked_add_signed(ChronoDuration::days(i)))
+                    .filter(|d| if r.by_day.is_empty() { d.weekday() == start.weekday() } else { r.by_day.iter().any(|b| b.weekday == d.weekday()) })
+                    .filter(|d| r.by_month.is_empty() || r.by_month.contains(&d.month()))
+                    .collect()
+            }
+            Freq::Monthly => {
+                let months = start.year() as i64 * 12 + start.month0() as i64 + step;
+                let (y, m) = (i32::try_from(months.div_euclid(12)).ok()?, months.rem_euclid(12) as u32 + 1);
+                if !r.by_month.is_empty() && !r.by_month.contains(&m) { vec![] } else { month_days(r, y, m, start.day()) }
+            }
+            Freq::Yearly => {
+                let y = i32::try_from(start.year() as i64 + step).ok()?;
+                NaiveDate::from_ymd_opt(y, 1, 1)?;
+                if !r.by_month.is_empty() {
+                    r.by_month.iter().flat_map(|m| month_days(r, y, *m, start.day())).collect()
+                } else if !r.by_month_day.is_empty() {
+                    (1..=12).flat_map(|m| month_days(r, y, m, start.day())).collect()
+                } else if !r.by_day.is_empty() {
+                    // BYDAY without BYMONTH: ordinals count within the year
+                    let all: Vec<NaiveDate> = NaiveDate::from_ymd_opt(y, 1, 1)?.iter_days().take_while(|d| d.year() == y).collect();
+                    r.by_day.iter().flat_map(|bd| pick_weekdays(&all, bd)).collect()
+                } else {
+                    NaiveDate::from_ymd_opt(y, start.month(), start.day()).into_iter().collect()
+                }
+            }
+        };
+        days.sort();
+        days.dedup();
+        Some(apply_set_pos(days, &r.by_set_pos))
+    }
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        loop {
+            if let Some(t) = self.buffer.pop_front() {
+                if self.rule.until.map(|u| t > u).unwrap_or(false) || self.rule.count.map(|c| self.emitted >= c).unwrap_or(false) {
+                    self.done = true;
+                    self.buffer.clear();
+                    return None;
+                }
+                self.emitted += 1;
+                return Some(t);
+            }
+            if self.done { return None; }
+            let days = match self.period_days() { Some(d) => d, None => { self.done = true; continue; } };
+            self.period += 1;
+            let time = self.dtstart.time();
+            let before = self.buffer.len();
+            self.buffer.extend(days.into_iter().map(|d| d.and_time(time)).filter(|t| *t >= self.dtstart));
+            if self.buffer.len() == before {
+                self.empty_periods += 1;
+                if self.empty_periods >= MAX_EMPTY_PERIODS { self.done = true; }
+            } else {
+                self.empty_periods = 0;
+            }
+        }
+    }
+}
+
+// ---------- storage ----------
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Recurrence {
+    pub id: i64,
+    pub user_id: i64,
+    /// "alarm" | "reminder" | "event"
+    pub kind: String,
+    pub title: String,
+    pub details: Option<String>,
+    /// First occurrence, `YYYY-MM-DDTHH:MM:SS` local time.
+    pub dtstart: String,
+    pub duration_minutes: Option<i64>,
+    pub rrule: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Occurrence {
+    pub recurrence_id: i64,
+    pub kind: String,
+    pub title: String,
+    pub details: Option<String>,
+    /// Start the rule produced; identifies the occurrence for exceptions.
+    pub original_start: String,
+    pub start: String,
+    pub end: Option<String>,
+    pub moved: bool,
+}
+
+pub fn ensure_recurrence_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS recurrences (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            kind TEXT NOT NULL CHECK (kind IN ('alarm', 'reminder', 'event')),
+            title TEXT NOT NULL,
+            details TEXT,
+            dtstart TEXT NOT NULL,
+            duration_minutes INTEGER,
+            rrule TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS recurrence_exceptions (
+            recurrence_id INTEGER NOT NULL,
+            occurrence_start TEXT NOT NULL,
+            action TEXT NOT NULL CHECK (action IN ('skip', 'move')),
+            new_start TEXT,
+            PRIMARY KEY (recurrence_id, occurrence_start)
+        );
+    "#).map_err(|e| e.to_string())
+}
+
+/// Whether the recurrence tables exist, for read-only callers that should not create them.
+pub fn has_tables(conn: &Connection) -> bool {
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'recurrences'", [], |r| r.get::<_, i64>(0)).map(|n| n > 0).unwrap_or(false)
+}
+
+pub fn parse_local_datetime(s: &str) -> Result<NaiveDateTime, String> {
+    let s = s.trim();
+    for fmt in [TS_FORMAT, "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) { return Ok(dt); }
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") { return Ok(d.and_time(chrono::NaiveTime::MIN)); }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) { return Ok(dt.with_timezone(&chrono::Local).naive_local()); }
+    Err(format!("Invalid date-time '{}'", s))
+}
+
+fn fmt_ts(t: NaiveDateTime) -> String {
+    t.format(TS_FORMAT).to_string()
+}
+
+fn recurrence_from_row(r: &rusqlite::Row) -> rusqlite::Result<Recurrence> {
+    Ok(Recurrence { id: r.get(0)?, user_id: r.get(1)?, kind: r.get(2)?, title: r.get(3)?, details: r.get(4)?, dtstart: r.get(5)?, duration_minutes: r.get(6)?, rrule: r.get(7)?, created_at: r.get(8)? })
+}
+
+const RECURRENCE_COLUMNS: &str = "id, user_id, kind, title, details, dtstart, duration_minutes, rrule, created_at";
+
+#[allow(clippy::too_many_arguments)]
+pub fn create(conn: &Connection, user_id: i64, kind: &str, title: &str, details: Option<&str>, dtstart: &str, duration_minutes: Option<i64>, rrule: &str) -> Result<Recurrence, String> {
+    ensure_recurrence_tables(conn)?;
+    if !KINDS.contains(&kind) { return Err(format!("Unknown kind '{}', expected alarm, reminder or event", kind)); }
+    if title.trim().is_empty() { return Err("Title is required".to_string()); }
+    let rule = RRule::parse(rrule)?;
+    let start = parse_local_datetime(dtstart)?;
+    conn.execute(
+        "INSERT INTO recurrences (user_id, kind, title, details, dtstart, duration_minutes, rrule) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        params![user_id, kind, title.trim(), details, fmt_ts(start), duration_minutes, rule.to_string()],
+    ).map_err(|e| e.to_string())?;
+    get(conn, conn.last_insert_rowid())?.ok_or_else(|| "Recurrence not found after insert".to_string())
+}
+
+pub fn get(conn: &Connection, id: i64) -> Result<Option<Recurrence>, String> {
+    ensure_recurrence_tables(conn)?;
+    conn.query_row(&format!("SELECT {} FROM recurrences WHERE id = ?", RECURRENCE_COLUMNS), params![id], recurrence_from_row)
+        .optional().map_err(|e| e.to_string())
+}
+
+/// Recurrences for `user_id` (all users when `None`).
+pub fn list(conn: &Connection, user_id: Option<i64>) -> Result<Vec<Recurrence>, String> {
+    ensure_recurrence_tables(conn)?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM recurrences WHERE ?1 IS NULL OR user_id = ?1 ORDER BY id", RECURRENCE_COLUMNS)).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![user_id], recurrence_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// `user_id`'s recurrence `id`; an error for a missing rule or someone else's.
+pub fn get_owned(conn: &Connection, user_id: i64, id: i64) -> Result<Recurrence, String> {
+    get(conn, id)?.filter(|r| r.user_id == user_id).ok_or_else(|| format!("Recurrence {} not found", id))
+}
+
+pub fn delete(conn: &Connection, user_id: i64, id: i64) -> Result<(), String> {
+    ensure_recurrence_tables(conn)?;
+    let deleted = conn.execute("DELETE FROM recurrences WHERE id = ? AND user_id = ?", params![id, user_id]).map_err(|e| e.to_string())?;
+    if deleted == 0 { return Err(format!("Recurrence {} not found", id)); }
+    conn.execute("DELETE FROM recurrence_exceptions WHERE recurrence_id = ?", params![id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Check that `occurrence_start` is an occurrence the rule actually produces.
+fn require_occurrence(rec: &Recurrence, occurrence_start: NaiveDateTime) -> Result<(), String> {
+    let rule = RRule::parse(&rec.rrule)?;
+    let start = parse_local_datetime(&rec.dtstart)?;
+    if rule.between(start, occurrence_start, occurrence_start).is_empty() {
+        return Err(format!("{} is not an occurrence of recurrence {}", fmt_ts(occurrence_start), rec.id));
+    }
+    Ok(())
+}
+
+/// Record a skip (`new_start` = None) or move of one occurrence, replacing any earlier exception.
+pub fn set_exception(conn: &Connection, recurrence_id: i64, occurrence_start: &str, new_start: Option<&str>) -> Result<(), String> {
+    let rec = get(conn, recurrence_id)?.ok_or_else(|| format!("Recurrence {} not found", recurrence_id))?;
+    let original = parse_local_datetime(occurrence_start)?;
+    require_occurrence(&rec, original)?;
+    let (action, moved_to) = match new_start {
+        Some(s) => ("move", Some(fmt_ts(parse_local_datetime(s)?))),
+        None => ("skip", None),
+    };
+    conn.execute(
+        "INSERT INTO recurrence_exceptions (recurrence_id, occurrence_start, action, new_start) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(recurrence_id, occurrence_start) DO UPDATE SET action = excluded.action, new_start = excluded.new_start",
+        params![recurrence_id, fmt_ts(original), action, moved_to],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn clear_exception(conn: &Connection, recurrence_id: i64, occurrence_start: &str) -> Result<(), String> {
+    ensure_recurrence_tables(conn)?;
+    let original = parse_local_datetime(occurrence_start)?;
+    conn.execute("DELETE FROM recurrence_exceptions WHERE recurrence_id = ? AND occurrence_start = ?", params![recurrence_id, fmt_ts(original)]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Expand every recurrence of `user_id` (all users when `None`) into occurrences that start in
+/// `from..=to`, applying skips and moves, sorted by start.
+pub fn occurrences_between(conn: &Connection, user_id: Option<i64>, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<Occurrence>, String> {
+    let mut out = Vec::new();
+    for rec in list(conn, user_id)? {
+        let rule = match RRule::parse(&rec.rrule) { Ok(r) => r, Err(_) => continue };
+        let start = match parse_local_datetime(&rec.dtstart) { Ok(s) => s, Err(_) => continue };
+        let mut exceptions: std::collections::HashMap<String, Option<String>> = std::collections::HashMap::new();
+        {
+            let mut stmt = conn.prepare("SELECT occurrence_start, action, new_start FROM recurrence_exceptions WHERE recurrence_id = ?").map_err(|e| e.to_string())?;
+            let rows = stmt.query_map(params![rec.id], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?))).map_err(|e| e.to_string())?;
+            for (orig, action, new_start) in rows.flatten() {
+                exceptions.insert(orig, if action == "move" { new_start } else { None });
+            }
+        }
+        let make = |original: NaiveDateTime, actual: NaiveDateTime, moved: bool| Occurrence {
+            recurrence_id: rec.id, kind: rec.kind.clone(), title: rec.title.clone(), details: rec.details.clone(),
+            original_start: fmt_ts(original), start: fmt_ts(actual),
+            end: rec.duration_minutes.map(|m| fmt_ts(actual + ChronoDuration::minutes(m))), moved,
+        };
+        for t in rule.between(start, from, to) {
+            if !exceptions.contains_key(&fmt_ts(t)) { out.push(make(t, t, false)); }
+        }
+        // Moved occurrences are placed by their new start, wherever the original was.
+        for (orig, new_start) in &exceptions {
+            if let (Some(ns), Ok(o)) = (new_start, parse_local_datetime(orig)) {
+                if let Ok(n) = parse_local_datetime(ns) {
+                    if n >= from && n <= to { out.push(make(o, n, true)); }
+                }
+            }
+        }
+    }
+    out.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.recurrence_id.cmp(&b.recurrence_id)));
+    Ok(out)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_recurrence(user_id: i64, kind: String, title: String, details: Option<String>, dtstart: String, duration_minutes: Option<i64>, rrule: String) -> Result<Recurrence, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    create(&conn, user_id, &kind, &title, details.as_deref(), &dtstart, duration_minutes, &rrule)
+}
+
+#[tauri::command]
+pub fn list_recurrences(user_id: i64) -> Result<Vec<Recurrence>, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    list(&conn, Some(user_id))
+}
+
+#[tauri::command]
+pub fn delete_recurrence(user_id: i64, recurrence_id: i64) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    delete(&conn, user_id, recurrence_id)
+}
+
+#[tauri::command]
+pub fn get_recurrence_occurrences(user_id: i64, start_iso: String, end_iso: String) -> Result<Vec<Occurrence>, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    let from = parse_local_datetime(&start_iso)?;
+    let to = match NaiveDate::parse_from_str(end_iso.trim(), "%Y-%m-%d") {
+        Ok(d) => d.and_hms_opt(23, 59, 59).ok_or("invalid date")?,
+        Err(_) => parse_local_datetime(&end_iso)?,
+    };
+    occurrences_between(&conn, Some(user_id), from, to)
+}
+
+#[tauri::command]
+pub fn skip_occurrence(user_id: i64, recurrence_id: i64, occurrence_start: String) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    get_owned(&conn, user_id, recurrence_id)?;
+    set_exception(&conn, recurrence_id, &occurrence_start, None)
+}
+
+#[tauri::command]
+pub fn move_occurrence(user_id: i64, recurrence_id: i64, occurrence_start: String, new_start: String) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    get_owned(&conn, user_id, recurrence_id)?;
+    set_exception(&conn, recurrence_id, &occurrence_start, Some(&new_start))
+}
+
+#[tauri::command]
+pub fn restore_occurrence(user_id: i64, recurrence_id: i64, occurrence_start: String) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    get_owned(&conn, user_id, recurrence_id)?;
+    clear_exception(&conn, recurrence_id, &occurrence_start)
+}
@@ -1,33 +1,390 @@
 use regex::Regex;
+use once_cell::sync::Lazy;
+use rusqlite::{Connection, params};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
 
-/// Redact obvious PII patterns from text. This is a conservative first-pass.
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+
+/// Email addresses. Shared with the outbound privacy filter.
+pub(crate) static EMAIL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+
+/// Phone numbers in common international / NANP layouts. Stricter than a plain digit run so that
+/// ISO dates and clock times in prompts and journals are not mistaken for phone numbers.
+pub(crate) static PHONE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?|\b\d{3}[ .-]?)\d{3}[ .-]?\d{4}\b|\+\d{1,3}(?:[ .-]?\d{2,4}){3,4}\b").unwrap());
+
+static CARD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").unwrap());
+static IBAN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b").unwrap());
+static IPV4_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d{1,3}(?:\.\d{1,3}){3}\b").unwrap());
+static IPV6_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)[0-9a-f]{0,4}(?::[0-9a-f]{0,4}){2,7}").unwrap());
+static STREET_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d{1,5}\s+(?:[A-Z][a-z]+\s+){1,3}(?:Street|St|Avenue|Ave|Road|Rd|Boulevard|Blvd|Lane|Ln|Drive|Dr|Court|Ct|Way|Place|Pl|Terrace|Close)\b\.?").unwrap());
+static JWT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\beyJ[A-Za-z0-9_-]{5,}\.[A-Za-z0-9_-]{5,}\.[A-Za-z0-9_-]{5,}").unwrap());
+static AWS_KEY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:AKIA|ASIA|AGPA|AIDA|AROA)[0-9A-Z]{16}\b").unwrap());
+static GITHUB_TOKEN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:gh[pousr]_[A-Za-z0-9]{36,255}|github_pat_[A-Za-z0-9_]{22,255})\b").unwrap());
+static API_KEY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(?:sk|api|token|key)[-_ ]?[A-Za-z0-9]{20,}\b").unwrap());
+static HIGH_ENTROPY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Za-z0-9+/=_-]{24,}").unwrap());
+
+// very small profanity list for demo
+const PROFANITY: [&str; 3] = ["shit", "fuck", "bitch"];
+const MAX_CONTENT_LEN: usize = 10000;
+
+/// What to do with content matched by a detector or rule.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyAction {
+    Redact,
+    Block,
+    Warn,
+}
+
+impl SafetyAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SafetyAction::Redact => "redact",
+            SafetyAction::Block => "block",
+            SafetyAction::Warn => "warn",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<SafetyAction> {
+        match s.to_lowercase().as_str() {
+            "redact" => Some(SafetyAction::Redact),
+            "block" => Some(SafetyAction::Block),
+            "warn" => Some(SafetyAction::Warn),
+            _ => None,
+        }
+    }
+}
+
+/// Built-in detectors and their default actions.
+pub const BUILTIN_DETECTORS: [(&str, SafetyAction); 13] = [
+    ("email", SafetyAction::Redact),
+    ("phone", SafetyAction::Redact),
+    ("credit_card", SafetyAction::Redact),
+    ("iban", SafetyAction::Redact),
+    ("ip_address", SafetyAction::Redact),
+    ("street_address", SafetyAction::Redact),
+    ("jwt", SafetyAction::Redact),
+    ("aws_access_key", SafetyAction::Redact),
+    ("github_token", SafetyAction::Redact),
+    ("api_key", SafetyAction::Redact),
+    ("high_entropy", SafetyAction::Redact),
+    ("profanity", SafetyAction::Block),
+    ("content_length", SafetyAction::Block),
+];
+
+/// A user-defined regex rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyRule {
+    pub id: Option<i64>,
+    pub name: String,
+    pub pattern: String,
+    pub action: SafetyAction,
+}
+
+/// Detector configuration for one scan: per-detector action overrides (`None` disables a
+/// detector) plus custom rules.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SafetyConfig {
+    pub overrides: HashMap<String, Option<SafetyAction>>,
+    pub rules: Vec<SafetyRule>,
+}
+
+impl SafetyConfig {
+    fn action_for(&self, detector: &str, default: SafetyAction) -> Option<SafetyAction> {
+        match self.overrides.get(detector) {
+            Some(over) => *over,
+            None => Some(default),
+        }
+    }
+}
+
+/// One match. Offsets are byte offsets into the scanned text; `line` is 1-based.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyFinding {
+    pub detector: String,
+    pub action: SafetyAction,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub preview: String,
+}
+
+/// Result of a scan: everything found, whether the content must be blocked, and the text with
+/// all `redact` findings replaced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyReport {
+    pub findings: Vec<SafetyFinding>,
+    pub blocked: bool,
+    pub block_reasons: Vec<String>,
+    pub warnings: Vec<String>,
+    pub redacted: String,
+}
+
+fn luhn_valid(digits: &[u32]) -> bool {
+    if digits.len() < 13 || digits.len() > 19 { return false; }
+    let mut sum = 0;
+    for (i, d) in digits.iter().rev().enumerate() {
+        let mut v = *d;
+        if i % 2 == 1 {
+            v *= 2;
+            if v > 9 { v -= 9; }
+        }
+        sum += v;
+    }
+    sum % 10 == 0
+}
+
+fn iban_valid(candidate: &str) -> bool {
+    let compact: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() < 15 || compact.len() > 34 { return false; }
+    let rearranged = format!("{}{}", &compact[4..], &compact[..4]);
+    let mut rem: u32 = 0;
+    for c in rearranged.chars() {
+        let v = match c.to_digit(36) { Some(v) => v, None => return false };
+        rem = if v >= 10 { (rem * 100 + v) % 97 } else { (rem * 10 + v) % 97 };
+    }
+    rem == 1
+}
+
+/// Shannon entropy in bits per character.
+fn shannon_entropy(s: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in s.chars() { *counts.entry(c).or_insert(0) += 1; }
+    let len = s.chars().count() as f64;
+    counts.values().map(|n| { let p = *n as f64 / len; -p * p.log2() }).sum()
+}
+
+fn preview(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() <= 6 { return "***".to_string(); }
+    format!("{}…{}", chars[..2].iter().collect::<String>(), chars[chars.len() - 2..].iter().collect::<String>())
+}
+
+/// Whether `text[start..end]` reads as an IPv6 address rather than a path like `std::fs` or a lone
+/// `::`. The match must stand alone (no word character or colon on either side) and have at least
+/// three groups, or a full group on the left of `::` and one on the right (`fe80::1`).
+fn ipv6_like(text: &str, start: usize, end: usize) -> bool {
+    let attached = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == ':');
+    if attached(text[..start].chars().next_back()) || attached(text[end..].chars().next()) { return false; }
+    let s = &text[start..end];
+    let groups: Vec<&str> = s.split(':').filter(|g| !g.is_empty()).collect();
+    let shaped = groups.len() >= 3 || s.split_once("::").is_some_and(|(a, b)| a.len() == 4 && !b.is_empty() && !b.contains(':'));
+    shaped && s.parse::<std::net::Ipv6Addr>().is_ok()
+}
+
+fn builtin_matches(detector: &str, text: &str) -> Vec<(usize, usize)> {
+    let spans = |re: &Regex| re.find_iter(text).map(|m| (m.start(), m.end())).collect::<Vec<_>>();
+    match detector {
+        "email" => spans(&EMAIL_RE),
+        "phone" => spans(&PHONE_RE),
+        "credit_card" => CARD_RE.find_iter(text)
+            .filter(|m| luhn_valid(&m.as_str().chars().filter_map(|c| c.to_digit(10)).collect::<Vec<_>>()))
+            .map(|m| (m.start(), m.end())).collect(),
+        "iban" => IBAN_RE.find_iter(text).filter(|m| iban_valid(m.as_str())).map(|m| (m.start(), m.end())).collect(),
+        "ip_address" => {
+            let mut out: Vec<(usize, usize)> = IPV4_RE.find_iter(text)
+                .filter(|m| m.as_str().parse::<std::net::Ipv4Addr>().is_ok())
+                .map(|m| (m.start(), m.end())).collect();
+            out.extend(IPV6_RE.find_iter(text)
+                .filter(|m| ipv6_like(text, m.start(), m.end()))
+                .map(|m| (m.start(), m.end())));
+            out
+        }
+        "street_address" => spans(&STREET_RE),
+        "jwt" => spans(&JWT_RE),
+        "aws_access_key" => spans(&AWS_KEY_RE),
+        "github_token" => spans(&GITHUB_TOKEN_RE),
+        "api_key" => spans(&API_KEY_RE),
+        "high_entropy" => HIGH_ENTROPY_RE.find_iter(text)
+            .filter(|m| {
+                let s = m.as_str();
+                s.chars().any(|c| c.is_ascii_digit()) && s.chars().any(|c| c.is_ascii_alphabetic()) && shannon_entropy(s) >= 4.0
+            })
+            .map(|m| (m.start(), m.end())).collect(),
+        "profanity" => {
+            // ASCII lowercasing keeps byte offsets aligned with the original text
+            let lower = text.to_ascii_lowercase();
+            let mut out = Vec::new();
+            for p in &PROFANITY {
+                for (i, _) in lower.match_indices(p) { out.push((i, i + p.len())); }
+            }
+            out
+        }
+        "content_length" => if text.len() > MAX_CONTENT_LEN { vec![(0, text.len())] } else { vec![] },
+        _ => vec![],
+    }
+}
+
+/// Run every enabled detector and custom rule over `text` and build a report.
+pub fn scan(text: &str, config: &SafetyConfig) -> SafetyReport {
+    let mut findings: Vec<SafetyFinding> = Vec::new();
+    let line_of = |pos: usize| text[..pos].matches('\n').count() + 1;
+
+    for (detector, default) in BUILTIN_DETECTORS.iter() {
+        let action = match config.action_for(detector, *default) { Some(a) => a, None => continue };
+        for (start, end) in builtin_matches(detector, text) {
+            // high-entropy is a catch-all; don't double-report spans a specific detector already found
+            if *detector == "high_entropy" && findings.iter().any(|f| f.start < end && start < f.end) { continue; }
+            let preview = if *detector == "content_length" { format!("{} bytes", text.len()) } else { preview(&text[start..end]) };
+            findings.push(SafetyFinding { detector: detector.to_string(), action, start, end, line: line_of(start), preview });
+        }
+    }
+    for rule in &config.rules {
+        let re = match Regex::new(&rule.pattern) { Ok(r) => r, Err(_) => continue };
+        for m in re.find_iter(text).filter(|m| m.start() < m.end()) {
+            findings.push(SafetyFinding { detector: format!("custom:{}", rule.name), action: rule.action, start: m.start(), end: m.end(), line: line_of(m.start()), preview: preview(m.as_str()) });
+        }
+    }
+    findings.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+    let mut block_reasons = Vec::new();
+    let mut warnings = Vec::new();
+    for f in &findings {
+        let msg = format!("{} at line {}", f.detector, f.line);
+        match f.action {
+            SafetyAction::Block => if !block_reasons.contains(&msg) { block_reasons.push(msg) },
+            SafetyAction::Warn => if !warnings.contains(&msg) { warnings.push(msg) },
+            SafetyAction::Redact => {}
+        }
+    }
+
+    // Rewrite in one pass; overlapping redactions keep the earliest/longest span
+    let mut redacted = String::with_capacity(text.len());
+    let mut pos = 0;
+    for f in findings.iter().filter(|f| f.action == SafetyAction::Redact) {
+        if f.start < pos { continue; }
+        redacted.push_str(&text[pos..f.start]);
+        let label = match f.detector.as_str() {
+            "api_key" => "KEY".to_string(),
+            d => d.trim_start_matches("custom:").to_uppercase(),
+        };
+        redacted.push_str(&format!("[REDACTED_{}]", label));
+        pos = f.end;
+    }
+    redacted.push_str(&text[pos..]);
+
+    SafetyReport { blocked: !block_reasons.is_empty(), findings, block_reasons, warnings, redacted }
+}
+
+/// Redact obvious PII patterns from text using the default detector set.
 pub fn redact_pii(input: &str) -> String {
-    let mut out = input.to_string();
-    // emails
-    let email_re = Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap();
-    out = email_re.replace_all(&out, "[REDACTED_EMAIL]").to_string();
-    // phone numbers (simple)
-    let phone_re = Regex::new(r"(?m)(?:\+?\d[\d .-]{7,}\d)").unwrap();
-    out = phone_re.replace_all(&out, "[REDACTED_PHONE]").to_string();
-    // api keys like strings (long alphanumeric)
-    let apikey_re = Regex::new(r"(?i)\b(?:sk|api|token|key)[-_ ]?[A-Za-z0-9]{20,}\b").unwrap();
-    out = apikey_re.replace_all(&out, "[REDACTED_KEY]").to_string();
-    out
+    scan(input, &SafetyConfig::default()).redacted
 }
 
 /// Basic policy check: returns Ok(()) if safe, Err(reason) if violation found.
 pub fn policy_check(input: &str) -> Result<(), String> {
-    // very small profanity list for demo
-    let profane = ["shit", "fuck", "bitch"];
-    let lower = input.to_lowercase();
-    for p in &profane {
-        if lower.contains(p) {
-            return Err(format!("policy_violation: found disallowed word: {}", p));
-        }
+    let report = scan(input, &SafetyConfig::default());
+    if report.blocked {
+        return Err(format!("policy_violation: {}", report.block_reasons.join(", ")));
+    }
+    Ok(())
+}
+
+fn ensure_safety_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS safety_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            action TEXT NOT NULL,
+            created_at TEXT DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS safety_detector_overrides (
+            user_id INTEGER NOT NULL,
+            detector TEXT NOT NULL,
+            action TEXT NOT NULL,
+            PRIMARY KEY(user_id, detector)
+        );
+    "#).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Load a user's detector overrides and custom rules.
+pub fn load_safety_config(conn: &Connection, user_id: i64) -> Result<SafetyConfig, String> {
+    ensure_safety_tables(conn)?;
+    let mut config = SafetyConfig::default();
+    let mut stmt = conn.prepare("SELECT detector, action FROM safety_detector_overrides WHERE user_id = ?").map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params![user_id]).map_err(|e| e.to_string())?;
+    while let Some(r) = rows.next().map_err(|e| e.to_string())? {
+        let detector: String = r.get(0).map_err(|e| e.to_string())?;
+        let action: String = r.get(1).map_err(|e| e.to_string())?;
+        config.overrides.insert(detector, SafetyAction::parse(&action));
     }
-    // if too long (excessively large) consider it suspicious
-    if input.len() > 10000 {
-        return Err("policy_violation: content too large".to_string());
+    let mut stmt = conn.prepare("SELECT id, name, pattern, action FROM safety_rules WHERE user_id = ? ORDER BY id").map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(params![user_id]).map_err(|e| e.to_string())?;
+    while let Some(r) = rows.next().map_err(|e| e.to_string())? {
+        let action: String = r.get(3).map_err(|e| e.to_string())?;
+        config.rules.push(SafetyRule {
+            id: r.get(0).map_err(|e| e.to_string())?,
+            name: r.get(1).map_err(|e| e.to_string())?,
+            pattern: r.get(2).map_err(|e| e.to_string())?,
+            action: SafetyAction::parse(&action).unwrap_or(SafetyAction::Warn),
+        });
+    }
+    Ok(config)
+}
+
+/// Convenience used by the provider layer: the user's config from the personality DB.
+pub fn safety_config_for_user(user_id: i64) -> Result<SafetyConfig, String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    load_safety_config(&conn, user_id)
+}
+
+#[tauri::command]
+pub fn scan_content(user_id: i64, text: String) -> Result<SafetyReport, String> {
+    let config = safety_config_for_user(user_id)?;
+    Ok(scan(&text, &config))
+}
+
+#[tauri::command]
+pub fn get_safety_config(user_id: i64) -> Result<SafetyConfig, String> {
+    safety_config_for_user(user_id)
+}
+
+/// Create or update a custom rule; returns its id.
+#[tauri::command]
+pub fn save_safety_rule(user_id: i64, rule: SafetyRule) -> Result<i64, String> {
+    Regex::new(&rule.pattern).map_err(|e| format!("invalid pattern: {}", e))?;
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    ensure_safety_tables(&conn)?;
+    if let Some(id) = rule.id {
+        conn.execute("UPDATE safety_rules SET name = ?, pattern = ?, action = ? WHERE id = ? AND user_id = ?", params![rule.name, rule.pattern, rule.action.as_str(), id, user_id]).map_err(|e| e.to_string())?;
+        Ok(id)
+    } else {
+        conn.execute("INSERT INTO safety_rules (user_id, name, pattern, action) VALUES (?, ?, ?, ?)", params![user_id, rule.name, rule.pattern, rule.action.as_str()]).map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+#[tauri::command]
+pub fn delete_safety_rule(user_id: i64, rule_id: i64) -> Result<(), String> {
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    ensure_safety_tables(&conn)?;
+    conn.execute("DELETE FROM safety_rules WHERE id = ? AND user_id = ?", params![rule_id, user_id]).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Override a built-in detector's action. `action` is "redact" | "block" | "warn" | "off";
+/// `None` restores the default.
+#[tauri::command]
+pub fn set_safety_detector_action(user_id: i64, detector: String, action: Option<String>) -> Result<(), String> {
+    if !BUILTIN_DETECTORS.iter().any(|(d, _)| *d == detector) {
+        return Err(format!("Unknown detector: {}", detector));
+    }
+    let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+    ensure_safety_tables(&conn)?;
+    match action {
+        None => {
+            conn.execute("DELETE FROM safety_detector_overrides WHERE user_id = ? AND detector = ?", params![user_id, detector]).map_err(|e| e.to_string())?;
+        }
+        Some(a) => {
+            if a != "off" && SafetyAction::parse(&a).is_none() {
+                return Err(format!("Unknown action: {}", a));
+            }
+            conn.execute("INSERT OR REPLACE INTO safety_detector_overrides (user_id, detector, action) VALUES (?, ?, ?)", params![user_id, detector, a]).map_err(|e| e.to_string())?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,283 @@
+//! Full-text search over journal entries (personality DB), goal titles and descriptions (workspace
+//! goals store) and the free-text columns of the daily DBs: session notes, event `details_json` and
+//! distraction reasons.
+//!
+//! Each database gets an FTS5 table kept current by triggers, so every write path (commands,
+//! serial bridge, imports) updates the index without going through this module. Daily indexes are
+//! created and backfilled when `utility::ensure_daily_db` creates or migrates a daily DB; a file
+//! that predates the index is migrated once, the first time it is searched.
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params};
+use chrono::NaiveDate;
+
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+use crate::backend::{goals, utility};
+
+pub const KIND_JOURNAL: &str = "journal";
+pub const KIND_SESSION: &str = "session";
+pub const KIND_EVENT: &str = "event";
+pub const KIND_DISTRACTION: &str = "distraction";
+pub const KIND_GOAL: &str = "goal";
+pub const ALL_KINDS: [&str; 5] = [KIND_JOURNAL, KIND_SESSION, KIND_EVENT, KIND_DISTRACTION, KIND_GOAL];
+
+const DEFAULT_LIMIT: usize = 50;
+const SNIPPET_TOKENS: i64 = 12;
+
+/// Daily DB sources: (table, kind, text column, timestamp column).
+const DAILY_SOURCES: [(&str, &str, &str, &str); 3] = [
+    ("session", KIND_SESSION, "notes", "start_time"),
+    ("event", KIND_EVENT, "details_json", "event_time"),
+    ("distraction", KIND_DISTRACTION, "reason", "created_at"),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub query: String,
+    /// Restrict to these kinds (see `ALL_KINDS`); empty means all.
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    /// Inclusive `YYYY-MM-DD` bounds.
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHit {
+    pub kind: String,
+    /// Row id in the source table.
+    pub ref_id: i64,
+    /// Day the row belongs to (`YYYY-MM-DD`).
+    pub date: String,
+    /// Matched text with hits wrapped in `[` `]`.
+    pub snippet: String,
+    /// Higher is more relevant (negated bm25).
+    pub score: f64,
+}
+
+fn table_exists(conn: &Connection, name: &str) -> bool {
+    conn.query_row("SELECT 1 FROM sqlite_master WHERE name = ?", params![name], |_| Ok(())).is_ok()
+}
+
+/// Create the journal index and its triggers in the personality DB, backfilling on first creation.
+pub fn ensure_journal_index(conn: &Connection) -> Result<(), String> {
+    if !table_exists(conn, "journal_entries") { return Ok(()); }
+    let created = !table_exists(conn, "journal_fts");
+    conn.execute_batch(r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS journal_fts USING fts5(content, content='journal_entries', content_rowid='id', tokenize='porter unicode61');
+        CREATE TRIGGER IF NOT EXISTS journal_fts_ai AFTER INSERT ON journal_entries BEGIN
+            INSERT INTO journal_fts(rowid, content) VALUES (new.id, new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS journal_fts_ad AFTER DELETE ON journal_entries BEGIN
+            INSERT INTO journal_fts(journal_fts, rowid, content) VALUES ('delete', old.id, old.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS journal_fts_au AFTER UPDATE OF content ON journal_entries BEGIN
+            INSERT INTO journal_fts(journal_fts, rowid, content) VALUES ('delete', old.id, old.content);
+            INSERT INTO journal_fts(rowid, content) VALUES (new.id, new.content);
+        END;
+    "#).map_err(|e| e.to_string())?;
+    if created {
+        conn.execute("INSERT INTO journal_fts(journal_fts) VALUES ('rebuild')", []).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Create the goal index and its triggers in the goals store, backfilling on first creation.
+pub fn ensure_goal_index(conn: &Connection) -> Result<(), String> {
+    if !table_exists(conn, "goals") { return Ok(()); }
+    let created = !table_exists(conn, "goal_fts");
+    conn.execute_batch(r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS goal_fts USING fts5(title, description, content='goals', content_rowid='id', tokenize='porter unicode61');
+        CREATE TRIGGER IF NOT EXISTS goal_fts_ai AFTER INSERT ON goals BEGIN
+            INSERT INTO goal_fts(rowid, title, description) VALUES (new.id, new.title, new.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS goal_fts_ad AFTER DELETE ON goals BEGIN
+            INSERT INTO goal_fts(goal_fts, rowid, title, description) VALUES ('delete', old.id, old.title, old.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS goal_fts_au AFTER UPDATE OF title, description ON goals BEGIN
+            INSERT INTO goal_fts(goal_fts, rowid, title, description) VALUES ('delete', old.id, old.title, old.description);
+            INSERT INTO goal_fts(rowid, title, description) VALUES (new.id, new.title, new.description);
+        END;
+    "#).map_err(|e| e.to_string())?;
+    if created {
+        conn.execute("INSERT INTO goal_fts(goal_fts) VALUES ('rebuild')", []).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Create the daily index and per-table triggers in a daily DB, backfilling on first creation.
+/// Only tables and columns that exist in this DB are wired up, so older files are handled too.
+pub fn ensure_daily_index(conn: &Connection) -> Result<(), String> {
+    let created = !table_exists(conn, "daily_fts");
+    conn.execute_batch("CREATE VIRTUAL TABLE IF NOT EXISTS daily_fts USING fts5(text, kind UNINDEXED, ref_id UNINDEXED, at UNINDEXED, tokenize='porter unicode61');")
+        .map_err(|e| e.to_string())?;
+    for (table, kind, text_col, at_col) in DAILY_SOURCES {
+        let cols = utility::table_columns(conn, table);
+        if !cols.contains(text_col) { continue; }
+        let at_new = if cols.contains(at_col) { format!("COALESCE(new.{}, '')", at_col) } else { "''".to_string() };
+        let sql = format!(r#"
+            CREATE TRIGGER IF NOT EXISTS daily_fts_{t}_ai AFTER INSERT ON {t} WHEN new.{c} IS NOT NULL AND new.{c} <> '' BEGIN
+                INSERT INTO daily_fts(text, kind, ref_id, at) VALUES (new.{c}, '{k}', new.id, {at});
+            END;
+            CREATE TRIGGER IF NOT EXISTS daily_fts_{t}_ad AFTER DELETE ON {t} BEGIN
+                DELETE FROM daily_fts WHERE kind = '{k}' AND ref_id = old.id;
+            END;
+            CREATE TRIGGER IF NOT EXISTS daily_fts_{t}_au AFTER UPDATE ON {t} BEGIN
+                DELETE FROM daily_fts WHERE kind = '{k}' AND ref_id = old.id;
+                INSERT INTO daily_fts(text, kind, ref_id, at) SELECT new.{c}, '{k}', new.id, {at} WHERE new.{c} IS NOT NULL AND new.{c} <> '';
+            END;
+        "#, t = table, c = text_col, k = kind, at = at_new);
+        conn.execute_batch(&sql).map_err(|e| e.to_string())?;
+        if created {
+            let at_sel = if cols.contains(at_col) { format!("COALESCE({}, '')", at_col) } else { "''".to_string() };
+            conn.execute(&format!("INSERT INTO daily_fts(text, kind, ref_id, at) SELECT {c}, '{k}', id, {at} FROM {t} WHERE {c} IS NOT NULL AND {c} <> ''", c = text_col, k = kind, at = at_sel, t = table), [])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Turn free user input into a safe FTS5 MATCH expression: every word becomes a quoted prefix
+/// term and terms are ANDed. Returns `None` when nothing searchable is left.
+pub fn sanitize_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|t| t.trim_matches('\'').replace('\'', ""))
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{}\"*", t))
+        .collect();
+    if terms.is_empty() { None } else { Some(terms.join(" ")) }
+}
+
+fn parse_bound(s: &Option<String>) -> Result<Option<NaiveDate>, String> {
+    match s.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d").map(Some).map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", d)),
+    }
+}
+
+fn in_range(d: NaiveDate, start: Option<NaiveDate>, end: Option<NaiveDate>) -> bool {
+    start.map(|s| d >= s).unwrap_or(true) && end.map(|e| d <= e).unwrap_or(true)
+}
+
+fn wants(q: &SearchQuery, kind: &str) -> bool {
+    q.kinds.is_empty() || q.kinds.iter().any(|k| k.eq_ignore_ascii_case(kind))
+}
+
+fn search_journal(conn: &Connection, user_id: i64, expr: &str, start: Option<NaiveDate>, end: Option<NaiveDate>, limit: usize) -> Result<Vec<SearchHit>, String> {
+    ensure_journal_index(conn)?;
+    if !table_exists(conn, "journal_fts") { return Ok(vec![]); }
+    let sql = format!(
+        "SELECT j.id, substr(j.created_at, 1, 10), snippet(journal_fts, 0, '[', ']', '…', {}), bm25(journal_fts) \
+         FROM journal_fts JOIN journal_entries j ON j.id = journal_fts.rowid \
+         WHERE journal_fts MATCH ?1 AND j.user_id = ?2 \
+           AND (?3 IS NULL OR substr(j.created_at, 1, 10) >= ?3) AND (?4 IS NULL OR substr(j.created_at, 1, 10) <= ?4) \
+         ORDER BY bm25(journal_fts) LIMIT ?5", SNIPPET_TOKENS);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let start_s = start.map(|d| d.format("%Y-%m-%d").to_string());
+    let end_s = end.map(|d| d.format("%Y-%m-%d").to_string());
+    let rows = stmt.query_map(params![expr, user_id, start_s, end_s, limit as i64], |r| {
+        Ok(SearchHit { kind: KIND_JOURNAL.to_string(), ref_id: r.get(0)?, date: r.get(1)?, snippet: r.get(2)?, score: -r.get::<_, f64>(3)? })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Goals are dated by their deadline, or the day they were created when they have none.
+fn search_goals(conn: &Connection, expr: &str, start: Option<NaiveDate>, end: Option<NaiveDate>, limit: usize) -> Result<Vec<SearchHit>, String> {
+    if !table_exists(conn, "goal_fts") { return Ok(vec![]); }
+    let sql = format!(
+        "SELECT g.id, substr(COALESCE(g.deadline, g.created_at), 1, 10) AS day, snippet(goal_fts, -1, '[', ']', '…', {}), bm25(goal_fts) \
+         FROM goal_fts JOIN goals g ON g.id = goal_fts.rowid \
+         WHERE goal_fts MATCH ?1 AND (?2 IS NULL OR day >= ?2) AND (?3 IS NULL OR day <= ?3) \
+         ORDER BY bm25(goal_fts) LIMIT ?4", SNIPPET_TOKENS);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let start_s = start.map(|d| d.format("%Y-%m-%d").to_string());
+    let end_s = end.map(|d| d.format("%Y-%m-%d").to_string());
+    let rows = stmt.query_map(params![expr, start_s, end_s, limit as i64], |r| {
+        Ok(SearchHit { kind: KIND_GOAL.to_string(), ref_id: r.get(0)?, date: r.get(1)?, snippet: r.get(2)?, score: -r.get::<_, f64>(3)? })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn search_daily(conn: &Connection, date: NaiveDate, q: &SearchQuery, expr: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+    let kinds: Vec<&str> = DAILY_SOURCES.iter().map(|(_, k, _, _)| *k).filter(|k| wants(q, k)).collect();
+    if kinds.is_empty() { return Ok(vec![]); }
+    let placeholders: Vec<String> = (0..kinds.len()).map(|i| format!("?{}", i + 3)).collect();
+    let sql = format!(
+        "SELECT kind, ref_id, snippet(daily_fts, 0, '[', ']', '…', {}), bm25(daily_fts) FROM daily_fts \
+         WHERE daily_fts MATCH ?1 AND kind IN ({}) ORDER BY bm25(daily_fts) LIMIT ?2", SNIPPET_TOKENS, placeholders.join(", "));
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut args: Vec<&dyn rusqlite::ToSql> = vec![&expr];
+    let lim = limit as i64;
+    args.push(&lim);
+    for k in &kinds { args.push(k); }
+    let day = date.format("%Y-%m-%d").to_string();
+    let rows = stmt.query_map(args.as_slice(), |r| {
+        Ok(SearchHit { kind: r.get(0)?, ref_id: r.get(1)?, date: day.clone(), snippet: r.get(2)?, score: -r.get::<_, f64>(3)? })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Search journal entries in `personality`, the goals store and every daily DB in `workspace_dir`
+/// within the date range, merging hits by relevance.
+pub fn search_all(personality: &Connection, user_id: i64, workspace_dir: Option<&str>, q: &SearchQuery) -> Result<Vec<SearchHit>, String> {
+    let expr = match sanitize_query(&q.query) { Some(e) => e, None => return Ok(vec![]) };
+    let start = parse_bound(&q.start_date)?;
+    let end = parse_bound(&q.end_date)?;
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+
+    let mut hits = Vec::new();
+    if wants(q, KIND_JOURNAL) {
+        hits.extend(search_journal(personality, user_id, &expr, start, end, limit)?);
+    }
+    if wants(q, KIND_GOAL) {
+        if let Some(store) = goals::open_store_for_read(workspace_dir)? {
+            hits.extend(search_goals(&store, &expr, start, end, limit)?);
+        }
+    }
+    if DAILY_SOURCES.iter().any(|(_, k, _, _)| wants(q, k)) {
+        for (date, path) in utility::list_daily_dbs(workspace_dir) {
+            if !in_range(date, start, end) { continue; }
+            let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+            if !table_exists(&conn, "daily_fts") {
+                utility::ensure_daily_db(workspace_dir, date)?;
+            }
+            hits.extend(search_daily(&conn, date, q, &expr, limit)?);
+        }
+    }
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal).then_with(|| b.date.cmp(&a.date)));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+#[tauri::command]
+pub async fn search(user_id: i64, query: SearchQuery, workspace_dir: Option<String>) -> Result<Vec<SearchHit>, String> {
+    tokio::task::spawn_blocking(move || {
+        let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+        search_all(&conn, user_id, workspace_dir.as_deref(), &query)
+    }).await.map_err(|e| e.to_string())?
+}
+
+/// Drop and rebuild every index (personality DB, goals store and all daily DBs). Returns the number
+/// of DBs indexed.
+#[tauri::command]
+pub async fn rebuild_search_index(workspace_dir: Option<String>) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+        conn.execute_batch("DROP TABLE IF EXISTS journal_fts;").map_err(|e| e.to_string())?;
+        ensure_journal_index(&conn)?;
+        let mut count = 1;
+        if let Some(store) = goals::open_store_for_read(workspace_dir.as_deref())? {
+            store.execute_batch("DROP TABLE IF EXISTS goal_fts;").map_err(|e| e.to_string())?;
+            ensure_goal_index(&store)?;
+            count += 1;
+        }
+        for (_, path) in utility::list_daily_dbs(workspace_dir.as_deref()) {
+            let daily = Connection::open(&path).map_err(|e| e.to_string())?;
+            daily.execute_batch("DROP TABLE IF EXISTS daily_fts;").map_err(|e| e.to_string())?;
+            ensure_daily_index(&daily)?;
+            count += 1;
+        }
+        Ok(count)
+    }).await.map_err(|e| e.to_string())?
+}
@@ -0,0 +1,304 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{Connection, params};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::collections::HashMap;
+use crate::backend::{goal_time, local_journal, utility};
+
+// ---------- focus streaks ----------
+//
+// A streak runs over every day from the oldest daily DB in the workspace up to the given day.
+// A day qualifies when it has at least `min_focus_minutes` of net focus (finished sessions on
+// session cards, distraction intervals cut out), or when the core card was tapped awake inside
+// the wake window; with `require_both` it needs both. Days without a daily DB simply do not
+// qualify.
+//
+// Up to `rest_days_per_week` non-qualifying days per ISO week are rest days: they keep the streak
+// alive without counting toward it. The day being evaluated never breaks a streak while it is
+// still in progress.
+//
+// Without explicit criteria the focus threshold is the user's `min_session_time` setting.
+
+/// Same default as the `min_session_time` user setting.
+pub const DEFAULT_MIN_FOCUS_MINUTES: i64 = 25;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StreakCriteria {
+    /// Net focused minutes a day needs; 0 turns the focus rule off.
+    pub min_focus_minutes: i64,
+    /// Wake window as "HH:MM"; the wake rule only applies when both ends are set.
+    pub wake_window_start: Option<String>,
+    pub wake_window_end: Option<String>,
+    /// Qualify only when both rules are met (when the wake rule applies).
+    pub require_both: bool,
+    pub rest_days_per_week: i64,
+}
+
+impl Default for StreakCriteria {
+    fn default() -> Self {
+        StreakCriteria { min_focus_minutes: DEFAULT_MIN_FOCUS_MINUTES, wake_window_start: None, wake_window_end: None, require_both: false, rest_days_per_week: 0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Streak {
+    /// First and last qualifying day (YYYY-MM-DD).
+    pub start: String,
+    pub end: String,
+    /// Qualifying days in the streak.
+    pub days: i64,
+    /// Rest days used between `start` and `end` (or after `end`, for the current streak).
+    pub rest_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreakDay {
+    pub day: String,
+    pub focus_minutes: i64,
+    /// Earliest core-card wake tap of the day ("HH:MM").
+    pub wake_time: Option<String>,
+    pub qualified: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreakReport {
+    pub as_of: String,
+    pub current: Option<Streak>,
+    pub longest: Option<Streak>,
+    pub qualified_today: bool,
+    pub rest_days_left_this_week: i64,
+    /// Qualifying days across the whole history.
+    pub total_qualified_days: i64,
+    /// Days since the oldest daily DB, including `as_of`.
+    pub history_days: i64,
+}
+
+/// `min_session_time` from `conn`'s `user_setting` table, for `user_id` when given (otherwise the
+/// most recently updated value). `None` when unset or unreadable.
+pub fn min_session_setting(conn: &Connection, user_id: Option<i64>) -> Option<i64> {
+    conn.query_row(
+        "SELECT value FROM user_setting WHERE key = 'min_session_time' AND (?1 IS NULL OR user_id = ?1) ORDER BY updated_at DESC LIMIT 1",
+        params![user_id], |r| r.get::<_, String>(0),
+    ).ok().and_then(|v| v.trim().parse::<i64>().ok()).map(|m| m.max(0))
+}
+
+/// The criteria used when a caller passes none: the defaults, with the focus threshold taken from
+/// the newest daily DB that stores a `min_session_time` setting.
+pub fn default_criteria(workspace_dir: Option<&str>, user_id: Option<i64>) -> StreakCriteria {
+    let mut dbs = utility::list_daily_dbs(workspace_dir);
+    dbs.sort_by_key(|(d, _)| std::cmp::Reverse(*d));
+    let min_focus_minutes = dbs.iter()
+        .find_map(|(_, path)| Connection::open(path).ok().and_then(|conn| min_session_setting(&conn, user_id)))
+        .unwrap_or(DEFAULT_MIN_FOCUS_MINUTES);
+    StreakCriteria { min_focus_minutes, ..StreakCriteria::default() }
+}
+
+fn parse_clock(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").map_err(|e| format!("Invalid time '{}': {}", s, e))
+}
+
+/// Net focused minutes of a daily DB: finished sessions on session cards minus distraction
+/// intervals.
+pub fn day_focus_minutes(conn: &Connection, day: NaiveDate) -> i64 {
+    let gaps = goal_time::distraction_intervals(conn, day);
+    let mut stmt = match conn.prepare("SELECT s.start_time, s.end_time FROM session s JOIN card c ON c.id = s.card_id WHERE c.type = 'session' AND s.end_time IS NOT NULL") {
+        Ok(s) => s,
+        Err(_) => return 0,
+    };
+    let rows = match stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))) { Ok(r) => r, Err(_) => return 0 };
+    rows.flatten()
+        .filter_map(|(s, e)| Some((local_journal::parse_timestamp(&s, day)?, local_journal::parse_timestamp(&e, day)?)))
+        .filter(|(s, e)| e > s)
+        .map(|(s, e)| goal_time::net_minutes(s, e, &gaps))
+        .sum()
+}
+
+/// Wake taps of a daily DB, from the `core_card_tap` log (when the DB has one) and from events on
+/// the core card or with a `wake` event type. Sleep taps are left out.
+pub fn day_wake_taps(conn: &Connection, day: NaiveDate) -> Vec<NaiveDateTime> {
+    let mut raw: Vec<String> = Vec::new();
+    let cols = utility::table_columns(conn, "core_card_tap");
+    let time_col = if cols.contains("time") { Some("time") } else if cols.contains("tap_time") { Some("tap_time") } else { None };
+    if let Some(time_col) = time_col {
+        let filter = if cols.contains("tap_type") { "WHERE tap_type = 'wake'" } else if cols.contains("label") { "WHERE label IS NULL OR LOWER(label) NOT LIKE '%sleep%'" } else { "" };
+        if let Ok(mut stmt) = conn.prepare(&format!("SELECT {} FROM core_card_tap {}", time_col, filter)) {
+            if let Ok(rows) = stmt.query_map([], |r| r.get::<_, Option<String>>(0)) { raw.extend(rows.flatten().flatten()); }
+        }
+    }
+    let sql = "SELECT e.event_time FROM event e LEFT JOIN card c ON c.id = e.card_id
+        WHERE (LOWER(e.event_type) LIKE '%wake%' OR c.type = 'core') AND LOWER(e.event_type) NOT LIKE '%sleep%'";
+    if let Ok(mut stmt) = conn.prepare(sql) {
+        if let Ok(rows) = stmt.query_map([], |r| r.get::<_, Option<String>>(0)) { raw.extend(rows.flatten().flatten()); }
+    }
+    let mut taps: Vec<NaiveDateTime> = raw.iter().filter_map(|s| local_journal::parse_timestamp(s, day)).collect();
+    taps.sort();
+    taps
+}
+
+/// Day-by-day qualification from the oldest daily DB through `until`.
+pub fn streak_days(workspace_dir: Option<&str>, until: NaiveDate, criteria: &StreakCriteria) -> Result<Vec<StreakDay>, String> {
+    let window = match (&criteria.wake_window_start, &criteria.wake_window_end) {
+        (Some(s), Some(e)) => Some((parse_clock(s)?, parse_clock(e)?)),
+        _ => None,
+    };
+    let dbs: HashMap<NaiveDate, std::path::PathBuf> = utility::list_daily_dbs(workspace_dir).into_iter().filter(|(d, _)| *d <= until).collect();
+    let first = match dbs.keys().min() { Some(d) => *d, None => return Ok(vec![]) };
+
+    let mut out = Vec::new();
+    let mut day = first;
+    while day <= until {
+        let (focus_minutes, wake) = match dbs.get(&day).and_then(|p| Connection::open(p).ok()) {
+            Some(conn) => {
+                let taps = day_wake_taps(&conn, day);
+                let in_window = match window {
+                    Some((start, end)) => taps.iter().find(|t| t.date() == day && t.time() >= start && t.time() <= end).copied(),
+                    None => None,
+                };
+                (day_focus_minutes(&conn, day), in_window.or_else(|| taps.first().copied()).map(|t| (t, in_window.is_some())))
+            }
+            None => (0, None),
+        };
+        let focus_ok = criteria.min_focus_minutes > 0 && focus_minutes >= criteria.min_focus_minutes;
+        let wake_ok = window.is_some() && wake.is_some_and(|(_, inside)| inside);
+        let qualified = if criteria.require_both && window.is_some() {
+            wake_ok && (focus_ok || criteria.min_focus_minutes <= 0)
+        } else {
+            focus_ok || wake_ok
+        };
+        out.push(StreakDay {
+            day: day.format("%Y-%m-%d").to_string(),
+            focus_minutes,
+            wake_time: wake.map(|(t, _)| t.format("%H:%M").to_string()),
+            qualified,
+        });
+        day += Duration::days(1);
+    }
+    Ok(out)
+}
+
+struct Run {
+    start: NaiveDate,
+    end: NaiveDate,
+    days: i64,
+    rest_days: i64,
+    /// Rest days counted between `start` and `end`; trailing ones only matter for a live streak.
+    settled_rest_days: i64,
+}
+
+impl Run {
+    fn to_streak(&self, settled: bool) -> Streak {
+        Streak {
+            start: self.start.format("%Y-%m-%d").to_string(),
+            end: self.end.format("%Y-%m-%d").to_string(),
+            days: self.days,
+            rest_days: if settled { self.settled_rest_days } else { self.rest_days },
+        }
+    }
+}
+
+/// Result of walking the days once: the live run, the longest finished or live run, and the rest
+/// days used in the last ISO week seen.
+struct Walk {
+    run: Option<Run>,
+    longest: Option<Streak>,
+    rest_week: Option<(i32, u32)>,
+    rest_used: i64,
+}
+
+/// Walk `parsed` in order, treating the last day as today (still in progress). `visit` sees each
+/// day with the run that is live after it.
+fn walk(parsed: &[(NaiveDate, bool)], rest_days_per_week: i64, mut visit: impl FnMut(NaiveDate, Option<&Run>)) -> Walk {
+    let last = parsed.last().map(|(d, _)| *d);
+    let mut w = Walk { run: None, longest: None, rest_week: None, rest_used: 0 };
+    let keep = |r: &Run, longest: &mut Option<Streak>| {
+        if longest.as_ref().is_none_or(|l| r.days >= l.days) { *longest = Some(r.to_streak(true)); }
+    };
+
+    for &(day, qualified) in parsed {
+        let week = (day.iso_week().year(), day.iso_week().week());
+        if w.rest_week != Some(week) { w.rest_week = Some(week); w.rest_used = 0; }
+        if qualified {
+            match w.run.as_mut() {
+                Some(r) => { r.end = day; r.days += 1; r.settled_rest_days = r.rest_days; }
+                None => w.run = Some(Run { start: day, end: day, days: 1, rest_days: 0, settled_rest_days: 0 }),
+            }
+        } else if Some(day) == last {
+            // Today is still in progress
+        } else if w.run.is_some() && w.rest_used < rest_days_per_week {
+            w.rest_used += 1;
+            if let Some(r) = w.run.as_mut() { r.rest_days += 1; }
+        } else if let Some(r) = w.run.take() {
+            keep(&r, &mut w.longest);
+        }
+        visit(day, w.run.as_ref());
+    }
+    if let Some(r) = &w.run { keep(r, &mut w.longest); }
+    w
+}
+
+fn parse_days(days: &[StreakDay]) -> Vec<(NaiveDate, bool)> {
+    days.iter().filter_map(|d| NaiveDate::parse_from_str(&d.day, "%Y-%m-%d").ok().map(|n| (n, d.qualified))).collect()
+}
+
+/// Current and longest streaks over `days` (as returned by `streak_days`). The longest streak is
+/// the one with the most qualifying days; ties go to the most recent.
+pub fn compute_streaks(days: &[StreakDay], rest_days_per_week: i64) -> StreakReport {
+    let parsed = parse_days(days);
+    let last = parsed.last().map(|(d, _)| *d);
+    let Walk { run, longest, rest_week, rest_used } = walk(&parsed, rest_days_per_week, |_, _| {});
+
+    // Rest days still available this week to protect the current streak
+    let today_week = last.map(|d| (d.iso_week().year(), d.iso_week().week()));
+    let used_this_week = if rest_week == today_week { rest_used } else { 0 };
+    StreakReport {
+        as_of: last.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+        current: run.as_ref().map(|r| r.to_streak(false)),
+        longest,
+        qualified_today: parsed.last().is_some_and(|(_, q)| *q),
+        rest_days_left_this_week: (rest_days_per_week - used_this_week).max(0),
+        total_qualified_days: parsed.iter().filter(|(_, q)| *q).count() as i64,
+        history_days: parsed.len() as i64,
+    }
+}
+
+/// Length of the current streak at the end of each of `days`, in one pass with the last day as
+/// today. A day that broke the streak reads 0.
+pub fn current_streak_by_day(days: &[StreakDay], rest_days_per_week: i64) -> Vec<(NaiveDate, i64)> {
+    let mut out = Vec::with_capacity(days.len());
+    walk(&parse_days(days), rest_days_per_week, |day, run| out.push((day, run.map(|r| r.days).unwrap_or(0))));
+    out
+}
+
+/// Current and longest focus streaks across all daily DBs, as of `date` (default today). Without
+/// `criteria`, `user_id`'s `min_session_time` setting sets the focus threshold.
+#[tauri::command]
+pub fn get_streaks(workspace_dir: Option<String>, date: Option<String>, criteria: Option<StreakCriteria>, user_id: Option<i64>) -> Result<StreakReport, String> {
+    let day = match date {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))?,
+        None => Local::now().date_naive(),
+    };
+    let criteria = criteria.unwrap_or_else(|| default_criteria(workspace_dir.as_deref(), user_id));
+    if criteria.rest_days_per_week < 0 || criteria.rest_days_per_week > 6 { return Err("rest_days_per_week must be between 0 and 6".to_string()); }
+    let days = streak_days(workspace_dir.as_deref(), day, &criteria)?;
+    let mut report = compute_streaks(&days, criteria.rest_days_per_week);
+    report.as_of = day.format("%Y-%m-%d").to_string();
+    Ok(report)
+}
+
+/// The per-day qualification behind `get_streaks`, for calendars, between `from` and `date`.
+#[tauri::command]
+pub fn get_streak_calendar(workspace_dir: Option<String>, from: Option<String>, date: Option<String>, criteria: Option<StreakCriteria>, user_id: Option<i64>) -> Result<Vec<StreakDay>, String> {
+    let day = match date {
+        Some(d) => NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", d, e))?,
+        None => Local::now().date_naive(),
+    };
+    let criteria = criteria.unwrap_or_else(|| default_criteria(workspace_dir.as_deref(), user_id));
+    let days = streak_days(workspace_dir.as_deref(), day, &criteria)?;
+    Ok(match from {
+        Some(f) => {
+            NaiveDate::parse_from_str(&f, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", f, e))?;
+            days.into_iter().filter(|d| d.day >= f).collect()
+        }
+        None => days,
+    })
+}
@@ -1,4 +1,5 @@
 use rusqlite::Connection;
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 use chrono::NaiveDate;
@@ -83,3 +84,222 @@ pub fn find_daily_db(workspace_dir: Option<String>, date: Option<NaiveDate>) ->
     }
     None
 }
+
+/// Strict lookup of the daily DB for exactly `date` (`focusd_YYYY-MM-DD` or `focusd_YYYYMMDD`
+/// with a .sqlite3/.sqlite/.db extension). Unlike `find_daily_db` this never falls back to
+/// another day's file, so it is safe for multi-day scans.
+pub fn daily_db_path(workspace_dir: Option<&str>, date: NaiveDate) -> Option<PathBuf> {
+    let base = PathBuf::from(workspace_dir.unwrap_or("."));
+    for pfx in [format!("focusd_{}", date.format("%Y-%m-%d")), format!("focusd_{}", date.format("%Y%m%d"))] {
+        for ext in [".sqlite3", ".sqlite", ".db"] {
+            let cand = base.join(format!("{}{}", pfx, ext));
+            if cand.is_file() { return Some(cand); }
+        }
+    }
+    None
+}
+
+/// Column names of `table`; empty when the table does not exist.
+pub fn table_columns(conn: &Connection, table: &str) -> HashSet<String> {
+    let mut cols = HashSet::new();
+    if let Ok(mut s) = conn.prepare(&format!("PRAGMA table_info({})", table)) {
+        if let Ok(rows) = s.query_map([], |r| r.get::<_, String>(1)) { cols.extend(rows.flatten()); }
+    }
+    cols
+}
+
+/// Path of the workspace-wide store `file` in `workspace_dir`. Store file names deliberately omit
+/// the `focusd_` prefix, which `find_daily_db` treats as a daily DB.
+pub fn workspace_store_path(workspace_dir: Option<&str>, file: &str) -> PathBuf {
+    PathBuf::from(workspace_dir.unwrap_or(".")).join(file)
+}
+
+/// File in the workspace holding its identifier (see `workspace_id`).
+const WORKSPACE_ID_FILE: &str = "workspace_id";
+
+/// Random identifier of `workspace_dir`, created on first use. Lets exports from this workspace be
+/// told apart from those of another device or workspace.
+pub fn workspace_id(workspace_dir: Option<&str>) -> Result<String, String> {
+    let base = PathBuf::from(workspace_dir.unwrap_or("."));
+    let path = base.join(WORKSPACE_ID_FILE);
+    if let Ok(id) = fs::read_to_string(&path) {
+        let id = id.trim();
+        if !id.is_empty() { return Ok(id.to_string()); }
+    }
+    let mut bytes = [0u8; 8];
+    rand::RngCore::fill_bytes(&mut rand::rng(), &mut bytes);
+    let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    fs::create_dir_all(&base).map_err(|e| format!("Failed to create workspace dir: {}", e))?;
+    fs::write(&path, &id).map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Tables every daily DB carries. Statements are idempotent so they can be re-applied to DBs
+/// created by older versions.
+pub const DAILY_DB_SCHEMA: [&str; 11] = [
+    // User profile (singleton row)
+    r#"CREATE TABLE IF NOT EXISTS user_profile (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        name TEXT,
+        onboarding_complete INTEGER DEFAULT 0,
+        personality_json TEXT,
+        created_at TEXT DEFAULT (datetime('now')),
+        updated_at TEXT DEFAULT (datetime('now'))
+    );"#,
+
+    // Card (core, session, event, distraction)
+    r#"CREATE TABLE IF NOT EXISTS card (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        rfid TEXT NOT NULL,
+        type TEXT NOT NULL CHECK (type IN ('core', 'session', 'event', 'distraction')),
+        label TEXT,
+        color TEXT,
+        metadata_json TEXT,
+        created_at TEXT DEFAULT (datetime('now')),
+        updated_at TEXT DEFAULT (datetime('now')),
+        UNIQUE(rfid, type)
+    );"#,
+
+    // Session (tracks focus sessions)
+    r#"CREATE TABLE IF NOT EXISTS session (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        card_id INTEGER NOT NULL,
+        start_time TEXT NOT NULL,
+        end_time TEXT,
+        notes TEXT,
+        ai_summary TEXT,
+        created_at TEXT DEFAULT (datetime('now')),
+        updated_at TEXT DEFAULT (datetime('now')),
+        FOREIGN KEY(card_id) REFERENCES card(id)
+    );"#,
+
+    // Event (arbitrary events, e.g. context switches)
+    r#"CREATE TABLE IF NOT EXISTS event (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        card_id INTEGER,
+        event_type TEXT NOT NULL,
+        event_time TEXT NOT NULL,
+        details_json TEXT,
+        created_at TEXT DEFAULT (datetime('now')),
+        FOREIGN KEY(card_id) REFERENCES card(id)
+    );"#,
+
+    // Distraction (tracks interruptions)
+    r#"CREATE TABLE IF NOT EXISTS distraction (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id INTEGER,
+        event_id INTEGER,
+        reason TEXT,
+        resolved INTEGER DEFAULT 0,
+        created_at TEXT DEFAULT (datetime('now')),
+        start_time TEXT,
+        end_time TEXT,
+        FOREIGN KEY(session_id) REFERENCES session(id),
+        FOREIGN KEY(event_id) REFERENCES event(id)
+    );"#,
+
+    // Goal (user goals, can be linked to cards)
+    r#"CREATE TABLE IF NOT EXISTS goal (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        card_id INTEGER,
+        description TEXT NOT NULL,
+        target_date TEXT,
+        completed INTEGER DEFAULT 0,
+        created_at TEXT DEFAULT (datetime('now')),
+        updated_at TEXT DEFAULT (datetime('now')),
+        FOREIGN KEY(card_id) REFERENCES card(id)
+    );"#,
+
+    // Alarm (reminders, can be linked to cards)
+    r#"CREATE TABLE IF NOT EXISTS alarm (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        card_id INTEGER,
+        alarm_time TEXT NOT NULL,
+        label TEXT,
+        triggered INTEGER DEFAULT 0,
+        created_at TEXT DEFAULT (datetime('now')),
+        FOREIGN KEY(card_id) REFERENCES card(id)
+    );"#,
+
+    // Log (arbitrary logs, for debugging/auditing)
+    r#"CREATE TABLE IF NOT EXISTS log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        log_time TEXT DEFAULT (datetime('now')),
+        level TEXT,
+        message TEXT,
+        details_json TEXT
+    );"#,
+
+    // Workspace config/state (singleton row)
+    r#"CREATE TABLE IF NOT EXISTS workspace_config (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        config_json TEXT,
+        created_at TEXT DEFAULT (datetime('now')),
+        updated_at TEXT DEFAULT (datetime('now'))
+    );"#,
+
+    // Reminder (free-text reminders, optionally at a time)
+    r#"CREATE TABLE IF NOT EXISTS reminder (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        text TEXT NOT NULL,
+        remind_at TEXT,
+        created_at TEXT DEFAULT (datetime('now'))
+    );"#,
+
+    // Scheduled session (planned focus blocks, e.g. accepted from a daily plan)
+    r#"CREATE TABLE IF NOT EXISTS scheduled_session (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT NOT NULL,
+        start_time TEXT NOT NULL,
+        end_time TEXT,
+        planned_minutes INTEGER,
+        goal_id INTEGER,
+        plan_item_id INTEGER,
+        status TEXT DEFAULT 'scheduled',
+        created_at TEXT DEFAULT (datetime('now'))
+    );"#,
+];
+
+/// Open the daily DB for `date`, creating `focusd_YYYY-MM-DD.sqlite3` in `workspace_dir` if none
+/// exists, and make sure the current schema is applied.
+pub fn ensure_daily_db(workspace_dir: Option<&str>, date: NaiveDate) -> Result<PathBuf, String> {
+    let path = match daily_db_path(workspace_dir, date) {
+        Some(p) => p,
+        None => {
+            let base = PathBuf::from(workspace_dir.unwrap_or("."));
+            fs::create_dir_all(&base).map_err(|e| format!("Failed to create workspace dir: {}", e))?;
+            base.join(format!("focusd_{}.sqlite3", date.format("%Y-%m-%d")))
+        }
+    };
+    let conn = Connection::open(&path).map_err(|e| e.to_string())?;
+    for stmt in DAILY_DB_SCHEMA.iter() {
+        conn.execute(stmt, []).map_err(|e| format!("Schema error: {}\nSQL: {}", e, stmt))?;
+    }
+    crate::backend::distractions::ensure_interval_columns(&conn)?;
+    crate::backend::search::ensure_daily_index(&conn)?;
+    Ok(path)
+}
+
+/// All daily DBs in `workspace_dir` whose file name encodes a date, sorted by date. When two files
+/// exist for the same day the first naming scheme found wins.
+pub fn list_daily_dbs(workspace_dir: Option<&str>) -> Vec<(NaiveDate, PathBuf)> {
+    let base = PathBuf::from(workspace_dir.unwrap_or("."));
+    let mut out: Vec<(NaiveDate, PathBuf)> = Vec::new();
+    if let Ok(entries) = fs::read_dir(&base) {
+        for e in entries.flatten() {
+            let path = e.path();
+            if !path.is_file() { continue; }
+            let stem = match path.file_name().and_then(|s| s.to_str()) {
+                Some(f) if f.ends_with(".sqlite3") || f.ends_with(".sqlite") || f.ends_with(".db") => f.split('.').next().unwrap_or(""),
+                _ => continue,
+            };
+            let date_part = match stem.strip_prefix("focusd_") { Some(d) => d, None => continue };
+            let date = NaiveDate::parse_from_str(date_part, "%Y-%m-%d").or_else(|_| NaiveDate::parse_from_str(date_part, "%Y%m%d"));
+            if let Ok(d) = date {
+                if !out.iter().any(|(od, _)| *od == d) { out.push((d, path)); }
+            }
+        }
+    }
+    out.sort_by_key(|(d, _)| *d);
+    out
+}
@@ -0,0 +1,252 @@
+//! Markdown vault export (Obsidian-compatible): one `Journal/YYYY-MM-DD.md` per day with YAML
+//! front-matter, wiki-linked to `Goals/<goal>.md` and `Weekly/YYYY-Www.md` rollup notes.
+//!
+//! Every exported note carries `focusd_hash`, the SHA-256 of the note as written (minus the hash
+//! line). On the next sync a note is rewritten only if its generated content changed and the file
+//! on disk still matches its hash; files edited outside the app, or created by the user, are left
+//! alone and reported.
+use serde::{Serialize, Deserialize};
+use rusqlite::Connection;
+use chrono::{Datelike, NaiveDate, Duration as ChronoDuration};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::goals::{self, GoalItem};
+use crate::backend::journals::{self, JournalEntry};
+use crate::backend::local_journal;
+use crate::backend::personality_db::PERSONALITY_DB_PATH;
+use crate::backend::retrospective::{self, PERIOD_WEEK};
+use crate::backend::sessions;
+
+pub const HASH_KEY: &str = "focusd_hash";
+const JOURNAL_DIR: &str = "Journal";
+const GOALS_DIR: &str = "Goals";
+const WEEKLY_DIR: &str = "Weekly";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteStatus {
+    Written,
+    Unchanged,
+    /// The file was changed outside the app (or never exported by it) and was not overwritten.
+    UserEdited,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultExportReport {
+    /// Vault-relative paths.
+    pub written: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub skipped_user_edited: Vec<String>,
+}
+
+impl VaultExportReport {
+    fn record(&mut self, rel: String, status: NoteStatus) {
+        match status {
+            NoteStatus::Written => self.written.push(rel),
+            NoteStatus::Unchanged => self.unchanged.push(rel),
+            NoteStatus::UserEdited => self.skipped_user_edited.push(rel),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GoalMention {
+    deadline: Option<String>,
+    completed: bool,
+    days: Vec<NaiveDate>,
+}
+
+fn sha256_hex(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The text a note's hash covers: the note with line endings normalised and the hash line removed.
+fn hashed_text(note: &str) -> String {
+    note.replace("\r\n", "\n").lines()
+        .filter(|l| !l.starts_with(&format!("{}:", HASH_KEY)))
+        .collect::<Vec<_>>().join("\n")
+}
+
+/// Value of `focusd_hash` in the note's front-matter, if any.
+pub fn stored_hash(note: &str) -> Option<String> {
+    let mut lines = note.lines();
+    if lines.next().map(str::trim) != Some("---") { return None; }
+    for l in lines {
+        if l.trim() == "---" { break; }
+        if let Some(v) = l.strip_prefix(&format!("{}:", HASH_KEY)) { return Some(v.trim().to_string()); }
+    }
+    None
+}
+
+/// Quote a YAML scalar when it could otherwise be misread.
+fn yaml_str(s: &str) -> String {
+    let plain = !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '/')) && !s.starts_with(' ') && !s.ends_with(' ');
+    if plain { s.to_string() } else { format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")) }
+}
+
+/// Render front-matter + body, appending the content hash as the last front-matter key.
+pub fn render_note(front: &[(&str, String)], body: &str) -> String {
+    let mut fm = String::from("---\n");
+    for (k, v) in front { fm.push_str(&format!("{}: {}\n", k, v)); }
+    let unhashed = format!("{}---\n\n{}", fm, body.trim_end());
+    let hash = sha256_hex(&hashed_text(&unhashed));
+    format!("{}{}: {}\n---\n\n{}\n", fm, HASH_KEY, hash, body.trim_end())
+}
+
+/// Write `note` to `path` unless the existing file was edited outside the app.
+pub fn sync_note(path: &Path, note: &str) -> Result<NoteStatus, String> {
+    if let Ok(existing) = fs::read_to_string(path) {
+        let stored = match stored_hash(&existing) { Some(h) => h, None => return Ok(NoteStatus::UserEdited) };
+        if sha256_hex(&hashed_text(&existing)) != stored { return Ok(NoteStatus::UserEdited); }
+        if stored_hash(note).as_deref() == Some(stored.as_str()) { return Ok(NoteStatus::Unchanged); }
+    }
+    if let Some(parent) = path.parent() { fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+    fs::write(path, note).map_err(|e| e.to_string())?;
+    Ok(NoteStatus::Written)
+}
+
+/// File-name-safe version of a goal title (Obsidian forbids `[]#^|\/:` and friends).
+pub fn note_name(title: &str) -> String {
+    let cleaned: String = title.chars().map(|c| if "\\/:*?\"<>|[]#^".contains(c) { ' ' } else { c }).collect();
+    let name = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() { "Untitled goal".to_string() } else { name.chars().take(100).collect() }
+}
+
+pub fn week_name(d: NaiveDate) -> String {
+    d.format("%G-W%V").to_string()
+}
+
+/// Goals open on `date` as (title, deadline, completed by then).
+fn goals_on(rows: &[GoalItem], date: NaiveDate) -> Vec<(String, Option<String>, bool)> {
+    rows.iter().filter(|g| goals::active_on(g, date)).map(|g| (g.title.clone(), g.deadline.clone(), goals::completed_by(g, date))).collect()
+}
+
+fn tags_yaml(tags: &[String]) -> String {
+    format!("[{}]", tags.iter().map(|t| yaml_str(t)).collect::<Vec<_>>().join(", "))
+}
+
+fn daily_note(e: &JournalEntry, day: NaiveDate, day_count: i64, focus_minutes: i64, sessions: usize, goal_names: &[String]) -> String {
+    let week = week_name(day);
+    let front = vec![
+        ("date", day.to_string()),
+        ("day", day_count.to_string()),
+        ("focus_minutes", focus_minutes.to_string()),
+        ("sessions", sessions.to_string()),
+        ("mood", e.mood.map(|m| m.to_string()).unwrap_or_default()),
+        ("energy", e.energy.map(|m| m.to_string()).unwrap_or_default()),
+        ("tags", tags_yaml(&e.tags)),
+        ("week", format!("\"[[{}/{}|{}]]\"", WEEKLY_DIR, week, week)),
+        ("focusd_entry_id", e.id.to_string()),
+        ("focusd_revision", e.current_revision.to_string()),
+    ];
+    let mut body = format!("# {}\n\n{}\n", day.format("%A, %B %-d, %Y"), e.content.trim());
+    if let Some(r) = e.reflection.as_deref().filter(|r| !r.trim().is_empty()) {
+        body.push_str(&format!("\n## Reflection\n{}\n", r.trim()));
+    }
+    if !goal_names.is_empty() {
+        body.push_str("\n## Goals\n");
+        for g in goal_names { body.push_str(&format!("- [[{}/{}|{}]]\n", GOALS_DIR, g, g)); }
+    }
+    body.push_str(&format!("\nWeek: [[{}/{}|{}]]\n", WEEKLY_DIR, week, week));
+    render_note(&front, &body)
+}
+
+fn goal_note(name: &str, g: &GoalMention) -> String {
+    let front = vec![
+        ("goal", yaml_str(name)),
+        ("deadline", g.deadline.clone().unwrap_or_default()),
+        ("completed", g.completed.to_string()),
+    ];
+    let mut body = format!("# {}\n\n## Mentioned in\n", name);
+    for d in &g.days { body.push_str(&format!("- [[{}/{}|{}]]\n", JOURNAL_DIR, d, d.format("%a %-d %b %Y"))); }
+    render_note(&front, &body)
+}
+
+fn weekly_note(week: &str, start: NaiveDate, days: &[(NaiveDate, i64)], retro: Option<&str>) -> String {
+    let total: i64 = days.iter().map(|(_, m)| m).sum();
+    let front = vec![
+        ("week", week.to_string()),
+        ("start", start.to_string()),
+        ("end", (start + ChronoDuration::days(6)).to_string()),
+        ("focus_minutes", total.to_string()),
+    ];
+    let mut body = format!("# Week {}\n\n## Days\n", week);
+    for (d, m) in days { body.push_str(&format!("- [[{}/{}|{}]] ({} min)\n", JOURNAL_DIR, d, d.format("%A"), m)); }
+    if let Some(r) = retro { body.push_str(&format!("\n## Retrospective\n{}\n", r.trim())); }
+    render_note(&front, &body)
+}
+
+/// Export `user_id`'s journal entries (optionally only from `since`) into `vault_dir`. Goal and
+/// weekly notes touched by the exported days are rebuilt from the full history, so a partial sync
+/// never shrinks them.
+pub fn export_vault(conn: &Connection, user_id: i64, workspace_dir: Option<&str>, vault_dir: &Path, since: Option<NaiveDate>) -> Result<VaultExportReport, String> {
+    let start = NaiveDate::from_ymd_opt(1, 1, 1).ok_or("invalid date")?;
+    let end = NaiveDate::from_ymd_opt(9999, 12, 31).ok_or("invalid date")?;
+    let since = since.unwrap_or(start);
+    let since_week_start = since - ChronoDuration::days(since.weekday().num_days_from_monday() as i64);
+    let entries = journals::entries_between(conn, user_id, start, end)?;
+    let retros: BTreeMap<String, String> = retrospective::list_retros(conn, user_id, Some(PERIOD_WEEK))?
+        .into_iter().map(|r| (r.period_start, r.content)).collect();
+
+    let goal_rows = match goals::open_store_for_read(workspace_dir)? {
+        Some(store) => goals::list_goal_rows(&store, None)?,
+        None => vec![],
+    };
+
+    let mut report = VaultExportReport::default();
+    let mut mentions: BTreeMap<String, GoalMention> = BTreeMap::new();
+    let mut touched_goals = std::collections::HashSet::new();
+    let mut weeks: BTreeMap<String, (NaiveDate, Vec<(NaiveDate, i64)>)> = BTreeMap::new();
+    let mut seen_days = std::collections::HashSet::new();
+    for e in &entries {
+        let day = match NaiveDate::parse_from_str(&e.entry_date, "%Y-%m-%d") { Ok(d) => d, Err(_) => continue };
+        if !seen_days.insert(day) { continue; }
+        let mut names = Vec::new();
+        for (title, deadline, completed) in goals_on(&goal_rows, day) {
+            let name = note_name(&title);
+            let g = mentions.entry(name.clone()).or_insert(GoalMention { deadline: None, completed: false, days: vec![] });
+            if deadline.as_deref().map(|d| !d.is_empty()).unwrap_or(false) { g.deadline = deadline; }
+            g.completed = completed;
+            if !g.days.contains(&day) { g.days.push(day); }
+            if !names.contains(&name) { names.push(name); }
+        }
+        // Days before the week `since` falls in only feed the goal notes
+        let week_start = day - ChronoDuration::days(day.weekday().num_days_from_monday() as i64);
+        if week_start < since_week_start { continue; }
+        let stats = local_journal::day_stats(day, &sessions::get_sessions_for_date(workspace_dir, day)?, &[]);
+        weeks.entry(week_name(day)).or_insert((week_start, vec![])).1.push((day, stats.focus_minutes));
+        if day < since { continue; }
+
+        touched_goals.extend(names.iter().cloned());
+        let day_count = journals::day_count_on(conn, user_id, day)?;
+        let rel = format!("{}/{}.md", JOURNAL_DIR, day);
+        let status = sync_note(&vault_dir.join(&rel), &daily_note(e, day, day_count, stats.focus_minutes, stats.session_count, &names))?;
+        report.record(rel, status);
+    }
+    for (name, g) in mentions.iter().filter(|(name, _)| touched_goals.contains(*name)) {
+        let rel = format!("{}/{}.md", GOALS_DIR, name);
+        report.record(rel.clone(), sync_note(&vault_dir.join(&rel), &goal_note(name, g))?);
+    }
+    for (week, (week_start, days)) in &weeks {
+        if !days.iter().any(|(d, _)| *d >= since) { continue; }
+        let rel = format!("{}/{}.md", WEEKLY_DIR, week);
+        let note = weekly_note(week, *week_start, days, retros.get(&week_start.to_string()).map(String::as_str));
+        report.record(rel.clone(), sync_note(&vault_dir.join(&rel), &note)?);
+    }
+    Ok(report)
+}
+
+/// Sync the Markdown vault at `vault_dir`. `since` (`YYYY-MM-DD`) limits the days exported.
+#[tauri::command]
+pub async fn export_journal_vault(user_id: i64, vault_dir: String, workspace_dir: Option<String>, since: Option<String>) -> Result<VaultExportReport, String> {
+    let since = match since {
+        Some(s) => Some(NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(|e| e.to_string())?),
+        None => None,
+    };
+    tokio::task::spawn_blocking(move || {
+        let conn = Connection::open(PERSONALITY_DB_PATH).map_err(|e| e.to_string())?;
+        export_vault(&conn, user_id, workspace_dir.as_deref(), &PathBuf::from(vault_dir), since)
+    }).await.map_err(|e| e.to_string())?
+}
@@ -219,76 +219,30 @@ fn get_metric_trend(
     db_path: String,
     user_id: i64,
     metric: String,
-    days: i64,
-    aggregation: Option<String>, // "day", "week", "month"
+    days: i64, // number of points
+    aggregation: Option<String>, // "day", "week" (ISO), "month" (calendar)
+    reducer: Option<String>, // "avg" (default), "sum", "max", "median", "p90", ...
     locale: Option<String>,
 ) -> Result<AnalyticsResponse<Vec<TrendPoint>>, String> {
     // Validate user_id and metric
     if user_id <= 0 { return Err("Invalid user_id".to_string()); }
-    let allowed_metrics = vec!["focus_score", "burnout", "punctuality", "streaks"];
-    if !allowed_metrics.contains(&metric.as_str()) {
+    if !backend::analytics::METRICS.contains(&metric.as_str()) {
         return Err("Unsupported metric".to_string());
     }
     let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
-    let today = chrono::Utc::now().date_naive();
-    let mut trend = Vec::new();
+    let today = chrono::Local::now().date_naive();
     let mut warnings = Vec::new();
     let agg = aggregation.unwrap_or("day".to_string());
-    for i in 0..days {
-        let (date, label) = match agg.as_str() {
-            "week" => {
-                let week_start = today - ChronoDuration::days(i * 7);
-                (week_start, format!("week_of_{}", week_start))
-            },
-            "month" => {
-                let month_start = today - ChronoDuration::days(i * 30);
-                (month_start, format!("month_of_{}", month_start))
-            },
-            _ => (today - ChronoDuration::days(i), (today - ChronoDuration::days(i)).to_string()),
-        };
-        let value = match metric.as_str() {
-            "focus_score" => {
-                let mut stmt = conn.prepare("SELECT AVG(focus_score) FROM session WHERE user_id = ? AND DATE(start_time) = ?").map_err(|e| e.to_string())?;
-                let mut rows = stmt.query(params![user_id, date.to_string()]).map_err(|e| e.to_string())?;
-                if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-                    row.get::<_, Option<f64>>(0).unwrap_or(None).unwrap_or(0.0)
-                } else { 0.0 }
-            },
-            "burnout" => {
-                let mut stmt = conn.prepare("SELECT AVG(burnout_score) FROM session WHERE user_id = ? AND DATE(start_time) = ?").map_err(|e| e.to_string())?;
-                let mut rows = stmt.query(params![user_id, date.to_string()]).map_err(|e| e.to_string())?;
-                if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-                    row.get::<_, Option<f64>>(0).unwrap_or(None).unwrap_or(0.0)
-                } else { 0.0 }
-            },
-            "punctuality" => {
-                let mut stmt = conn.prepare("SELECT AVG(status = 'on_time') FROM punctuality_log WHERE user_id = ? AND DATE(actual_time) = ?").map_err(|e| e.to_string())?;
-                let mut rows = stmt.query(params![user_id, date.to_string()]).map_err(|e| e.to_string())?;
-                if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-                    row.get::<_, Option<f64>>(0).unwrap_or(None).unwrap_or(0.0)
-                } else { 0.0 }
-            },
-            "streaks" => {
-                // Streaks: count consecutive days with at least one session
-                let mut stmt = conn.prepare("SELECT COUNT(*) FROM session WHERE user_id = ? AND DATE(start_time) = ?").map_err(|e| e.to_string())?;
-                let mut rows = stmt.query(params![user_id, date.to_string()]).map_err(|e| e.to_string())?;
-                if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-                    if row.get::<_, Option<i64>>(0).unwrap_or(Some(0)).unwrap_or(0) > 0 { 1.0 } else { 0.0 }
-                } else { 0.0 }
-            },
-            _ => 0.0
-        };
-        trend.push(TrendPoint { date: label, value });
-    }
-    trend.reverse();
-    // Anomaly detection: warn if any value is 0 or outlier
-    let mean = if trend.is_empty() { 0.0 } else { trend.iter().map(|p| p.value).sum::<f64>() / trend.len() as f64 };
-    let stddev = if trend.len() < 2 { 0.0 } else {
-        (trend.iter().map(|p| (p.value - mean).powi(2)).sum::<f64>() / (trend.len() as f64 - 1.0)).sqrt()
+    let trend = backend::analytics::metric_trend(&conn, user_id, &metric, days, &agg, reducer.as_deref().unwrap_or("avg"), today)?;
+    // Anomaly detection over the points that have data: warn on gaps and outliers
+    let with_data: Vec<&TrendPoint> = trend.iter().filter(|p| p.contributing_days > 0).collect();
+    let mean = if with_data.is_empty() { 0.0 } else { with_data.iter().map(|p| p.value).sum::<f64>() / with_data.len() as f64 };
+    let stddev = if with_data.len() < 2 { 0.0 } else {
+        (with_data.iter().map(|p| (p.value - mean).powi(2)).sum::<f64>() / (with_data.len() as f64 - 1.0)).sqrt()
     };
     for p in &trend {
-        if p.value == 0.0 {
-            warnings.push(AnalyticsWarning { message: "No data for some days".to_string(), code: "no_data".to_string() });
+        if p.contributing_days == 0 {
+            warnings.push(AnalyticsWarning { message: format!("No data for {}", p.date), code: "no_data".to_string() });
         } else if stddev > 0.0 && (p.value - mean).abs() > 2.0 * stddev {
             warnings.push(AnalyticsWarning { message: format!("Anomaly detected on {}", p.date), code: "anomaly".to_string() });
         }
@@ -307,12 +261,16 @@ use chrono::{Duration as ChronoDuration};
 pub struct TrendPoint {
     pub date: String,
     pub value: f64,
+    /// Days with data behind this point; 0 means "no data" rather than a zero value.
+    #[serde(default)]
+    pub contributing_days: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Forecast {
     pub metric: String,
     pub forecast_points: Vec<TrendPoint>,
+    /// Prediction interval of the first forecast day.
     pub confidence_interval: Option<(f64, f64)>,
 }
 
@@ -320,7 +278,20 @@ pub struct Forecast {
 pub struct Recommendation {
     pub message: String,
     pub reason: String,
+    /// Start of a concrete open slot (`YYYY-MM-DDTHH:MM:SS`) for the suggested session.
     pub recommended_time: Option<String>,
+    #[serde(default)]
+    pub recommended_minutes: Option<i64>,
+}
+
+/// First open calendar slot today or tomorrow that fits a `minutes`-long session, looking in the
+/// workspace that holds `db_path`.
+fn next_open_slot(db_path: &str, user_id: i64, minutes: i64) -> Option<String> {
+    let workspace = std::path::Path::new(db_path).parent().map(|p| p.to_string_lossy().to_string()).filter(|p| !p.is_empty());
+    let now = chrono::Local::now().naive_local();
+    let hours = backend::orchestrator::WorkingHours::default();
+    let slots = backend::orchestrator::find_slots(workspace, Some(user_id), now.date(), now.date() + ChronoDuration::days(1), minutes, &hours, Some(now)).ok()?;
+    slots.first().map(|s| s.start.clone())
 }
 
 #[tauri::command]
@@ -333,9 +304,9 @@ fn get_focus_trend(db_path: String, user_id: i64, days: i64) -> Result<Vec<Trend
         let mut stmt = conn.prepare("SELECT AVG(focus_score) FROM session WHERE user_id = ? AND DATE(start_time) = ?").map_err(|e| e.to_string())?;
         let mut rows = stmt.query(params![user_id, date.to_string()]).map_err(|e| e.to_string())?;
         let value = if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-            row.get::<_, Option<f64>>(0).unwrap_or(None).unwrap_or(0.0)
-        } else { 0.0 };
-        trend.push(TrendPoint { date: date.to_string(), value });
+            row.get::<_, Option<f64>>(0).unwrap_or(None)
+        } else { None };
+        trend.push(TrendPoint { date: date.to_string(), value: value.unwrap_or(0.0), contributing_days: value.is_some() as i64 });
     }
     trend.reverse();
     Ok(trend)
@@ -343,33 +314,14 @@ fn get_focus_trend(db_path: String, user_id: i64, days: i64) -> Result<Vec<Trend
 
 #[tauri::command]
 fn forecast_productivity(db_path: String, user_id: i64, days_ahead: i64) -> Result<Forecast, String> {
-    // Simple linear forecast based on last 14 days
-    let trend = get_focus_trend(db_path.clone(), user_id, 14)?;
-    let n = trend.len() as f64;
-    if n < 2.0 {
-        return Err("Not enough data for forecast".to_string());
-    }
-    let sum_x: f64 = (0..trend.len()).map(|i| i as f64).sum();
-    let sum_y: f64 = trend.iter().map(|p| p.value).sum();
-    let sum_xx: f64 = (0..trend.len()).map(|i| (i as f64).powi(2)).sum();
-    let sum_xy: f64 = (0..trend.len()).map(|i| (i as f64) * trend[i].value).sum();
-    let denom = n * sum_xx - sum_x.powi(2);
-    if denom.abs() < 1e-6 {
-        return Err("Forecast error: denominator too small".to_string());
-    }
-    let slope = (n * sum_xy - sum_x * sum_y) / denom;
-    let intercept = (sum_y - slope * sum_x) / n;
-    let mut forecast_points = Vec::new();
-    for i in 0..days_ahead {
-        let x = trend.len() as f64 + i as f64;
-        let y = slope * x + intercept;
-        let date = (chrono::Utc::now().date_naive() + ChronoDuration::days(i as i64)).to_string();
-        forecast_points.push(TrendPoint { date, value: y });
-    }
+    // Seasonal forecast of the focus score, starting tomorrow (today is the last training day)
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let today = chrono::Local::now().date_naive();
+    let f = backend::forecast::forecast_metric(&conn, user_id, "focus_score", days_ahead, today, &backend::forecast::ForecastOptions::default())?;
     Ok(Forecast {
-        metric: "focus_score".to_string(),
-        forecast_points,
-        confidence_interval: None, // Could be added with more advanced stats
+        metric: f.metric,
+        confidence_interval: f.points.first().map(|p| (p.lower, p.upper)),
+        forecast_points: f.points.into_iter().map(|p| TrendPoint { date: p.date, value: p.value, contributing_days: 0 }).collect(),
     })
 }
 
@@ -383,19 +335,22 @@ fn get_productivity_recommendations(db_path: String, user_id: i64) -> Result<Vec
         recs.push(Recommendation {
             message: "Try shorter, more frequent sessions for better focus".to_string(),
             reason: "Your average focus score is below optimal".to_string(),
-            recommended_time: Some("25m".to_string()),
+            recommended_time: next_open_slot(&db_path, user_id, 25),
+            recommended_minutes: Some(25),
         });
     } else if avg_focus > 85.0 {
         recs.push(Recommendation {
             message: "Consider longer sessions or more challenging goals".to_string(),
             reason: "Your focus score is consistently high".to_string(),
-            recommended_time: Some("50m".to_string()),
+            recommended_time: next_open_slot(&db_path, user_id, 50),
+            recommended_minutes: Some(50),
         });
     } else {
         recs.push(Recommendation {
             message: "Maintain your current routine for steady productivity".to_string(),
             reason: "Your focus score is in the optimal range".to_string(),
             recommended_time: None,
+            recommended_minutes: None,
         });
     }
     Ok(recs)
@@ -1222,113 +1177,16 @@ fn init_daily_database(workspace_dir: String) -> Result<String, String> {
         Err(e) => return Err(format!("Failed to open DB: {}", e)),
     };
 
-    // Create tables (robust schema, extensible, with comments)
-    let schema = [
-        // User profile (singleton row)
-        r#"CREATE TABLE IF NOT EXISTS user_profile (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            name TEXT,
-            onboarding_complete INTEGER DEFAULT 0,
-            personality_json TEXT,
-            created_at TEXT DEFAULT (datetime('now')),
-            updated_at TEXT DEFAULT (datetime('now'))
-        );"#,
-
-        // Card (core, session, event, distraction)
-        r#"CREATE TABLE IF NOT EXISTS card (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            rfid TEXT NOT NULL,
-            type TEXT NOT NULL CHECK (type IN ('core', 'session', 'event', 'distraction')),
-            label TEXT,
-            color TEXT,
-            metadata_json TEXT,
-            created_at TEXT DEFAULT (datetime('now')),
-            updated_at TEXT DEFAULT (datetime('now')),
-            UNIQUE(rfid, type)
-        );"#,
-
-        // Session (tracks focus sessions)
-        r#"CREATE TABLE IF NOT EXISTS session (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            card_id INTEGER NOT NULL,
-            start_time TEXT NOT NULL,
-            end_time TEXT,
-            notes TEXT,
-            ai_summary TEXT,
-            created_at TEXT DEFAULT (datetime('now')),
-            updated_at TEXT DEFAULT (datetime('now')),
-            FOREIGN KEY(card_id) REFERENCES card(id)
-        );"#,
-
-        // Event (arbitrary events, e.g. context switches)
-        r#"CREATE TABLE IF NOT EXISTS event (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            card_id INTEGER,
-            event_type TEXT NOT NULL,
-            event_time TEXT NOT NULL,
-            details_json TEXT,
-            created_at TEXT DEFAULT (datetime('now')),
-            FOREIGN KEY(card_id) REFERENCES card(id)
-        );"#,
-
-        // Distraction (tracks interruptions)
-        r#"CREATE TABLE IF NOT EXISTS distraction (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            session_id INTEGER,
-            event_id INTEGER,
-            reason TEXT,
-            resolved INTEGER DEFAULT 0,
-            created_at TEXT DEFAULT (datetime('now')),
-            FOREIGN KEY(session_id) REFERENCES session(id),
-            FOREIGN KEY(event_id) REFERENCES event(id)
-        );"#,
-
-        // Goal (user goals, can be linked to cards)
-        r#"CREATE TABLE IF NOT EXISTS goal (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            card_id INTEGER,
-            description TEXT NOT NULL,
-            target_date TEXT,
-            completed INTEGER DEFAULT 0,
-            created_at TEXT DEFAULT (datetime('now')),
-            updated_at TEXT DEFAULT (datetime('now')),
-            FOREIGN KEY(card_id) REFERENCES card(id)
-        );"#,
-
-        // Alarm (reminders, can be linked to cards)
-        r#"CREATE TABLE IF NOT EXISTS alarm (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            card_id INTEGER,
-            alarm_time TEXT NOT NULL,
-            label TEXT,
-            triggered INTEGER DEFAULT 0,
-            created_at TEXT DEFAULT (datetime('now')),
-            FOREIGN KEY(card_id) REFERENCES card(id)
-        );"#,
-
-        // Log (arbitrary logs, for debugging/auditing)
-        r#"CREATE TABLE IF NOT EXISTS log (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            log_time TEXT DEFAULT (datetime('now')),
-            level TEXT,
-            message TEXT,
-            details_json TEXT
-        );"#,
-
-        // Workspace config/state (singleton row)
-        r#"CREATE TABLE IF NOT EXISTS workspace_config (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            config_json TEXT,
-            created_at TEXT DEFAULT (datetime('now')),
-            updated_at TEXT DEFAULT (datetime('now'))
-        );"#,
-    ];
+    // Create tables (schema shared with utility::ensure_daily_db)
+    let schema = utility::DAILY_DB_SCHEMA;
 
     for stmt in schema.iter() {
         if let Err(e) = conn.execute(stmt, []) {
             return Err(format!("Schema error: {}\nSQL: {}", e, stmt));
         }
     }
+    backend::distractions::ensure_interval_columns(&conn)?;
+    backend::search::ensure_daily_index(&conn)?;
 
     Ok(db_path.to_string_lossy().to_string())
 }
@@ -1447,10 +1305,39 @@ pub fn run() {
     , ai_provider::store_master_secret_in_keyring, ai_provider::get_master_secret_from_keyring, ai_provider::cache_master_secret_temp, ai_provider::clear_master_secret_cache
     , ai_provider::set_prompt_template, ai_provider::get_prompt_template, ai_provider::list_prompt_templates, ai_provider::generate_journal_entry
     , journals::init_journals_table, journals::save_journal_entry, journals::list_journal_entries, journals::get_journal_entry
+    , journals::edit_journal_entry, journals::list_journal_revisions, journals::restore_journal_revision, journals::annotate_journal_entry
+    , journals::list_journal_drafts, journals::accept_journal_draft, journals::discard_journal_draft
     , backend::personality_db::aggregate_all_time_stats_async
     , backend::personality_db::get_profile_and_stats_async
     , backend::dashboard::get_dashboard_summary
-    , backend::orchestrator::get_calendar_range
+    , backend::orchestrator::get_calendar_range, backend::orchestrator::get_calendar_conflicts, backend::orchestrator::find_free_slots
+    , backend::privacy::get_privacy_settings, backend::privacy::set_privacy_settings, backend::privacy::preview_outbound_prompt
+    , backend::safety::scan_content, backend::safety::get_safety_config, backend::safety::save_safety_rule, backend::safety::delete_safety_rule, backend::safety::set_safety_detector_action
+    , backend::prompt_assembler::assemble_prompt_with_manifest
+    , backend::coach_chat::create_chat_thread, backend::coach_chat::list_chat_threads, backend::coach_chat::get_chat_messages, backend::coach_chat::delete_chat_thread, backend::coach_chat::send_chat_message, backend::coach_chat::ask_about_journal_entry
+    , backend::ai_tools::generate_ai_with_tools, backend::ai_tools::list_ai_tool_log
+    , backend::planner::generate_daily_plan, backend::planner::list_plan_items, backend::planner::accept_plan_item, backend::planner::dismiss_plan_item
+    , backend::local_journal::generate_local_journal_entry
+    , backend::search::search
+    , backend::search::rebuild_search_index
+    , backend::retrospective::generate_retrospective, backend::retrospective::list_retrospectives, backend::retrospective::get_retrospective
+    , backend::vault_export::export_journal_vault
+    , backend::ical::export_calendar_ics, backend::ical::import_calendar_ics
+    , backend::recurrence::create_recurrence, backend::recurrence::list_recurrences, backend::recurrence::delete_recurrence, backend::recurrence::get_recurrence_occurrences, backend::recurrence::skip_occurrence, backend::recurrence::move_occurrence, backend::recurrence::restore_occurrence
+    , backend::alarms::start_alarm_scheduler, backend::alarms::snooze_alarm, backend::alarms::dismiss_alarm, backend::alarms::list_active_alarms, backend::alarms::get_alarm_preferences, backend::alarms::set_alarm_preferences, backend::alarms::get_punctuality_log
+    , backend::goals::get_pending_goals, backend::goals::get_pending_tasks, backend::goals::get_reminders
+    , backend::goals::create_goal, backend::goals::update_goal, backend::goals::set_goal_status, backend::goals::delete_goal, backend::goals::list_goals
+    , backend::goals::create_task, backend::goals::update_task, backend::goals::set_task_status, backend::goals::delete_task, backend::goals::list_tasks
+    , backend::goals::create_reminder, backend::goals::update_reminder, backend::goals::set_reminder_status, backend::goals::delete_reminder, backend::goals::list_reminders
+    , backend::goal_hierarchy::get_goal_tree, backend::goal_hierarchy::set_goal_parent, backend::goal_hierarchy::create_milestone, backend::goal_hierarchy::set_milestone_status, backend::goal_hierarchy::delete_milestone, backend::goal_hierarchy::list_milestones, backend::goal_hierarchy::link_goal_card, backend::goal_hierarchy::unlink_goal_card
+    , backend::goal_priority::get_goal_priorities, backend::goal_priority::start_goal_escalation_watcher
+    , backend::goal_time::get_goal_time, backend::goal_time::tag_session_goals
+    , backend::habits::create_habit, backend::habits::list_habits, backend::habits::set_habit_archived, backend::habits::delete_habit, backend::habits::check_in_habit, backend::habits::delete_habit_checkin, backend::habits::get_habit_status
+    , backend::streaks::get_streaks, backend::streaks::get_streak_calendar
+    , backend::analytics::get_focus_heatmap
+    , backend::distractions::start_distraction, backend::distractions::end_distraction
+    , backend::forecast::get_metric_forecast
+    , backend::distraction_analytics::get_distraction_analytics, backend::distraction_analytics::list_distraction_categories, backend::distraction_analytics::save_distraction_category, backend::distraction_analytics::delete_distraction_category
     ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
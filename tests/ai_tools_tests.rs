@@ -0,0 +1,53 @@
+use rusqlite::Connection;
+use serde_json::json;
+use tempfile::tempdir;
+
+use focusd_lib::backend::ai_tools::{execute_tool, gemini_tools_json, openai_tools_json, parse_gemini_turn, parse_openai_turn, ToolCall};
+
+#[test]
+fn test_parse_tool_calls_from_both_providers() {
+    let openai = json!({ "choices": [ { "message": { "content": null, "tool_calls": [
+        { "id": "call_abc", "type": "function", "function": { "name": "get_sessions", "arguments": "{\"start_date\":\"2025-09-08\",\"end_date\":\"2025-09-10\"}" } }
+    ] } } ] });
+    let turn = parse_openai_turn(&openai);
+    assert!(turn.text.is_none());
+    assert_eq!(turn.tool_calls, vec![ToolCall { id: "call_abc".to_string(), name: "get_sessions".to_string(), arguments: json!({ "start_date": "2025-09-08", "end_date": "2025-09-10" }) }]);
+
+    let gemini = json!({ "candidates": [ { "content": { "role": "model", "parts": [
+        { "functionCall": { "name": "get_metric_trend", "args": { "metric": "focus_score", "days": 7 } } }
+    ] } } ] });
+    let turn = parse_gemini_turn(&gemini);
+    assert_eq!(turn.tool_calls.len(), 1);
+    assert_eq!(turn.tool_calls[0].arguments["metric"], "focus_score");
+
+    let final_answer = json!({ "candidates": [ { "content": { "parts": [ { "text": "You focused 90 minutes." } ] } } ] });
+    let turn = parse_gemini_turn(&final_answer);
+    assert_eq!(turn.text.as_deref(), Some("You focused 90 minutes."));
+    assert!(turn.tool_calls.is_empty());
+
+    assert_eq!(openai_tools_json().as_array().unwrap().len(), 4);
+    assert_eq!(gemini_tools_json()[0]["functionDeclarations"].as_array().unwrap().len(), 4);
+}
+
+#[test]
+fn test_get_sessions_tool_reads_each_day_in_range() {
+    let tmp = tempdir().expect("tempdir");
+    for (date, label) in [("2025-09-08", "Essay"), ("2025-09-10", "Reading")] {
+        let conn = Connection::open(tmp.path().join(format!("focusd_{}.sqlite3", date))).unwrap();
+        conn.execute("CREATE TABLE session (id INTEGER PRIMARY KEY, start_time TEXT, end_time TEXT, label TEXT, notes TEXT)", []).unwrap();
+        conn.execute("INSERT INTO session (start_time, end_time, label, notes) VALUES (?1, ?2, ?3, 'n')", [format!("{}T09:00:00", date), format!("{}T09:50:00", date), label.to_string()]).unwrap();
+    }
+    let dir = tmp.path().to_string_lossy().to_string();
+    let call = ToolCall { id: "1".to_string(), name: "get_sessions".to_string(), arguments: json!({ "start_date": "2025-09-08", "end_date": "2025-09-10" }) };
+    let out = execute_tool(1, Some(&dir), &call).expect("tool result");
+    let days = out["days"].as_array().unwrap();
+    assert_eq!(days.len(), 3);
+    assert_eq!(days[0]["sessions"][0]["label"], "Essay");
+    assert!(days[1]["sessions"].as_array().unwrap().is_empty());
+    assert_eq!(days[2]["sessions"][0]["label"], "Reading");
+
+    let too_long = ToolCall { id: "2".to_string(), name: "get_sessions".to_string(), arguments: json!({ "start_date": "2025-01-01", "end_date": "2025-09-10" }) };
+    assert!(execute_tool(1, Some(&dir), &too_long).is_err());
+    let unknown = ToolCall { id: "3".to_string(), name: "delete_everything".to_string(), arguments: json!({}) };
+    assert!(execute_tool(1, Some(&dir), &unknown).is_err());
+}
@@ -0,0 +1,81 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection};
+use tempfile::tempdir;
+
+use focusd_lib::backend::alarms::{dismiss, load_preferences, punctuality_since, save_preferences, snooze, tick, AlarmPreferences};
+use focusd_lib::backend::recurrence;
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn at(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+}
+
+#[test]
+fn test_alarm_fires_snoozes_and_is_dismissed_late() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_str().unwrap();
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    let db = Connection::open(ensure_daily_db(Some(ws), day).unwrap()).unwrap();
+    db.execute("INSERT INTO alarm (alarm_time, label) VALUES ('07:00', 'Wake up')", []).unwrap();
+    let conn = Connection::open(tmp.path().join("personality.db")).unwrap();
+
+    assert!(tick(&conn, Some(ws), 1, at("2025-09-10T06:59:00")).unwrap().is_empty());
+    let fired = tick(&conn, Some(ws), 1, at("2025-09-10T07:00:10")).unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].label, "Wake up");
+    let key = fired[0].alarm_key.clone();
+    let triggered: i64 = db.query_row("SELECT triggered FROM alarm", [], |r| r.get(0)).unwrap();
+    assert_eq!(triggered, 1);
+    // Firing is idempotent
+    assert!(tick(&conn, Some(ws), 1, at("2025-09-10T07:00:30")).unwrap().is_empty());
+
+    // Another user cannot snooze or dismiss it
+    assert!(snooze(&conn, 2, &key, at("2025-09-10T07:00:40")).is_err());
+    assert!(dismiss(&conn, 2, &key, at("2025-09-10T07:00:40"), Some(ws), None).is_err());
+    assert!(punctuality_since(&conn, 2, day).unwrap().is_empty());
+
+    let snoozed = snooze(&conn, 1, &key, at("2025-09-10T07:01:00")).unwrap();
+    assert_eq!(snoozed.fire_time, "2025-09-10T07:10:00");
+    assert!(snooze(&conn, 1, &key, at("2025-09-10T07:02:00")).is_err());
+    assert!(tick(&conn, Some(ws), 1, at("2025-09-10T07:05:00")).unwrap().is_empty());
+    let refired = tick(&conn, Some(ws), 1, at("2025-09-10T07:10:05")).unwrap();
+    assert_eq!(refired[0].snooze_count, 1);
+
+    let record = dismiss(&conn, 1, &key, at("2025-09-10T07:11:00"), Some(ws), None).unwrap();
+    assert_eq!(record.status, "late");
+    assert_eq!(record.response_seconds, Some(660));
+    assert!(dismiss(&conn, 1, &key, at("2025-09-10T07:12:00"), Some(ws), None).is_err());
+}
+
+#[test]
+fn test_core_tap_requirement_and_missed_alarms() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_str().unwrap();
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    let db = Connection::open(ensure_daily_db(Some(ws), day).unwrap()).unwrap();
+    db.execute("INSERT INTO card (rfid, type, label) VALUES ('CORE-1', 'core', 'Core')", []).unwrap();
+    let conn = Connection::open(tmp.path().join("personality.db")).unwrap();
+    save_preferences(&conn, 1, &AlarmPreferences { require_core_tap: true, ..AlarmPreferences::default() }).unwrap();
+    assert!(load_preferences(&conn, 1).unwrap().require_core_tap);
+
+    let rec = recurrence::create(&conn, 1, "alarm", "Standup", None, "2025-09-08T09:00:00", None, "FREQ=WEEKLY;BYDAY=MO,WE").unwrap();
+    let fired = tick(&conn, Some(ws), 1, at("2025-09-10T09:00:00")).unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].alarm_key, format!("recurrence:{}:2025-09-10T09:00:00", rec.id));
+    assert!(fired[0].require_core_tap);
+    let key = fired[0].alarm_key.clone();
+    assert!(dismiss(&conn, 1, &key, at("2025-09-10T09:02:00"), Some(ws), None).is_err());
+    assert!(dismiss(&conn, 1, &key, at("2025-09-10T09:02:00"), Some(ws), Some("OTHER")).is_err());
+    assert_eq!(dismiss(&conn, 1, &key, at("2025-09-10T09:02:00"), Some(ws), Some("CORE-1")).unwrap().status, "on_time");
+
+    // Nobody answers the 18:00 alarm within the hour
+    db.execute("INSERT INTO alarm (alarm_time, label) VALUES (?, 'Gym')", params!["2025-09-10T18:00:00"]).unwrap();
+    assert_eq!(tick(&conn, Some(ws), 1, at("2025-09-10T18:00:00")).unwrap().len(), 1);
+    assert!(tick(&conn, Some(ws), 1, at("2025-09-10T19:30:00")).unwrap().is_empty());
+
+    let log = punctuality_since(&conn, 1, NaiveDate::from_ymd_opt(2025, 9, 1).unwrap()).unwrap();
+    let statuses: Vec<(&str, &str)> = log.iter().map(|r| (r.label.as_str(), r.status.as_str())).collect();
+    assert_eq!(statuses, vec![("Standup", "on_time"), ("Gym", "missed")]);
+    let rate: f64 = conn.query_row("SELECT AVG(status = 'on_time') FROM punctuality_log WHERE user_id = ? AND DATE(actual_time) = ?", params![1, "2025-09-10"], |r| r.get(0)).unwrap();
+    assert!((rate - 0.5).abs() < 1e-9);
+}
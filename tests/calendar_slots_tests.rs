@@ -0,0 +1,49 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection};
+use tempfile::tempdir;
+
+use focusd_lib::backend::orchestrator::{collect_calendar_items, find_conflicts, find_slots, free_slots, WorkingHours};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn at(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+}
+
+#[test]
+fn test_conflicts_between_events_and_sessions() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_str().unwrap();
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    let db = Connection::open(ensure_daily_db(Some(ws), day).unwrap()).unwrap();
+    db.execute("INSERT INTO card (rfid, type, label) VALUES ('S1', 'session', 'Deep work')", []).unwrap();
+    db.execute("INSERT INTO session (card_id, start_time, end_time) VALUES (1, '2025-09-10T09:30:00', '2025-09-10T10:30:00')", []).unwrap();
+    db.execute("INSERT INTO event (event_type, event_time, details_json) VALUES ('Client call', '2025-09-10T10:00:00', ?)", params![r#"{"duration_minutes": 45}"#]).unwrap();
+    db.execute("INSERT INTO event (event_type, event_time, details_json) VALUES ('Lunch', '2025-09-10T12:00:00', ?)", params![r#"{"end": "2025-09-10T13:00:00"}"#]).unwrap();
+    db.execute("INSERT INTO scheduled_session (title, start_time, planned_minutes) VALUES ('Write report', '2025-09-10T12:30:00', 50)", []).unwrap();
+    db.execute("INSERT INTO alarm (alarm_time, label) VALUES ('2025-09-10T10:15:00', 'Stretch')", []).unwrap();
+
+    let items = collect_calendar_items(Some(ws.to_string()), None, "2025-09-10", "2025-09-10").unwrap().unwrap();
+    let call = items.iter().find(|i| i.title == "Client call").unwrap();
+    assert_eq!(call.end.as_deref(), Some("2025-09-10T10:45:00"));
+    assert!(items.iter().any(|i| i.kind == "session" && i.title == "Deep work"));
+
+    let conflicts = find_conflicts(&items);
+    let pairs: Vec<(&str, &str, i64)> = conflicts.iter().map(|c| (c.first.title.as_str(), c.second.title.as_str(), c.overlap_minutes)).collect();
+    assert_eq!(pairs, vec![("Deep work", "Client call", 30), ("Lunch", "Write report", 30)]);
+
+    let slots = find_slots(Some(ws.to_string()), None, day, day, 30, &WorkingHours::default(), None).unwrap();
+    let spans: Vec<(&str, &str)> = slots.iter().map(|s| (&s.start[11..16], &s.end[11..16])).collect();
+    assert_eq!(spans, vec![("09:00", "09:30"), ("10:45", "12:00"), ("13:20", "17:00")]);
+}
+
+#[test]
+fn test_free_slots_respect_working_days_and_not_before() {
+    let busy = vec![(at("2025-09-12T08:00:00"), at("2025-09-12T10:00:00"))];
+    let hours = WorkingHours { start: "09:00".to_string(), end: "12:00".to_string(), weekdays: Some(vec![1, 2, 3, 4, 5]) };
+    // Friday 12th through Monday 15th; the weekend is skipped
+    let slots = free_slots(&busy, NaiveDate::from_ymd_opt(2025, 9, 12).unwrap(), NaiveDate::from_ymd_opt(2025, 9, 15).unwrap(), 60, &hours, Some(at("2025-09-12T10:30:00"))).unwrap();
+    let starts: Vec<&str> = slots.iter().map(|s| s.start.as_str()).collect();
+    assert_eq!(starts, vec!["2025-09-12T10:30:00", "2025-09-15T09:00:00"]);
+    assert_eq!(slots[0].minutes, 90);
+    assert!(free_slots(&busy, NaiveDate::from_ymd_opt(2025, 9, 12).unwrap(), NaiveDate::from_ymd_opt(2025, 9, 12).unwrap(), 30, &WorkingHours { start: "17:00".to_string(), end: "09:00".to_string(), weekdays: None }, None).is_err());
+}
@@ -0,0 +1,60 @@
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::coach_chat::{add_message, build_chat_request, create_thread, delete_thread, find_thread_for_entry, list_messages, list_threads, list_user_messages, COACH_CHAT_PREAMBLE};
+
+#[test]
+fn test_threads_and_messages_persist() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("personality.db")).expect("open db");
+
+    let general = create_thread(&conn, 1, "Coaching chat", None).unwrap();
+    let about = create_thread(&conn, 1, "About journal entry #7", Some(7)).unwrap();
+    create_thread(&conn, 2, "Someone else", None).unwrap();
+
+    add_message(&conn, general.id, "user", "How was my morning?").unwrap();
+    add_message(&conn, general.id, "assistant", "Two solid sessions before 11:00.").unwrap();
+
+    let msgs = list_messages(&conn, general.id).unwrap();
+    assert_eq!(msgs.len(), 2);
+    assert_eq!(msgs[0].role, "user");
+    assert_eq!(list_threads(&conn, 1).unwrap().len(), 2);
+    assert_eq!(find_thread_for_entry(&conn, 1, 7).unwrap().map(|t| t.id), Some(about.id));
+    assert!(find_thread_for_entry(&conn, 2, 7).unwrap().is_none());
+
+    // Other users can neither read nor delete the thread
+    assert_eq!(list_user_messages(&conn, 1, general.id).unwrap().len(), 2);
+    assert!(list_user_messages(&conn, 2, general.id).unwrap().is_empty());
+    assert!(delete_thread(&conn, 2, general.id).is_err());
+    delete_thread(&conn, 1, general.id).unwrap();
+    assert!(list_messages(&conn, general.id).unwrap().is_empty());
+    assert_eq!(list_threads(&conn, 1).unwrap().len(), 1);
+}
+
+#[test]
+fn test_chat_request_windows_and_summarises_history() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("personality.db")).expect("open db");
+    let thread = create_thread(&conn, 1, "About journal entry #3", Some(3)).unwrap();
+    for i in 0..10 {
+        add_message(&conn, thread.id, "user", &format!("Question {}? More detail here.", i)).unwrap();
+        add_message(&conn, thread.id, "assistant", &format!("Answer {}. Because of the data.", i)).unwrap();
+    }
+    let history = list_messages(&conn, thread.id).unwrap();
+
+    let req = build_chat_request("### Sessions\n- [09:00-09:50] Session: \"Deep work\"", Some("My afternoon was unfocused."), &history, 6);
+    assert_eq!(req.len(), 7);
+    assert_eq!(req[0].role, "system");
+    assert!(req[0].content.starts_with(COACH_CHAT_PREAMBLE));
+    assert!(req[0].content.contains("Deep work"));
+    assert!(req[0].content.contains("My afternoon was unfocused."));
+    // Dropped turns are summarised by their first sentence, capped to the latest ten
+    assert!(req[0].content.contains("(4 older turns omitted)"));
+    assert!(!req[0].content.contains("Question 1?"));
+    assert!(req[0].content.contains("User: Question 2?"));
+    assert!(req[0].content.contains("Coach: Answer 6."));
+    assert!(!req[0].content.contains("More detail here"));
+    // Most recent turns are sent verbatim, oldest first
+    assert_eq!(req[1].content, "Question 7? More detail here.");
+    assert_eq!(req[6].content, "Answer 9. Because of the data.");
+}
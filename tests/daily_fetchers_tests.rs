@@ -165,7 +165,7 @@ fn test_missing_tables_return_fallbacks() {
     conn.execute("DROP TABLE IF EXISTS goal", []).ok();
 
     let wd = Some(dir.to_string_lossy().to_string());
-    // Should not panic; functions will return fallback demo data
+    // Should not panic; the remaining tables are still imported into the goals store
     let _ = goals::get_pending_goals(wd.clone()).expect("get goals fallback");
     let _ = goals::get_pending_tasks(wd.clone()).expect("get tasks fallback");
     let _ = goals::get_reminders(wd.clone()).expect("get reminders fallback");
@@ -0,0 +1,98 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::distraction_analytics::{self, categorize, load_categories, save_distraction_category};
+use focusd_lib::backend::distractions::{end_distraction, start_distraction};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn test_timed_distractions_and_editable_categories() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    let date = Some("2025-09-10".to_string());
+    let db = Connection::open(ensure_daily_db(Some(&ws), d("2025-09-10")).unwrap()).unwrap();
+    db.execute_batch("INSERT INTO card (id, rfid, type, label) VALUES (1, 'DEEP', 'session', 'Deep work');
+        INSERT INTO session (id, card_id, start_time) VALUES (1, 1, '2025-09-10T09:00:00');").unwrap();
+
+    // Attached to the running session; only one distraction runs at a time
+    let id = start_distraction(wd.clone(), date.clone(), None, " Phone ".to_string(), Some("09:15".to_string())).unwrap();
+    assert!(start_distraction(wd.clone(), date.clone(), None, "Slack".to_string(), Some("09:16".to_string())).is_err());
+    assert!(end_distraction(wd.clone(), date.clone(), None, Some("09:00".to_string())).is_err());
+    assert_eq!(end_distraction(wd.clone(), date.clone(), None, Some("09:27".to_string())).unwrap(), 12);
+    assert!(end_distraction(wd.clone(), date.clone(), Some(id), None).is_err());
+    let row: (i64, String, i64, String) = db.query_row("SELECT session_id, reason, resolved, end_time FROM distraction WHERE id = ?", [id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))).unwrap();
+    assert_eq!(row, (1, "Phone".to_string(), 1, "2025-09-10T09:27:00".to_string()));
+
+    // Daily DBs from before interval tracking gain the columns
+    let old = tmp.path().join("focusd_2025-09-11.sqlite3");
+    Connection::open(&old).unwrap().execute_batch("CREATE TABLE distraction (id INTEGER PRIMARY KEY AUTOINCREMENT, session_id INTEGER, event_id INTEGER, reason TEXT, resolved INTEGER DEFAULT 0, created_at TEXT);").unwrap();
+    ensure_daily_db(Some(&ws), d("2025-09-11")).unwrap();
+    assert!(Connection::open(&old).unwrap().prepare("SELECT start_time, end_time FROM distraction").is_ok());
+
+    // Built-in categories until the user edits them
+    let defaults = load_categories(Some(&ws)).unwrap();
+    assert_eq!((defaults.len(), defaults[0].id), (5, 0));
+    assert_eq!(categorize("Reddit rabbit hole", &defaults), "Social media");
+    assert_eq!(categorize("  ", &defaults), "Unspecified");
+    assert_eq!(categorize("Daydreaming", &defaults), "Other");
+    // Keywords match whole words only
+    assert_eq!(categorize("Texts from mum", &defaults), "Phone");
+    assert_eq!(categorize("context switch", &defaults), "Other");
+    assert_eq!(categorize("tried to recall a name", &defaults), "Other");
+    assert_eq!(categorize("breakfast", &defaults), "Other");
+
+    let mine = save_distraction_category(wd.clone(), None, "Daydreaming".to_string(), vec!["daydream".to_string(), " Zoning out ".to_string()], None).unwrap();
+    assert_eq!((mine.position, mine.keywords.clone()), (5, vec!["daydream".to_string(), "zoning out".to_string()]));
+    let renamed = save_distraction_category(wd.clone(), Some(mine.id), "Mind wandering".to_string(), mine.keywords, None).unwrap();
+    assert_eq!(categorize("zoning out again", &load_categories(Some(&ws)).unwrap()), renamed.name);
+    assert!(save_distraction_category(wd.clone(), None, "phone".to_string(), vec![], None).is_err());
+    assert!(save_distraction_category(wd.clone(), None, "Other".to_string(), vec![], None).is_err());
+    distraction_analytics::delete_distraction_category(wd.clone(), renamed.id).unwrap();
+    assert!(distraction_analytics::delete_distraction_category(wd, renamed.id).is_err());
+}
+
+#[test]
+fn test_pareto_correlations_recovery_and_week_over_week() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let db = Connection::open(ensure_daily_db(Some(&ws), d("2025-09-10")).unwrap()).unwrap();
+    db.execute_batch("INSERT INTO card (id, rfid, type, label) VALUES (1, 'DEEP', 'session', 'Deep work');
+        INSERT INTO session (id, card_id, start_time, end_time) VALUES (1, 1, '09:00', '10:00');
+        INSERT INTO session (id, card_id, start_time, end_time) VALUES (2, 1, '11:00', '11:20');
+        INSERT INTO session (id, card_id, start_time, end_time) VALUES (3, 1, '14:00', '15:30');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (1, 'Phone call', '09:20', '09:30');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (NULL, 'Instagram', '09:40', '09:45');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (2, 'phone', '11:10', '11:12');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (NULL, 'Colleague came by', '10:00', '10:30');").unwrap();
+    // Last week: one untimed distraction
+    let earlier = Connection::open(ensure_daily_db(Some(&ws), d("2025-09-03")).unwrap()).unwrap();
+    earlier.execute("INSERT INTO distraction (reason) VALUES ('Email')", []).unwrap();
+
+    let a = distraction_analytics::get_distraction_analytics(Some(ws), Some("2025-09-08".to_string()), Some("2025-09-14".to_string())).unwrap();
+    assert_eq!((a.total, a.timed, a.total_minutes), (4, 4, 47.0));
+    let by_count: Vec<(&str, i64, f64)> = a.by_count.iter().map(|p| (p.category.as_str(), p.count, p.cumulative_share)).collect();
+    assert_eq!(by_count, vec![("Phone", 2, 0.5), ("People", 1, 0.75), ("Social media", 1, 1.0)]);
+    assert_eq!(a.by_count[0].reasons, vec!["Phone call".to_string(), "phone".to_string()]);
+    let by_minutes: Vec<(&str, f64, f64)> = a.by_minutes.iter().map(|p| (p.category.as_str(), p.minutes, p.share)).collect();
+    assert_eq!(by_minutes, vec![("People", 30.0, 0.638), ("Phone", 12.0, 0.255), ("Social media", 5.0, 0.106)]);
+
+    // Hour 10 had a distraction but no session time
+    assert_eq!((a.by_hour[9].count, a.by_hour[9].session_minutes, a.by_hour[9].rate), (2, 60.0, Some(2.0)));
+    assert_eq!((a.by_hour[10].count, a.by_hour[10].rate), (1, None));
+    assert_eq!(a.by_hour[11].rate, Some(3.0));
+    let lengths: Vec<(i64, i64)> = a.by_session_length.iter().map(|b| (b.sessions, b.distractions)).collect();
+    assert_eq!(lengths, vec![(1, 1), (0, 0), (1, 2), (1, 0)]);
+    assert!(a.session_length_correlation.unwrap() < -0.9);
+
+    // The colleague ended outside a session; the next one started 30 minutes later
+    assert_eq!((a.avg_recovery_minutes, a.recovery_samples), (Some(30.0), 1));
+    let w = &a.week_over_week;
+    assert_eq!((w.this_week_count, w.last_week_count, w.count_change_pct), (4, 1, Some(300.0)));
+    assert_eq!((w.this_week_minutes, w.last_week_minutes, w.minutes_change_pct), (47.0, 0.0, None));
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::analytics::{get_focus_heatmap, split_at_hours};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn at(s: &str) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+}
+
+#[test]
+fn test_intervals_split_at_hour_boundaries() {
+    let parts = split_at_hours(at("2025-09-10T09:40:00"), at("2025-09-10T11:15:00"));
+    assert_eq!(parts, vec![
+        (at("2025-09-10T09:40:00"), at("2025-09-10T10:00:00")),
+        (at("2025-09-10T10:00:00"), at("2025-09-10T11:00:00")),
+        (at("2025-09-10T11:00:00"), at("2025-09-10T11:15:00")),
+    ]);
+    // Across midnight the second part lands on the next day
+    let overnight = split_at_hours(at("2025-09-10T23:30:00"), at("2025-09-11T00:20:00"));
+    assert_eq!(overnight.len(), 2);
+    assert_eq!(overnight[1].0, at("2025-09-11T00:00:00"));
+    assert!(split_at_hours(at("2025-09-10T10:00:00"), at("2025-09-10T10:00:00")).is_empty());
+}
+
+#[test]
+fn test_heatmap_cells_net_minutes_score_and_distraction_rate() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    // Wednesday: 09:40-11:15 with a 20 minute distraction from 10:50
+    let wed = Connection::open(ensure_daily_db(Some(&ws), NaiveDate::from_ymd_opt(2025, 9, 10).unwrap()).unwrap()).unwrap();
+    wed.execute_batch("INSERT INTO card (id, rfid, type, label) VALUES (1, 'DEEP', 'session', 'Deep work');
+        INSERT INTO session (id, card_id, start_time, end_time) VALUES (1, 1, '2025-09-10T09:40:00', '2025-09-10T11:15:00');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (1, 'Phone', '2025-09-10T10:50:00', '2025-09-10T11:10:00');").unwrap();
+    // Thursday records its own focus score
+    let thu = Connection::open(ensure_daily_db(Some(&ws), NaiveDate::from_ymd_opt(2025, 9, 11).unwrap()).unwrap()).unwrap();
+    thu.execute_batch("ALTER TABLE session ADD COLUMN focus_score REAL;
+        INSERT INTO card (id, rfid, type, label) VALUES (1, 'DEEP', 'session', 'Deep work');
+        INSERT INTO session (card_id, start_time, end_time, focus_score) VALUES (1, '10:00', '10:30', 70);").unwrap();
+
+    let map = get_focus_heatmap(wd.clone(), Some("2025-09-08".to_string()), Some("2025-09-14".to_string())).unwrap();
+    assert_eq!(map.days_with_data, 2);
+    assert_eq!((map.focused_minutes.len(), map.focused_minutes[0].len()), (7, 24));
+    assert_eq!((map.focused_minutes[2][9], map.focused_minutes[2][10], map.focused_minutes[2][11]), (20.0, 50.0, 5.0));
+    assert_eq!(map.session_minutes[2][11], 15.0);
+    assert_eq!(map.focus_score[2][10], Some(83.3));
+    assert_eq!(map.focus_score[3][10], Some(70.0));
+    assert_eq!(map.focus_score[2][8], None);
+    assert_eq!((map.distraction_rate[2][10], map.distraction_rate[2][9]), (Some(1.0), Some(0.0)));
+    assert_eq!((map.hour_totals[10], map.best_hour, map.best_slot), (80.0, Some(10), Some((2, 10))));
+
+    let wed_only = get_focus_heatmap(wd.clone(), Some("2025-09-10".to_string()), Some("2025-09-10".to_string())).unwrap();
+    assert_eq!((wed_only.days_with_data, wed_only.focused_minutes[3][10]), (1, 0.0));
+    assert!(get_focus_heatmap(wd, Some("2025-09-12".to_string()), Some("2025-09-10".to_string())).is_err());
+}
@@ -0,0 +1,76 @@
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection};
+
+use focusd_lib::backend::forecast::{backtest, fit, forecast_metric, normal_quantile, ForecastOptions};
+
+const PATTERN: [f64; 7] = [10.0, 6.0, 4.0, 0.0, -2.0, -8.0, -10.0];
+
+fn weekly(weeks: usize) -> Vec<f64> {
+    (0..weeks * 7).map(|t| 50.0 + PATTERN[t % 7] + 0.5 * t as f64).collect()
+}
+
+#[test]
+fn test_methods_follow_weekly_pattern_and_trend() {
+    assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-4);
+    assert!((normal_quantile(0.9) - 1.281552).abs() < 1e-4);
+
+    let y = weekly(6);
+    let expected: Vec<f64> = (42..49).map(|t| 50.0 + PATTERN[t % 7] + 0.5 * t as f64).collect();
+    for method in ["holt_winters", "seasonal_naive"] {
+        let f = fit(method, &y, 7).unwrap();
+        for (got, want) in f.values.iter().zip(&expected) {
+            assert!((got - want).abs() < 1.5, "{}: {} vs {}", method, got, want);
+        }
+        assert!(f.std_errors.windows(2).all(|w| w[1] >= w[0]));
+    }
+    // A straight line cannot see the weekly swing, so the backtest prefers the seasonal methods
+    let hw = backtest("holt_winters", &y, 7).unwrap();
+    let line = backtest("linear", &y, 7).unwrap();
+    assert_eq!((hw.folds, hw.horizon), (3, 7));
+    assert!(hw.mae < line.mae);
+    assert!(hw.mae < hw.naive_mae);
+
+    assert!(fit("holt_winters", &y[..13], 7).is_err());
+    assert!(fit("seasonal_naive", &y[..8], 3).is_ok());
+    assert_eq!(fit("linear", &[1.0, 2.0, 3.0], 2).unwrap().values, vec![4.0, 5.0]);
+    assert!(fit("arima", &y, 7).is_err());
+}
+
+#[test]
+fn test_forecast_starts_tomorrow_with_bounded_intervals() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE session (id INTEGER PRIMARY KEY, user_id INTEGER, start_time TEXT, focus_score REAL, burnout_score REAL);
+        CREATE TABLE punctuality_log (id INTEGER PRIMARY KEY, user_id INTEGER, actual_time TEXT, status TEXT);").unwrap();
+    let today = NaiveDate::from_ymd_opt(2025, 9, 28).unwrap();
+    for i in 0..28 {
+        let day = today - Duration::days(27 - i);
+        // High focus early in the week, near the ceiling; Sundays are skipped entirely
+        if i % 7 == 6 { continue; }
+        let score = (98.0 - 4.0 * (i % 7) as f64).min(100.0);
+        conn.execute("INSERT INTO session (user_id, start_time, focus_score, burnout_score) VALUES (1, ?, ?, 20)", params![format!("{}T09:00:00", day), score]).unwrap();
+        conn.execute("INSERT INTO punctuality_log (user_id, actual_time, status) VALUES (1, ?, ?)", params![format!("{}T09:00:00", day), if i % 2 == 0 { "on_time" } else { "late" }]).unwrap();
+    }
+
+    let f = forecast_metric(&conn, 1, "focus_score", 10, today, &ForecastOptions::default()).unwrap();
+    assert_eq!(f.points.len(), 10);
+    assert_eq!(f.points[0].date, "2025-09-29");
+    assert_eq!(f.history_days, 28);
+    assert!(f.backtest.is_some());
+    for p in &f.points {
+        assert!(p.lower <= p.value && p.value <= p.upper && p.upper <= 100.0 && p.lower >= 0.0);
+    }
+    // Mondays (the start of the pattern) are forecast above Saturdays
+    assert!(f.points[0].value > f.points[5].value);
+
+    let wide = forecast_metric(&conn, 1, "focus_score", 10, today, &ForecastOptions { level: 0.95, method: "seasonal_naive".to_string(), ..ForecastOptions::default() }).unwrap();
+    assert_eq!(wide.method, "seasonal_naive");
+    for metric in ["burnout", "punctuality"] {
+        let m = forecast_metric(&conn, 1, metric, 3, today, &ForecastOptions::default()).unwrap();
+        assert!(m.points.iter().all(|p| p.upper <= if metric == "burnout" { 100.0 } else { 1.0 }));
+    }
+    // Streaks come from the workspace's daily DBs, which an in-memory DB does not have
+    assert!(forecast_metric(&conn, 1, "streaks", 3, today, &ForecastOptions::default()).unwrap_err().contains("Not enough data"));
+    assert!(forecast_metric(&conn, 1, "mood", 3, today, &ForecastOptions::default()).is_err());
+    assert!(forecast_metric(&conn, 2, "focus_score", 3, today, &ForecastOptions::default()).is_err());
+    assert!(forecast_metric(&conn, 1, "focus_score", 0, today, &ForecastOptions::default()).is_err());
+}
@@ -0,0 +1,88 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::goal_hierarchy::{self, get_goal_tree};
+use focusd_lib::backend::goals::{self, ItemUpdate};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+#[test]
+fn test_progress_rolls_up_through_milestones_and_sub_goals() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    let thesis = goals::create_goal(wd.clone(), "Finish thesis".to_string(), None, None).unwrap();
+    let paper = goals::create_goal(wd.clone(), "Submit paper".to_string(), None, None).unwrap();
+    goal_hierarchy::set_goal_parent(wd.clone(), paper.id, Some(thesis.id)).unwrap();
+    // No loops
+    assert!(goal_hierarchy::set_goal_parent(wd.clone(), thesis.id, Some(paper.id)).is_err());
+    assert!(goal_hierarchy::set_goal_parent(wd.clone(), thesis.id, Some(thesis.id)).is_err());
+
+    let draft = goal_hierarchy::create_milestone(wd.clone(), thesis.id, "Draft".to_string(), Some("2025-11-01".to_string())).unwrap();
+    let review = goal_hierarchy::create_milestone(wd.clone(), thesis.id, "Review".to_string(), None).unwrap();
+    assert_eq!((draft.position, review.position), (0, 1));
+    for (title, done) in [("Chapter 1", true), ("Chapter 2", false), ("Chapter 3", false), ("Chapter 4", true)] {
+        let t = goals::create_task(wd.clone(), title.to_string(), None, None, None).unwrap();
+        let t = goals::update_task(wd.clone(), t.id, ItemUpdate { milestone_id: Some(draft.id), ..ItemUpdate::default() }).unwrap();
+        assert_eq!(t.goal_id, Some(thesis.id));
+        if done { goals::set_task_status(wd.clone(), t.id, "completed".to_string()).unwrap(); }
+    }
+    let stray = goals::create_task(wd.clone(), "Stray".to_string(), Some(paper.id), None, None).unwrap();
+    assert!(goals::update_task(wd.clone(), stray.id, ItemUpdate { milestone_id: Some(draft.id), goal_id: Some(paper.id), ..ItemUpdate::default() }).is_err());
+    goal_hierarchy::set_milestone_status(wd.clone(), review.id, "completed".to_string()).unwrap();
+
+    // draft 50, review 100, paper 0 -> 50
+    let tree = get_goal_tree(wd.clone(), None, None).unwrap();
+    assert_eq!(tree.len(), 1);
+    let root = &tree[0];
+    assert_eq!(root.goal.id, thesis.id);
+    assert_eq!(root.milestones[0].progress, 50.0);
+    assert_eq!(root.progress, 50.0);
+    assert_eq!(root.children[0].goal.title, "Submit paper");
+    assert_eq!(goals::get_pending_goals(wd.clone()).unwrap()[0].linked, vec!["Milestone - Draft", "Task - Chapter 2", "Task - Chapter 3"]);
+
+    // Open view hides finished rows but keeps them in the roll-up
+    assert_eq!(root.milestones.len(), 1);
+    assert_eq!(root.milestones[0].tasks.len(), 2);
+    let full = get_goal_tree(wd.clone(), Some(thesis.id), Some(true)).unwrap();
+    assert_eq!(full[0].milestones.len(), 2);
+
+    // Deleting the milestone keeps its tasks on the goal
+    goal_hierarchy::delete_milestone(wd.clone(), draft.id).unwrap();
+    let tree = get_goal_tree(wd, None, Some(true)).unwrap();
+    assert_eq!(tree[0].tasks.len(), 4);
+    assert_eq!(tree[0].progress, 50.0);
+}
+
+#[test]
+fn test_linked_session_cards_count_as_time_invested() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    for (date, start, end) in [("2025-09-09", "09:00", "10:30"), ("2025-09-10", "2025-09-10T14:00:00", "2025-09-10T14:45:00")] {
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        let db = Connection::open(ensure_daily_db(Some(&ws), day).unwrap()).unwrap();
+        db.execute_batch("INSERT INTO card (id, rfid, type, label) VALUES (1, 'WRITE', 'session', 'Writing'), (2, 'CODE', 'session', 'Coding');").unwrap();
+        db.execute("INSERT INTO session (card_id, start_time, end_time) VALUES (1, ?, ?)", [start, end]).unwrap();
+        db.execute("INSERT INTO session (card_id, start_time, end_time) VALUES (2, ?, ?)", [start, end]).unwrap();
+        // Still running: not counted
+        db.execute("INSERT INTO session (card_id, start_time) VALUES (1, '2025-09-10T20:00:00')", []).unwrap();
+    }
+    let parent = goals::create_goal(wd.clone(), "Publish book".to_string(), None, None).unwrap();
+    let child = goals::create_goal(wd.clone(), "Write chapter 1".to_string(), None, None).unwrap();
+    goal_hierarchy::set_goal_parent(wd.clone(), child.id, Some(parent.id)).unwrap();
+    goal_hierarchy::link_goal_card(wd.clone(), child.id, "WRITE".to_string()).unwrap();
+    goals::update_goal(wd.clone(), child.id, ItemUpdate { target_minutes: Some(540), ..ItemUpdate::default() }).unwrap();
+    assert!(goal_hierarchy::unlink_goal_card(wd.clone(), parent.id, "WRITE".to_string()).is_err());
+
+    let tree = get_goal_tree(wd.clone(), None, None).unwrap();
+    let node = &tree[0];
+    assert_eq!((node.own_minutes, node.time_invested_minutes), (0, 135));
+    assert_eq!(node.children[0].cards, vec!["WRITE"]);
+    // 135 of 540 minutes is the child's only progress signal
+    assert_eq!(node.children[0].progress, 25.0);
+    assert_eq!(node.progress, 25.0);
+
+    goals::set_goal_status(wd.clone(), child.id, "completed".to_string()).unwrap();
+    assert_eq!(get_goal_tree(wd, Some(parent.id), None).unwrap()[0].progress, 100.0);
+}
@@ -0,0 +1,90 @@
+use chrono::{Duration, Local, NaiveDate};
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::goal_hierarchy;
+use focusd_lib::backend::goal_priority::{score_goals, take_new_escalations, PriorityConfig};
+use focusd_lib::backend::goals::{self, ItemUpdate};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn day(today: NaiveDate, offset: i64) -> String {
+    (today + Duration::days(offset)).format("%Y-%m-%d").to_string()
+}
+
+#[test]
+fn test_scores_and_escalation_states() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    let today = Local::now().date_naive();
+
+    let late = goals::create_goal(wd.clone(), "File taxes".to_string(), None, Some(day(today, -2))).unwrap();
+    let heavy = goals::create_goal(wd.clone(), "Write report".to_string(), None, Some(day(today, 3))).unwrap();
+    goals::update_goal(wd.clone(), heavy.id, ItemUpdate { target_minutes: Some(600), ..ItemUpdate::default() }).unwrap();
+    let light = goals::create_goal(wd.clone(), "Book flights".to_string(), None, Some(day(today, 5))).unwrap();
+    goals::create_task(wd.clone(), "Compare prices".to_string(), Some(light.id), None, None).unwrap();
+    let someday = goals::create_goal(wd.clone(), "Learn piano".to_string(), None, None).unwrap();
+    goals::update_goal(wd.clone(), someday.id, ItemUpdate { priority: Some(5), ..ItemUpdate::default() }).unwrap();
+    assert!(goals::update_goal(wd.clone(), someday.id, ItemUpdate { priority: Some(9), ..ItemUpdate::default() }).is_err());
+
+    // An hour on the report's card yesterday
+    let db = Connection::open(ensure_daily_db(Some(&ws), today - Duration::days(1)).unwrap()).unwrap();
+    db.execute("INSERT INTO card (id, rfid, type, label) VALUES (1, 'REPORT', 'session', 'Report')", []).unwrap();
+    db.execute("INSERT INTO session (card_id, start_time, end_time) VALUES (1, '09:00', '10:00')", []).unwrap();
+    goal_hierarchy::link_goal_card(wd.clone(), heavy.id, "REPORT".to_string()).unwrap();
+
+    let store = goals::open_store(Some(&ws)).unwrap();
+    let scored = score_goals(&store, Some(&ws), today, &PriorityConfig::default()).unwrap();
+    let by_id = |id: i64| scored.iter().find(|p| p.goal_id == id).unwrap();
+    assert_eq!(by_id(late.id).escalation.as_deref(), Some("overdue"));
+    assert_eq!(by_id(late.id).days_left, Some(-2));
+    // 540 minutes left over 3 days of 120 minutes does not fit
+    assert_eq!(by_id(heavy.id).remaining_minutes, 540);
+    assert_eq!(by_id(heavy.id).days_since_session, 1);
+    assert_eq!(by_id(heavy.id).escalation.as_deref(), Some("at_risk"));
+    assert_eq!(by_id(light.id).escalation.as_deref(), Some("upcoming"));
+    assert_eq!(by_id(someday.id).escalation, None);
+    // Work that no longer fits outranks a goal that is late but has nothing left to do
+    let order: Vec<i64> = scored.iter().map(|p| p.goal_id).collect();
+    assert_eq!(order, vec![heavy.id, late.id, light.id, someday.id]);
+
+    // A short neglect window turns the untouched upcoming goal at-risk
+    let strict = PriorityConfig { neglect_days: 0, ..PriorityConfig::default() };
+    let scored = score_goals(&store, Some(&ws), today, &strict).unwrap();
+    assert!(scored.iter().find(|p| p.goal_id == light.id).unwrap().neglected);
+    assert_eq!(scored.iter().find(|p| p.goal_id == light.id).unwrap().escalation.as_deref(), Some("at_risk"));
+
+    // Pending goals follow the stored priority, then the deadline
+    let titles: Vec<String> = goals::get_pending_goals(wd).unwrap().into_iter().map(|g| g.title).collect();
+    assert_eq!(titles, vec!["Learn piano", "File taxes", "Write report", "Book flights"]);
+}
+
+#[test]
+fn test_escalations_are_notified_once_per_state() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    let today = Local::now().date_naive();
+    let now = today.and_hms_opt(9, 0, 0).unwrap();
+    let goal = goals::create_goal(wd.clone(), "Renew passport".to_string(), None, Some(day(today, 4))).unwrap();
+    let store = goals::open_store(Some(&ws)).unwrap();
+    let cfg = PriorityConfig::default();
+
+    let first = take_new_escalations(&store, &score_goals(&store, Some(&ws), today, &cfg).unwrap(), now).unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].escalation.as_deref(), Some("upcoming"));
+    assert!(take_new_escalations(&store, &score_goals(&store, Some(&ws), today, &cfg).unwrap(), now).unwrap().is_empty());
+
+    // Same goal five days later is overdue: a new notification
+    let later = today + Duration::days(5);
+    let overdue = take_new_escalations(&store, &score_goals(&store, Some(&ws), later, &cfg).unwrap(), now).unwrap();
+    assert_eq!(overdue[0].escalation.as_deref(), Some("overdue"));
+
+    // Completed goals drop out, and a reopened goal notifies again
+    goals::set_goal_status(wd.clone(), goal.id, "completed".to_string()).unwrap();
+    assert!(score_goals(&store, Some(&ws), later, &cfg).unwrap().is_empty());
+    assert!(take_new_escalations(&store, &[], now).unwrap().is_empty());
+    goals::set_goal_status(wd, goal.id, "open".to_string()).unwrap();
+    let again = take_new_escalations(&store, &score_goals(&store, Some(&ws), later, &cfg).unwrap(), now).unwrap();
+    assert_eq!(again[0].escalation.as_deref(), Some("overdue"));
+}
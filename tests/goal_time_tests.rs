@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::goal_time::{self, card_goal_ids, net_minutes, tag_session_goals};
+use focusd_lib::backend::goal_hierarchy::get_goal_tree;
+use focusd_lib::backend::goals;
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn at(s: &str) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+}
+
+#[test]
+fn test_net_minutes_cut_out_overlapping_distractions() {
+    let (start, end) = (at("2025-09-10T09:00:00"), at("2025-09-10T10:00:00"));
+    assert_eq!(net_minutes(start, end, &[]), 60);
+    // Overlapping gaps are not subtracted twice; gaps outside the session are ignored
+    let gaps = [
+        (at("2025-09-10T09:10:00"), at("2025-09-10T09:20:00")),
+        (at("2025-09-10T09:15:00"), at("2025-09-10T09:25:00")),
+        (at("2025-09-10T08:50:00"), at("2025-09-10T09:05:00")),
+        (at("2025-09-10T11:00:00"), at("2025-09-10T11:30:00")),
+    ];
+    assert_eq!(net_minutes(start, end, &gaps), 40);
+    assert_eq!(card_goal_ids(Some(r#"{"goal_ids": [3, 1], "goal_id": 3, "color": "red"}"#)), vec![1, 3]);
+    assert!(card_goal_ids(Some("not json")).is_empty());
+    assert!(card_goal_ids(None).is_empty());
+
+    // An uneven split keeps every minute: the first goal takes the remainder
+    let session = goal_time::AttributedSession {
+        day: start.date(), session_id: 1, rfid: "A".to_string(), start, end,
+        gross_minutes: 60, net_minutes: 40, goal_ids: vec![1, 2, 3], tagged: false,
+    };
+    assert_eq!([1, 2, 3, 4].map(|g| session.minutes_for(g)), [14, 13, 13, 0]);
+}
+
+#[test]
+fn test_card_goals_and_session_tags_attribute_net_time() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    let thesis = goals::create_goal(wd.clone(), "Thesis".to_string(), None, None).unwrap();
+    let course = goals::create_goal(wd.clone(), "Course".to_string(), None, None).unwrap();
+
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    let db = Connection::open(ensure_daily_db(Some(&ws), day).unwrap()).unwrap();
+    db.execute("INSERT INTO card (id, rfid, type, label, metadata_json) VALUES (1, 'STUDY', 'session', 'Study', ?)", [format!(r#"{{"goal_ids": [{}, {}]}}"#, thesis.id, course.id)]).unwrap();
+    db.execute_batch("INSERT INTO session (id, card_id, start_time, end_time) VALUES (1, 1, '2025-09-10T09:00:00', '2025-09-10T10:00:00');
+        INSERT INTO session (id, card_id, start_time, end_time) VALUES (2, 1, '2025-09-10T14:00:00', '2025-09-10T14:30:00');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (1, 'Phone', '2025-09-10T09:30:00', '2025-09-10T09:50:00');").unwrap();
+
+    // Card split: 40 net minutes of session 1 and 30 of session 2, halved between both goals
+    let time = goal_time::get_goal_time(wd.clone(), None, None).unwrap();
+    let minutes: Vec<(i64, i64)> = time.iter().map(|g| (g.goal_id, g.net_minutes)).collect();
+    assert_eq!(minutes, vec![(thesis.id, 35), (course.id, 35)]);
+
+    // Tagging the afternoon session to the thesis overrides the card for that session
+    tag_session_goals(wd.clone(), Some("2025-09-10".to_string()), 2, vec![thesis.id]).unwrap();
+    let time = goal_time::get_goal_time(wd.clone(), Some("2025-09-10".to_string()), Some("2025-09-10".to_string())).unwrap();
+    assert_eq!((time[0].goal_id, time[0].net_minutes, time[0].hours), (thesis.id, 50, 0.8));
+    assert_eq!((time[1].goal_id, time[1].net_minutes), (course.id, 20));
+    assert!(goal_time::get_goal_time(wd.clone(), Some("2025-09-11".to_string()), None).unwrap().is_empty());
+    assert!(tag_session_goals(wd.clone(), Some("2025-09-10".to_string()), 99, vec![thesis.id]).is_err());
+    assert!(tag_session_goals(wd.clone(), Some("2025-09-10".to_string()), 2, vec![999]).is_err());
+
+    // The goal tree uses the same attribution
+    let tree = get_goal_tree(wd.clone(), Some(thesis.id), None).unwrap();
+    assert_eq!((tree[0].own_minutes, tree[0].last_session_day.as_deref()), (50, Some("2025-09-10")));
+
+    // Clearing the tag falls back to the card
+    tag_session_goals(wd.clone(), Some("2025-09-10".to_string()), 2, vec![]).unwrap();
+    assert_eq!(goal_time::get_goal_time(wd, None, None).unwrap()[0].net_minutes, 35);
+}
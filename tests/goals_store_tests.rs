@@ -0,0 +1,99 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::goals::{self, ItemUpdate};
+
+#[test]
+fn test_goal_task_reminder_crud_and_status_transitions() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    // Nothing in the workspace yet: empty lists, no demo data, no store file left behind
+    assert!(goals::get_pending_goals(wd.clone()).unwrap().is_empty());
+    assert!(goals::get_reminders(wd.clone()).unwrap().is_empty());
+    assert!(!goals::store_path(Some(&ws)).exists());
+
+    let goal = goals::create_goal(wd.clone(), "Ship v1".to_string(), None, Some("2025-10-01".to_string())).unwrap();
+    assert_eq!(goal.status, "open");
+    let task = goals::create_task(wd.clone(), "Write release notes".to_string(), Some(goal.id), None, None).unwrap();
+    goals::create_reminder(wd.clone(), "Email beta testers".to_string(), Some("2025-09-30T09:00:00".to_string()), Some(goal.id), None).unwrap();
+    assert!(goals::create_task(wd.clone(), "Orphan".to_string(), Some(999), None, None).is_err());
+    assert!(goals::create_goal(wd.clone(), "Bad date".to_string(), None, Some("soon".to_string())).is_err());
+
+    let pending = goals::get_pending_goals(wd.clone()).unwrap();
+    assert_eq!(pending[0].linked, vec!["Task - Write release notes", "Reminder - Email beta testers"]);
+    assert_eq!(goals::get_pending_tasks(wd.clone()).unwrap()[0].linked, vec!["Goal - Ship v1"]);
+
+    // Links can be removed again
+    let spare = goals::create_task(wd.clone(), "Spare".to_string(), Some(goal.id), None, None).unwrap();
+    let unlinked = goals::update_task(wd.clone(), spare.id, ItemUpdate { clear_goal: true, ..ItemUpdate::default() }).unwrap();
+    assert_eq!(unlinked.goal_id, None);
+    assert!(goals::update_task(wd.clone(), spare.id, ItemUpdate { clear_goal: true, goal_id: Some(goal.id), ..ItemUpdate::default() }).is_err());
+    let note = goals::create_reminder(wd.clone(), "Spare note".to_string(), None, Some(goal.id), Some(spare.id)).unwrap();
+    let note = goals::update_reminder(wd.clone(), note.id, ItemUpdate { clear_goal: true, clear_task: true, ..ItemUpdate::default() }).unwrap();
+    assert_eq!((note.goal_id, note.task_id), (None, None));
+    goals::delete_task(wd.clone(), spare.id).unwrap();
+    goals::delete_reminder(wd.clone(), note.id).unwrap();
+
+    let started = goals::set_task_status(wd.clone(), task.id, "in_progress".to_string()).unwrap();
+    assert!(started.completed_at.is_none());
+    let done = goals::set_task_status(wd.clone(), task.id, "completed".to_string()).unwrap();
+    assert!(done.completed_at.is_some());
+    assert!(goals::set_task_status(wd.clone(), task.id, "in_progress".to_string()).is_err());
+    let reopened = goals::set_task_status(wd.clone(), task.id, "open".to_string()).unwrap();
+    assert!(reopened.completed_at.is_none());
+    assert!(goals::set_goal_status(wd.clone(), goal.id, "finished".to_string()).is_err());
+
+    let renamed = goals::update_goal(wd.clone(), goal.id, ItemUpdate { title: Some("Ship 1.0".to_string()), deadline: Some(String::new()), ..ItemUpdate::default() }).unwrap();
+    assert_eq!((renamed.title.as_str(), renamed.deadline.as_deref()), ("Ship 1.0", None));
+
+    goals::set_goal_status(wd.clone(), goal.id, "completed".to_string()).unwrap();
+    assert!(goals::get_pending_goals(wd.clone()).unwrap().is_empty());
+    assert_eq!(goals::list_goals(wd.clone(), Some("completed".to_string())).unwrap().len(), 1);
+
+    // Deleting the goal keeps its task, unlinked
+    goals::delete_goal(wd.clone(), goal.id).unwrap();
+    assert_eq!(goals::list_tasks(wd.clone(), None, None).unwrap()[0].goal_id, None);
+    assert!(goals::delete_goal(wd, goal.id).is_err());
+}
+
+#[test]
+fn test_legacy_daily_rows_are_imported_once_and_persist() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    for (date, completed) in [("2025-09-09", 0), ("2025-09-10", 1)] {
+        let conn = Connection::open(tmp.path().join(format!("focusd_{}.sqlite3", date))).unwrap();
+        conn.execute_batch("CREATE TABLE goal (id INTEGER PRIMARY KEY, description TEXT, target_date TEXT, completed INTEGER DEFAULT 0);
+                            CREATE TABLE reminder (id INTEGER PRIMARY KEY, text TEXT, remind_at TEXT, created_at TEXT);").unwrap();
+        // The same goal copied into both days' files
+        conn.execute("INSERT INTO goal (description, target_date, completed) VALUES ('Learn Rust', '2025-12-01', ?)", [completed]).unwrap();
+        conn.execute("INSERT INTO reminder (text) VALUES (?)", [format!("Reminder from {}", date)]).unwrap();
+    }
+    // A raw store connection, so 2025-09-10 is "today" and its file keeps being re-read
+    let store = Connection::open(goals::store_path(Some(&ws))).unwrap();
+    goals::ensure_store_schema(&store).unwrap();
+    let today = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    goals::import_legacy(&store, Some(&ws), today).unwrap();
+
+    let all = goals::list_goal_rows(&store, None).unwrap();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].title, "Learn Rust");
+    // The completed copy from 2025-09-10 completes the goal imported from 2025-09-09
+    assert_eq!((all[0].status.as_str(), all[0].completed_at.as_deref()), ("completed", Some("2025-09-10")));
+    let reminders = goals::list_reminder_rows(&store, None).unwrap();
+    assert_eq!(reminders.len(), 2);
+
+    // Today's file is re-read, but a reminder deleted from the store stays deleted
+    let todays = reminders.iter().find(|r| r.text == "Reminder from 2025-09-10").unwrap();
+    goals::delete_row(&store, "reminders", todays.id).unwrap();
+    goals::import_legacy(&store, Some(&ws), today).unwrap();
+    assert_eq!(goals::list_reminder_rows(&store, None).unwrap().len(), 1);
+
+    // Past daily files are not re-read, so the user's changes in the store stick
+    goals::set_goal_status(Some(ws.clone()), all[0].id, "open".to_string()).unwrap();
+    goals::set_goal_status(Some(ws.clone()), all[0].id, "cancelled".to_string()).unwrap();
+    goals::import_legacy(&store, Some(&ws), today).unwrap();
+    assert!(goals::get_pending_goal_refs(Some(ws.clone())).unwrap().is_empty());
+    assert_eq!(goals::list_goal_rows(&store, None).unwrap().len(), 1);
+}
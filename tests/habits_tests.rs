@@ -0,0 +1,79 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::habits::{self, add_checkin, habit_status, insert_habit, sync_event_checkins, HabitSchedule};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn schedule(kind: &str, weekdays: Vec<u32>, times_per_week: Option<u32>) -> HabitSchedule {
+    HabitSchedule { kind: kind.to_string(), weekdays, times_per_week }
+}
+
+#[test]
+fn test_daily_streaks_forgive_allowed_skips() {
+    let tmp = tempdir().unwrap();
+    let conn = habits::open_store(Some(tmp.path().to_str().unwrap())).unwrap();
+    let water = insert_habit(&conn, "Drink water", None, &schedule("daily", vec![], None), 2, 1, d("2025-09-01")).unwrap();
+    assert_eq!(water.tag, "drink-water");
+    assert!(insert_habit(&conn, "Drink Water!", None, &schedule("daily", vec![], None), 1, 0, d("2025-09-01")).is_err());
+    assert!(insert_habit(&conn, "Gym", None, &schedule("weekly", vec![], Some(9)), 1, 0, d("2025-09-01")).is_err());
+    assert!(insert_habit(&conn, "Read", None, &schedule("weekdays", vec![0], None), 1, 0, d("2025-09-01")).is_err());
+
+    // Sep 3 is one short (the week's skip), Sep 5 is missed outright
+    for (day, n) in [("2025-09-01", 2), ("2025-09-02", 2), ("2025-09-03", 1), ("2025-09-04", 2), ("2025-09-06", 2), ("2025-09-07", 2), ("2025-09-08", 2), ("2025-09-09", 2), ("2025-09-10", 1)] {
+        add_checkin(&conn, water.id, d(day), n).unwrap();
+    }
+    let early = habit_status(&conn, &water, d("2025-09-05")).unwrap();
+    assert_eq!((early.current_streak, early.skips_left_this_week), (3, 0));
+    assert!(!early.done_today);
+
+    let status = habit_status(&conn, &water, d("2025-09-10")).unwrap();
+    assert_eq!((status.current_streak, status.longest_streak, status.streak_unit.as_str()), (4, 4, "days"));
+    assert_eq!((status.today_count, status.done_today, status.skips_left_this_week), (1, false, 1));
+    // 7 of 8 settled days done; the skip and today are left out
+    assert!((status.completion_rate - 0.875).abs() < 1e-9);
+
+    let reading = insert_habit(&conn, "Reading", None, &schedule("weekdays", vec![1, 3, 5], None), 1, 0, d("2025-09-01")).unwrap();
+    add_checkin(&conn, reading.id, d("2025-09-01"), 1).unwrap();
+    let tuesday = habit_status(&conn, &reading, d("2025-09-02")).unwrap();
+    assert!(!tuesday.due_today);
+    assert_eq!(tuesday.current_streak, 1);
+}
+
+#[test]
+fn test_weekly_habit_from_tagged_event_taps() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_str().unwrap();
+    let conn = habits::open_store(Some(ws)).unwrap();
+    let gym = insert_habit(&conn, "Gym", None, &schedule("weekly", vec![], Some(3)), 1, 0, d("2025-09-01")).unwrap();
+    for (day, event_type, details) in [
+        ("2025-09-01", "habit:Gym", "{}"),
+        ("2025-09-03", "workout", r#"{"habit": "gym"}"#),
+        ("2025-09-05", "habit:gym", "{}"),
+        ("2025-09-09", "habit:gym", "{}"),
+        ("2025-09-15", "habit:gym", "{}"),
+        ("2025-09-15", "meeting", r#"{"note": "not a habit"}"#),
+    ] {
+        let db = Connection::open(ensure_daily_db(Some(ws), d(day)).unwrap()).unwrap();
+        db.execute("INSERT INTO event (event_type, event_time, details_json) VALUES (?, ?, ?)", [event_type, &format!("{}T07:00:00", day), details]).unwrap();
+    }
+    assert_eq!(sync_event_checkins(&conn, Some(ws)).unwrap(), 5);
+    assert_eq!(sync_event_checkins(&conn, Some(ws)).unwrap(), 0);
+
+    // Week 1 done, week 2 missed, week 3 in progress
+    let status = habits::get_habit_status(Some(ws.to_string()), Some("2025-09-17".to_string())).unwrap();
+    assert_eq!(status.len(), 1);
+    let gym_status = &status[0];
+    assert_eq!((gym_status.current_streak, gym_status.longest_streak, gym_status.week_count), (0, 1, 1));
+    assert_eq!(gym_status.streak_unit, "weeks");
+
+    // Removing an event check-in sticks across syncs
+    let id: i64 = conn.query_row("SELECT id FROM habit_checkins WHERE day = '2025-09-15'", [], |r| r.get(0)).unwrap();
+    habits::delete_habit_checkin(Some(ws.to_string()), id).unwrap();
+    sync_event_checkins(&conn, Some(ws)).unwrap();
+    assert_eq!(habit_status(&conn, &gym, d("2025-09-17")).unwrap().week_count, 0);
+}
@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::ical::{export_ics, fold_line, import_ics, parse_duration, parse_ics};
+use focusd_lib::backend::journals::save_generated;
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+const FEED: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Example//EN\r\nBEGIN:VEVENT\r\nUID:standup-1@example.com\r\nDTSTAMP:20250901T000000Z\r\nDTSTART:20250910T093000\r\nDTEND:20250910T094500\r\nSUMMARY:Team standup\r\nDESCRIPTION:Daily sync\\, bring blockers\r\nLOCATION:Room 4\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT10M\r\nDESCRIPTION:Standup soon\r\nEND:VALARM\r\nEND:VEVENT\r\nBEGIN:VTODO\r\nUID:todo-9@example.com\r\nDTSTAMP:20250901T000000Z\r\nSUMMARY:Renew library boo\r\n k\r\nDUE;VALUE=DATE:20250911\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+#[test]
+fn test_parse_unfolds_and_nests_components() {
+    let comps = parse_ics(FEED).unwrap();
+    assert_eq!(comps.len(), 2);
+    assert_eq!(comps[0].text("DESCRIPTION").as_deref(), Some("Daily sync, bring blockers"));
+    assert_eq!(comps[0].children[0].name, "VALARM");
+    assert_eq!(comps[1].text("SUMMARY").as_deref(), Some("Renew library book"));
+    assert_eq!(parse_duration("-PT10M"), Some(chrono::Duration::minutes(-10)));
+    assert_eq!(parse_duration("P1W2DT3H"), Some(chrono::Duration::hours(7 * 24 + 2 * 24 + 3)));
+    assert!(parse_duration("P1H").is_none());
+    assert!(parse_ics("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VCALENDAR\r\n").is_err());
+    let folded = fold_line(&"x".repeat(100));
+    assert!(folded.lines().all(|l| l.len() <= 75));
+}
+
+#[test]
+fn test_import_maps_rows_into_daily_dbs_and_dedupes_by_uid() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().to_string_lossy().to_string();
+    let p = Connection::open(tmp.path().join("p.db")).unwrap();
+
+    let report = import_ics(&p, Some(&dir), FEED, d("2025-09-01")).unwrap();
+    assert_eq!((report.events_inserted, report.alarms_inserted, report.reminders_inserted), (1, 1, 1));
+
+    let day10 = Connection::open(tmp.path().join("focusd_2025-09-10.sqlite3")).unwrap();
+    let (title, time): (String, String) = day10.query_row("SELECT event_type, event_time FROM event", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+    assert_eq!((title.as_str(), time.as_str()), ("Team standup", "2025-09-10T09:30:00"));
+    let alarm: (String, String) = day10.query_row("SELECT alarm_time, label FROM alarm", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+    assert_eq!(alarm, ("2025-09-10T09:20:00".to_string(), "Standup soon".to_string()));
+    let day11 = Connection::open(tmp.path().join("focusd_2025-09-11.sqlite3")).unwrap();
+    let remind_at: String = day11.query_row("SELECT remind_at FROM reminder", [], |r| r.get(0)).unwrap();
+    assert_eq!(remind_at, "2025-09-11");
+
+    // Re-import with the event moved to the next day: updated and moved, not duplicated.
+    let moved = FEED.replace("DTSTART:20250910T093000", "DTSTART:20250911T100000").replace("DTEND:20250910T094500", "DTEND:20250911T101500");
+    let report = import_ics(&p, Some(&dir), &moved, d("2025-09-01")).unwrap();
+    assert_eq!((report.events_inserted, report.reminders_updated), (1, 1));
+    let left: i64 = day10.query_row("SELECT COUNT(*) FROM event", [], |r| r.get(0)).unwrap();
+    let now: i64 = day11.query_row("SELECT COUNT(*) FROM event", [], |r| r.get(0)).unwrap();
+    assert_eq!((left, now), (0, 1));
+    let reminders: i64 = day11.query_row("SELECT COUNT(*) FROM reminder", [], |r| r.get(0)).unwrap();
+    assert_eq!(reminders, 1);
+}
+
+#[test]
+fn test_export_covers_kinds_and_round_trips_without_duplicates() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().to_string_lossy().to_string();
+    let p = Connection::open(tmp.path().join("p.db")).unwrap();
+    let conn = Connection::open(ensure_daily_db(Some(&dir), d("2025-09-10")).unwrap()).unwrap();
+    conn.execute_batch("INSERT INTO card (id, rfid, type, label) VALUES (1, 'A', 'session', 'Deep work');
+        INSERT INTO session (card_id, start_time, end_time, notes) VALUES (1, '2025-09-10T09:00:00', '2025-09-10T10:00:00', 'Chapter 2');
+        INSERT INTO event (event_type, event_time, details_json) VALUES ('meeting', '2025-09-10T14:00:00', '{\"room\":\"4B\"}');
+        INSERT INTO alarm (alarm_time, label) VALUES ('2025-09-10T07:30:00', 'Wake up');
+        INSERT INTO reminder (text, remind_at) VALUES ('Pay bills', NULL);
+        INSERT INTO goal (description, target_date) VALUES ('Submit thesis', '2025-09-12');").unwrap();
+    save_generated(&p, 1, d("2025-09-10"), "local", None, "A calm, productive day.", None).unwrap();
+
+    let ics = export_ics(&p, 1, Some(&dir), d("2025-09-10"), d("2025-09-12")).unwrap();
+    for needle in ["SUMMARY:Deep work", "X-FOCUSD-KIND:session", "SUMMARY:meeting", "TRIGGER:PT0S", "SUMMARY:Pay bills", "DUE;VALUE=DATE:20250912", "BEGIN:VJOURNAL", "DESCRIPTION:A calm\\, productive day."] {
+        assert!(ics.contains(needle), "missing {}", needle);
+    }
+
+    let report = import_ics(&p, Some(&dir), &ics, d("2025-09-10")).unwrap();
+    assert_eq!((report.events_inserted, report.alarms_inserted, report.reminders_inserted), (0, 0, 0));
+    assert_eq!((report.events_updated, report.alarms_updated, report.reminders_updated), (1, 1, 1));
+    assert_eq!(report.skipped.len(), 3);
+    let events: i64 = conn.query_row("SELECT COUNT(*) FROM event", [], |r| r.get(0)).unwrap();
+    assert_eq!(events, 1);
+    // The round trip keeps the event's own fields
+    let (kind, details): (String, String) = conn.query_row("SELECT event_type, details_json FROM event", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+    assert_eq!((kind.as_str(), details.as_str()), ("meeting", r#"{"room":"4B"}"#));
+
+    // The same export imported into another workspace is foreign there: nothing local is touched
+    let other = tmp.path().join("other");
+    let other_dir = other.to_string_lossy().to_string();
+    let theirs = Connection::open(ensure_daily_db(Some(&other_dir), d("2025-09-10")).unwrap()).unwrap();
+    theirs.execute("INSERT INTO event (event_type, event_time, details_json) VALUES ('dentist', '2025-09-10T16:00:00', '{}')", []).unwrap();
+    let report = import_ics(&p, Some(&other_dir), &ics, d("2025-09-10")).unwrap();
+    assert_eq!((report.events_inserted, report.alarms_inserted, report.reminders_inserted), (1, 1, 1));
+    assert_eq!((report.events_updated, report.alarms_updated, report.reminders_updated), (0, 0, 0));
+    let kinds: Vec<String> = theirs.prepare("SELECT event_type FROM event ORDER BY id").unwrap().query_map([], |r| r.get(0)).unwrap().map(|r| r.unwrap()).collect();
+    assert_eq!(kinds, vec!["dentist", "meeting"]);
+}
+
+#[test]
+fn test_foreign_feed_imported_into_two_workspaces_keeps_rows_apart() {
+    let tmp = tempdir().expect("tempdir");
+    let p = Connection::open(tmp.path().join("p.db")).unwrap();
+    let a = tmp.path().join("a").to_string_lossy().to_string();
+    let b = tmp.path().join("b").to_string_lossy().to_string();
+    import_ics(&p, Some(&a), FEED, d("2025-09-01")).unwrap();
+
+    // Workspace B already has unrelated rows with the ids the feed got in A.
+    let theirs = Connection::open(ensure_daily_db(Some(&b), d("2025-09-10")).unwrap()).unwrap();
+    theirs.execute_batch("INSERT INTO event (event_type, event_time, details_json) VALUES ('dentist', '2025-09-10T16:00:00', '{}');
+        INSERT INTO alarm (alarm_time, label) VALUES ('2025-09-10T07:00:00', 'Wake up');").unwrap();
+    let report = import_ics(&p, Some(&b), FEED, d("2025-09-01")).unwrap();
+    assert_eq!((report.events_inserted, report.alarms_inserted, report.reminders_inserted), (1, 1, 1));
+    assert_eq!((report.events_updated, report.alarms_updated, report.reminders_updated), (0, 0, 0));
+    let events: Vec<String> = theirs.prepare("SELECT event_type FROM event ORDER BY id").unwrap().query_map([], |r| r.get(0)).unwrap().map(|r| r.unwrap()).collect();
+    assert_eq!(events, vec!["dentist", "Team standup"]);
+    let alarms: Vec<String> = theirs.prepare("SELECT label FROM alarm ORDER BY id").unwrap().query_map([], |r| r.get(0)).unwrap().map(|r| r.unwrap()).collect();
+    assert_eq!(alarms, vec!["Wake up", "Standup soon"]);
+
+    // Re-importing into A still updates A's rows.
+    let report = import_ics(&p, Some(&a), FEED, d("2025-09-01")).unwrap();
+    assert_eq!((report.events_updated, report.alarms_updated, report.reminders_updated), (1, 1, 1));
+}
@@ -0,0 +1,103 @@
+use chrono::{Local, NaiveDate, TimeZone, Utc};
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::journals::{
+    accept_draft, check_owner, day_count_on, discard_draft, edit_entry, line_diff, format_diff, list_drafts, list_revisions, read_entry,
+    restore_revision, save_generated, set_annotations, JournalAnnotations, SavedJournal,
+};
+
+#[test]
+fn test_line_diff_marks_changed_lines() {
+    let diff = format_diff(&line_diff("a\nb\nc", "a\nB\nc\nd"));
+    assert_eq!(diff, " a\n-b\n+B\n c\n+d");
+}
+
+#[test]
+fn test_regenerate_and_edit_create_revisions_of_one_entry() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("p.db")).unwrap();
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+
+    let (id, draft) = save_generated(&conn, 1, day, "chatgpt", Some("gpt-4o"), "## Day 1\nFocused 2h.", None).unwrap();
+    assert_eq!(draft, None);
+    // Regeneration is kept as a draft until the user accepts it
+    let (again, draft) = save_generated(&conn, 1, day, "local", None, "## Day 1\nFocused 3h.", None).unwrap();
+    assert_eq!(again, id);
+    assert_eq!(read_entry(&conn, id).unwrap().unwrap().content, "## Day 1\nFocused 2h.");
+    assert_eq!(list_drafts(&conn, id).unwrap()[0].diff, " ## Day 1\n-Focused 2h.\n+Focused 3h.");
+    // ...and reported as such, so a regenerate is not mistaken for a save
+    let (message, code) = SavedJournal { entry_id: id, draft_id: draft }.status();
+    assert_eq!((message, code.as_deref()), (format!("draft: {} (entry {} unchanged)", draft.unwrap(), id), Some("draft")));
+    assert_eq!(accept_draft(&conn, draft.unwrap()).unwrap(), 2);
+    assert!(list_drafts(&conn, id).unwrap().is_empty());
+    assert_eq!(read_entry(&conn, id).unwrap().unwrap().provider, "local");
+    assert_eq!(edit_entry(&conn, id, "## Day 1\nFocused 3h at the library.").unwrap(), 3);
+    assert_eq!(edit_entry(&conn, id, "## Day 1\nFocused 3h at the library.").unwrap(), 3);
+
+    let revs = list_revisions(&conn, id).unwrap();
+    let sources: Vec<&str> = revs.iter().map(|r| r.source.as_str()).collect();
+    assert_eq!(sources, vec!["generated", "regenerated", "edit"]);
+    assert_eq!(revs[2].diff, " ## Day 1\n-Focused 3h.\n+Focused 3h at the library.");
+
+    assert_eq!(restore_revision(&conn, id, 1).unwrap(), 4);
+    let entry = read_entry(&conn, id).unwrap().unwrap();
+    assert_eq!(entry.content, "## Day 1\nFocused 2h.");
+    assert_eq!(entry.entry_date, "2025-09-10");
+    assert_eq!(entry.current_revision, 4);
+
+    // A later regeneration leaves the restored text alone until accepted
+    let (_, draft) = save_generated(&conn, 1, day, "local", None, "## Day 1\nRegenerated.", None).unwrap();
+    discard_draft(&conn, draft.unwrap()).unwrap();
+    assert!(accept_draft(&conn, draft.unwrap()).is_err());
+    assert_eq!(read_entry(&conn, id).unwrap().unwrap().content, "## Day 1\nFocused 2h.");
+    check_owner(&conn, 1, id).unwrap();
+    assert!(check_owner(&conn, 2, id).is_err());
+
+    // A new day is a new entry, and the day number counts days rather than rows.
+    let next = day.succ_opt().unwrap();
+    assert_eq!(day_count_on(&conn, 1, day).unwrap(), 1);
+    assert_eq!(day_count_on(&conn, 1, next).unwrap(), 2);
+    let (other, _) = save_generated(&conn, 1, next, "local", None, "## Day 2", None).unwrap();
+    assert_ne!(other, id);
+}
+
+#[test]
+fn test_annotations_validate_and_survive_regeneration() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("p.db")).unwrap();
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    let (id, _) = save_generated(&conn, 1, day, "local", None, "Entry", None).unwrap();
+
+    assert!(set_annotations(&conn, id, &JournalAnnotations { mood: Some(6), ..Default::default() }).is_err());
+    let entry = set_annotations(&conn, id, &JournalAnnotations {
+        reflection: Some("  Felt rushed after lunch. ".into()),
+        mood: Some(3),
+        energy: Some(4),
+        tags: vec!["#Thesis".into(), "thesis".into(), " gym ".into()],
+    }).unwrap();
+    assert_eq!(entry.tags, vec!["thesis", "gym"]);
+    assert_eq!(entry.reflection.as_deref(), Some("Felt rushed after lunch."));
+
+    let (_, draft) = save_generated(&conn, 1, day, "local", None, "Entry v2", None).unwrap();
+    accept_draft(&conn, draft.unwrap()).unwrap();
+    let entry = read_entry(&conn, id).unwrap().unwrap();
+    assert_eq!(entry.content, "Entry v2");
+    assert_eq!((entry.mood, entry.energy), (Some(3), Some(4)));
+    assert_eq!(entry.reflection.as_deref(), Some("Felt rushed after lunch."));
+}
+
+#[test]
+fn test_legacy_table_is_migrated() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("p.db")).unwrap();
+    conn.execute("CREATE TABLE journal_entries (id INTEGER PRIMARY KEY AUTOINCREMENT, user_id INTEGER NOT NULL, created_at TEXT NOT NULL, provider TEXT NOT NULL, model TEXT, content TEXT NOT NULL, tokens INTEGER DEFAULT NULL)", []).unwrap();
+    conn.execute("INSERT INTO journal_entries (user_id, created_at, provider, content) VALUES (1, '2025-09-08 20:00:00', 'gemini', 'Old'), (1, '2025-09-08 22:00:00', 'gemini', 'Old again')", []).unwrap();
+    assert_eq!(day_count_on(&conn, 1, NaiveDate::from_ymd_opt(2025, 9, 10).unwrap()).unwrap(), 2);
+    let revs = list_revisions(&conn, 1).unwrap();
+    assert_eq!(revs.len(), 1);
+    assert_eq!(revs[0].content, "Old");
+    // created_at is UTC; the backfilled day is the local one, like new entries
+    let local_day = Utc.with_ymd_and_hms(2025, 9, 8, 22, 0, 0).unwrap().with_timezone(&Local).format("%Y-%m-%d").to_string();
+    assert_eq!(read_entry(&conn, 2).unwrap().unwrap().entry_date, local_day);
+}
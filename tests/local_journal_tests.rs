@@ -0,0 +1,43 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::local_journal::build_local_journal;
+
+fn seed_day(dir: &std::path::Path, date: &str, sessions: &[(&str, &str, &str)], reasons: &[&str]) {
+    let conn = Connection::open(dir.join(format!("focusd_{}.sqlite3", date))).unwrap();
+    conn.execute("CREATE TABLE session (id INTEGER PRIMARY KEY, card_id INTEGER, start_time TEXT, end_time TEXT, notes TEXT)", []).unwrap();
+    conn.execute("CREATE TABLE distraction (id INTEGER PRIMARY KEY, session_id INTEGER, reason TEXT, created_at TEXT)", []).unwrap();
+    for (start, end, notes) in sessions {
+        conn.execute("INSERT INTO session (card_id, start_time, end_time, notes) VALUES (1, ?1, ?2, ?3)", [format!("{}T{}:00", date, start), format!("{}T{}:00", date, end), notes.to_string()]).unwrap();
+    }
+    for r in reasons {
+        conn.execute("INSERT INTO distraction (reason) VALUES (?1)", [r]).unwrap();
+    }
+}
+
+#[test]
+fn test_local_journal_summarises_day_and_compares_to_yesterday() {
+    let tmp = tempdir().expect("tempdir");
+    seed_day(tmp.path(), "2025-09-09", &[("09:00", "09:30", "Email")], &["Phone"]);
+    seed_day(tmp.path(), "2025-09-10", &[("09:00", "09:50", "Thesis chapter 2"), ("14:00", "15:30", "Data cleanup")], &["Phone", "phone", "Slack"]);
+    let conn = Connection::open(tmp.path().join("focusd_2025-09-10.sqlite3")).unwrap();
+    conn.execute("CREATE TABLE goal (id INTEGER PRIMARY KEY, card_id INTEGER, description TEXT, target_date TEXT, completed INTEGER DEFAULT 0)", []).unwrap();
+    conn.execute("INSERT INTO goal (description, target_date) VALUES ('Submit thesis draft', '2025-09-11'), ('Learn Rust', '2025-12-01')", []).unwrap();
+
+    let dir = tmp.path().to_string_lossy().to_string();
+    let day = NaiveDate::from_ymd_opt(2025, 9, 10).unwrap();
+    let journal = build_local_journal(4, Some(&dir), day).expect("journal");
+
+    assert!(journal.starts_with("## Day 4\n"));
+    assert!(journal.contains("2h 20m across 2 sessions"));
+    assert!(journal.contains("\"Data cleanup\" at 14:00 (1h 30m)"));
+    assert!(journal.contains("Phone (2×)"));
+    assert!(journal.contains("1h 50m more focus time than yesterday (30m)"));
+    assert!(journal.contains("Distractions went up from 1 to 3"));
+    assert!(journal.contains("\"Submit thesis draft\" is due tomorrow"));
+    assert!(!journal.contains("Learn Rust"));
+
+    // Same inputs, same entry
+    assert_eq!(journal, build_local_journal(4, Some(&dir), day).unwrap());
+}
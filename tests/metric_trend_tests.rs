@@ -0,0 +1,90 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::analytics::{bucket_label, metric_trend, parse_reducer, percentile, reduce};
+use focusd_lib::backend::forecast::{forecast_metric, ForecastOptions};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn seeded() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE session (id INTEGER PRIMARY KEY, user_id INTEGER, start_time TEXT, focus_score REAL, burnout_score REAL);").unwrap();
+    // Two sessions on Mon Sep 1, one each on Wed Sep 3, Sun Sep 7, Mon Sep 8; nothing in August
+    for (start, score) in [("2025-09-01T09:00:00", 60.0), ("2025-09-01T14:00:00", 80.0), ("2025-09-03T09:00:00", 50.0), ("2025-09-07T09:00:00", 90.0), ("2025-09-08T09:00:00", 40.0)] {
+        conn.execute("INSERT INTO session (user_id, start_time, focus_score) VALUES (1, ?, ?)", rusqlite::params![start, score]).unwrap();
+    }
+    conn
+}
+
+#[test]
+fn test_reducers_and_labels() {
+    assert_eq!(reduce(&[], "avg"), None);
+    assert_eq!(reduce(&[1.0, 2.0, 6.0], "avg"), Some(3.0));
+    assert_eq!(reduce(&[1.0, 2.0, 6.0], "sum"), Some(9.0));
+    assert_eq!(reduce(&[1.0, 2.0, 6.0], "max"), Some(6.0));
+    assert_eq!(reduce(&[6.0, 1.0, 2.0], "median"), Some(2.0));
+    assert_eq!(percentile(&[10.0, 20.0, 30.0, 40.0, 50.0], 90.0), Some(46.0));
+    assert_eq!(reduce(&[10.0, 20.0, 30.0, 40.0, 50.0], "p100"), Some(50.0));
+    assert_eq!(parse_reducer(" P90 ").unwrap(), "p90");
+    assert!(parse_reducer("p0").is_err());
+    assert!(parse_reducer("mode").is_err());
+    // ISO weeks can belong to the neighbouring year
+    assert_eq!(bucket_label(d("2024-12-30"), "week"), "2025-W01");
+    assert_eq!(bucket_label(d("2025-09-01"), "month"), "2025-09");
+}
+
+#[test]
+fn test_calendar_buckets_aggregate_every_day() {
+    let conn = seeded();
+    let today = d("2025-09-10");
+
+    // ISO weeks: Sep 1-7 averages three days (70, 50, 90), the current week only Sep 8
+    let weeks = metric_trend(&conn, 1, "focus_score", 3, "week", "avg", today).unwrap();
+    let summary: Vec<(&str, f64, i64)> = weeks.iter().map(|p| (p.date.as_str(), p.value, p.contributing_days)).collect();
+    assert_eq!(summary, vec![("2025-W35", 0.0, 0), ("2025-W36", 70.0, 3), ("2025-W37", 40.0, 1)]);
+    assert_eq!(metric_trend(&conn, 1, "focus_score", 1, "week", "max", d("2025-09-07")).unwrap()[0].value, 90.0);
+
+    // An in-memory DB has no workspace, so no streak history
+    assert!(metric_trend(&conn, 1, "streaks", 2, "month", "sum", today).unwrap().iter().all(|p| p.contributing_days == 0));
+
+    let days = metric_trend(&conn, 1, "focus_score", 3, "day", "avg", today).unwrap();
+    assert_eq!(days.iter().map(|p| p.date.as_str()).collect::<Vec<_>>(), vec!["2025-09-08", "2025-09-09", "2025-09-10"]);
+    assert_eq!(days[1].contributing_days, 0);
+    assert!(metric_trend(&conn, 1, "focus_score", 3, "year", "avg", today).is_err());
+    assert!(metric_trend(&conn, 1, "mood", 3, "day", "avg", today).is_err());
+}
+
+#[test]
+fn test_streak_trend_is_the_current_streak_per_day() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    for (day, session) in [("2025-08-30", false), ("2025-09-01", true), ("2025-09-02", true), ("2025-09-03", false), ("2025-09-05", true)] {
+        let db = Connection::open(ensure_daily_db(Some(&ws), d(day)).unwrap()).unwrap();
+        db.execute("INSERT INTO card (id, rfid, type, label) VALUES (1, 'DEEP', 'session', 'Deep work')", []).unwrap();
+        if session { db.execute("INSERT INTO session (card_id, start_time, end_time) VALUES (1, '09:00', '09:30')", []).unwrap(); }
+    }
+    let conn = Connection::open(tmp.path().join("main.db")).unwrap();
+    conn.execute_batch("CREATE TABLE user_setting (id INTEGER PRIMARY KEY AUTOINCREMENT, user_id INTEGER NOT NULL, key TEXT NOT NULL, value TEXT NOT NULL, version INTEGER NOT NULL, updated_at TEXT NOT NULL, UNIQUE(user_id, key));").unwrap();
+
+    // Seen from Sep 5, Sep 3 broke the streak; Sep 4 has no DB at all
+    let days = metric_trend(&conn, 1, "streaks", 5, "day", "avg", d("2025-09-05")).unwrap();
+    assert_eq!(days.iter().map(|p| p.value).collect::<Vec<_>>(), vec![1.0, 2.0, 0.0, 0.0, 1.0]);
+    // On Sep 3 itself the day is still in progress
+    let days = metric_trend(&conn, 1, "streaks", 3, "day", "avg", d("2025-09-03")).unwrap();
+    assert_eq!(days.iter().map(|p| p.value).collect::<Vec<_>>(), vec![1.0, 2.0, 2.0]);
+    // Zeros start at the first session day: August only has an empty daily DB
+    let months = metric_trend(&conn, 1, "streaks", 2, "month", "sum", d("2025-09-05")).unwrap();
+    let summary: Vec<(&str, f64, i64)> = months.iter().map(|p| (p.date.as_str(), p.value, p.contributing_days)).collect();
+    assert_eq!(summary, vec![("2025-08", 0.0, 0), ("2025-09", 4.0, 5)]);
+    let err = forecast_metric(&conn, 1, "streaks", 3, d("2025-08-31"), &ForecastOptions::default()).unwrap_err();
+    assert!(err.contains("Not enough data"), "{}", err);
+
+    // A 45-minute min_session_time means no day qualifies
+    conn.execute("INSERT INTO user_setting (user_id, key, value, version, updated_at) VALUES (1, 'min_session_time', '45', 1, '2025-09-01T08:00:00')", []).unwrap();
+    let days = metric_trend(&conn, 1, "streaks", 5, "day", "max", d("2025-09-05")).unwrap();
+    assert!(days.iter().all(|p| p.value == 0.0));
+}
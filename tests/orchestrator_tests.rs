@@ -56,7 +56,7 @@ fn test_orchestrator_multi_day_aggregation() {
     let found_tomorrow = focusd_lib::backend::utility::find_daily_db(Some(".".to_string()), Some(tomorrow));
     println!("found_today={:?}", found_today.map(|p| p.to_string_lossy().to_string()));
     println!("found_tomorrow={:?}", found_tomorrow.map(|p| p.to_string_lossy().to_string()));
-    let res = focusd_lib::backend::orchestrator::get_calendar_range(Some(".".to_string()), start.clone(), end.clone());
+    let res = focusd_lib::backend::orchestrator::get_calendar_range(Some(".".to_string()), start.clone(), end.clone(), None);
     // restore cwd
     std::env::set_current_dir(cur).unwrap();
 
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::planner::{accept_item, dismiss_item, list_items, parse_daily_plan, save_plan};
+
+#[test]
+fn test_parse_daily_plan_validates_and_repairs() {
+    let goals: HashSet<i64> = [3, 7].into_iter().collect();
+    let reply = "Here is your plan:\n```json\n{\n  \"blocks\": [\n    {\"start\": \"13:00\", \"end\": \"13:15\", \"kind\": \"reminder\", \"title\": \"Call the bank\"},\n    {\"start\": \"9:00\", \"end\": \"10:30\", \"kind\": \"session\", \"title\": \"Thesis draft\", \"goal_id\": 3, \"session_minutes\": 50},\n    {\"start\": \"10:30\", \"end\": \"10:45\", \"kind\": \"break\", \"title\": \"Walk\", \"goal_id\": 99},\n  ],\n  \"priority_goal_ids\": [3, 42, 7],\n  \"session_length_minutes\": 400\n}\n```";
+    let parsed = parse_daily_plan(reply, &goals).expect("repairable plan");
+    let plan = parsed.plan;
+    assert_eq!(plan.blocks.len(), 3);
+    assert_eq!(plan.blocks[0].start, "09:00");
+    assert_eq!(plan.blocks[0].goal_id, Some(3));
+    assert_eq!(plan.blocks[1].goal_id, None);
+    assert_eq!(plan.priority_goal_ids, vec![3, 7]);
+    assert_eq!(plan.session_length_minutes, 180);
+    assert!(parsed.repairs.iter().any(|r| r.contains("JSON syntax")));
+    assert!(parsed.repairs.iter().any(|r| r.contains("42")));
+
+    let overlapping = r#"{"blocks": [{"start": "09:00", "end": "10:00", "kind": "session", "title": "A"}, {"start": "09:30", "end": "11:00", "kind": "task", "title": "B"}], "priority_goal_ids": [], "session_length_minutes": 25}"#;
+    let errors = parse_daily_plan(overlapping, &goals).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("overlap")));
+    assert!(parse_daily_plan("I can't do that", &goals).is_err());
+}
+
+#[test]
+fn test_accepting_plan_items_writes_daily_db() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("personality.db")).unwrap();
+    let date = NaiveDate::from_ymd_opt(2025, 9, 12).unwrap();
+    let reply = r#"{"blocks": [
+        {"start": "09:00", "end": "10:00", "kind": "session", "title": "Thesis draft", "goal_id": 3},
+        {"start": "12:00", "end": "12:05", "kind": "reminder", "title": "Drink water"},
+        {"start": "13:00", "end": "13:30", "kind": "task", "title": "Inbox zero"},
+        {"start": "13:30", "end": "13:45", "kind": "break", "title": "Walk"}
+    ], "priority_goal_ids": [3], "session_length_minutes": 45}"#;
+    let plan = parse_daily_plan(reply, &[3].into_iter().collect()).unwrap().plan;
+    let plan_id = save_plan(&conn, 1, date, "chatgpt", &plan).unwrap();
+    let items = list_items(&conn, 1, plan_id).unwrap();
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0].session_minutes, Some(45));
+
+    let dir = tmp.path().to_string_lossy().to_string();
+    // Breaks are not turned into alarms
+    assert!(accept_item(&conn, 1, Some(&dir), items[3].id, None).is_err());
+    assert!(accept_item(&conn, 1, Some(&dir), items[3].id, Some("alarm")).is_err());
+    let accepted: Vec<_> = items[..3].iter().map(|i| accept_item(&conn, 1, Some(&dir), i.id, None).unwrap()).collect();
+    assert_eq!(accepted[0].accepted_as.as_deref(), Some("scheduled_session"));
+    assert_eq!(accepted[1].accepted_as.as_deref(), Some("reminder"));
+    assert_eq!(accepted[2].accepted_as.as_deref(), Some("alarm"));
+    // Accepting twice does not duplicate rows
+    accept_item(&conn, 1, Some(&dir), items[2].id, None).unwrap();
+
+    let daily = Connection::open(tmp.path().join("focusd_2025-09-12.sqlite3")).unwrap();
+    let (title, minutes, goal): (String, i64, i64) = daily.query_row("SELECT title, planned_minutes, goal_id FROM scheduled_session", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))).unwrap();
+    assert_eq!((title.as_str(), minutes, goal), ("Thesis draft", 45, 3));
+    let alarm_time: String = daily.query_row("SELECT alarm_time FROM alarm", [], |r| r.get(0)).unwrap();
+    assert_eq!(alarm_time, "2025-09-12T13:00:00");
+    let alarms: i64 = daily.query_row("SELECT COUNT(*) FROM alarm", [], |r| r.get(0)).unwrap();
+    assert_eq!(alarms, 1);
+    let reminder: String = daily.query_row("SELECT text FROM reminder", [], |r| r.get(0)).unwrap();
+    assert_eq!(reminder, "Drink water");
+    // Another user can neither see, accept nor dismiss these items
+    assert!(list_items(&conn, 2, plan_id).unwrap().is_empty());
+    assert_eq!(accept_item(&conn, 2, Some(&dir), items[3].id, Some("reminder")).unwrap_err(), "Plan item not found");
+    assert!(dismiss_item(&conn, 2, items[3].id).is_err());
+    dismiss_item(&conn, 1, items[3].id).unwrap();
+    assert_eq!(list_items(&conn, 1, plan_id).unwrap()[3].status, "dismissed");
+    let reminders: i64 = daily.query_row("SELECT COUNT(*) FROM reminder", [], |r| r.get(0)).unwrap();
+    assert_eq!(reminders, 1);
+}
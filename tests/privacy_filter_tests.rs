@@ -0,0 +1,50 @@
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::privacy::{PrivacyFilter, PrivacySettings, effective_privacy_settings};
+
+#[test]
+fn test_mask_uses_stable_placeholders_and_rehydrates() {
+    let settings = PrivacySettings {
+        names: vec!["Priya".to_string()],
+        custom_patterns: vec![r"PRJ-\d+".to_string()],
+        ..PrivacySettings::default()
+    };
+    let mut filter = PrivacyFilter::new(&settings).expect("filter");
+
+    let prompt = "- [2025-09-10 10:00] Event: \"Call with Priya\" (description: \"priya@example.com, +1 555 123 4567, PRJ-42\")\n- [11:30] Follow up with Priya about PRJ-42";
+    let masked = filter.mask(prompt);
+    assert!(!masked.contains("Priya"));
+    assert!(!masked.contains("priya@example.com"));
+    assert!(!masked.contains("555 123 4567"));
+    assert!(!masked.contains("PRJ-42"));
+    // Timestamps are context, not PII
+    assert!(masked.contains("2025-09-10 10:00"));
+    assert!(masked.contains("[11:30]"));
+    // Same value -> same placeholder
+    assert_eq!(masked.matches("[NAME_1]").count(), 2);
+    assert_eq!(masked.matches("[CUSTOM_1]").count(), 2);
+
+    let response = "You met [NAME_1] and should email [EMAIL_1] about [CUSTOM_1].";
+    assert_eq!(filter.rehydrate(response), "You met Priya and should email priya@example.com about PRJ-42.");
+}
+
+#[test]
+fn test_filter_cannot_be_disabled_without_data_sharing_consent() {
+    let tmp = tempdir().expect("tempdir");
+    let conn = Connection::open(tmp.path().join("personality.db")).expect("open db");
+    conn.execute("CREATE TABLE user (id INTEGER PRIMARY KEY, ai_opt_in BOOLEAN, data_sharing_opt_in BOOLEAN)", []).unwrap();
+    conn.execute("INSERT INTO user (id, ai_opt_in, data_sharing_opt_in) VALUES (1, 1, 0), (2, 1, 1)", []).unwrap();
+    let disabled = PrivacySettings { enabled: false, mask_emails: false, mask_phones: false, ..PrivacySettings::default() };
+    let json = serde_json::to_string(&disabled).unwrap();
+    conn.execute("CREATE TABLE privacy_settings (user_id INTEGER PRIMARY KEY, settings_json TEXT NOT NULL, updated_at TEXT)", []).unwrap();
+    conn.execute("INSERT INTO privacy_settings (user_id, settings_json) VALUES (1, ?1), (2, ?1)", [json]).unwrap();
+
+    let no_consent = effective_privacy_settings(&conn, 1).expect("settings");
+    assert!(no_consent.enabled && no_consent.mask_emails && no_consent.mask_phones);
+
+    let consented = effective_privacy_settings(&conn, 2).expect("settings");
+    assert!(!consented.enabled);
+    let mut filter = PrivacyFilter::new(&consented).unwrap();
+    assert_eq!(filter.mask("mail me at a@b.io"), "mail me at a@b.io");
+}
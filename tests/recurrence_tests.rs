@@ -0,0 +1,82 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::recurrence::{create, delete, get_owned, occurrences_between, set_exception, clear_exception, RRule};
+
+fn dt(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+}
+
+fn expand(rule: &str, start: &str, from: &str, to: &str) -> Vec<String> {
+    RRule::parse(rule).unwrap().between(dt(start), dt(from), dt(to))
+        .into_iter().map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string()).collect()
+}
+
+#[test]
+fn test_rrule_expansion_matches_rfc_examples() {
+    // Every other week on Tuesday and Thursday, 4 times
+    assert_eq!(
+        expand("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;COUNT=4", "2025-09-02T09:00:00", "2025-01-01T00:00:00", "2026-01-01T00:00:00"),
+        vec!["2025-09-02T09:00:00", "2025-09-04T09:00:00", "2025-09-16T09:00:00", "2025-09-18T09:00:00"]
+    );
+    // Last weekday of the month
+    assert_eq!(
+        expand("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1", "2025-09-01T17:00:00", "2025-09-01T00:00:00", "2025-12-31T23:59:59"),
+        vec!["2025-09-30T17:00:00", "2025-10-31T17:00:00", "2025-11-28T17:00:00", "2025-12-31T17:00:00"]
+    );
+    // Second Sunday of May, every year
+    assert_eq!(
+        expand("RRULE:FREQ=YEARLY;BYMONTH=5;BYDAY=2SU", "2025-01-01T08:00:00", "2025-01-01T00:00:00", "2027-12-31T00:00:00"),
+        vec!["2025-05-11T08:00:00", "2026-05-10T08:00:00", "2027-05-09T08:00:00"]
+    );
+    // Monthly on the 31st skips short months; UNTIL is inclusive of the whole day
+    assert_eq!(
+        expand("FREQ=MONTHLY;UNTIL=20260131", "2025-10-31T07:00:00", "2025-01-01T00:00:00", "2027-01-01T00:00:00"),
+        vec!["2025-10-31T07:00:00", "2025-12-31T07:00:00", "2026-01-31T07:00:00"]
+    );
+    // Rules that never match stop instead of looping forever
+    assert!(expand("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", "2025-01-01T00:00:00", "2025-01-01T00:00:00", "2100-01-01T00:00:00").is_empty());
+
+    assert!(RRule::parse("FREQ=HOURLY").is_err());
+    assert!(RRule::parse("FREQ=DAILY;COUNT=2;UNTIL=20250101").is_err());
+    assert!(RRule::parse("FREQ=WEEKLY;BYDAY=1MO").is_err());
+    let rule = RRule::parse("freq=monthly;interval=2;byday=-1fr").unwrap();
+    assert_eq!(rule.to_string(), "FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR");
+}
+
+#[test]
+fn test_occurrences_apply_skip_and_move_exceptions() {
+    let tmp = tempdir().unwrap();
+    let conn = Connection::open(tmp.path().join("personality.db")).unwrap();
+    let rec = create(&conn, 1, "alarm", "Stretch", None, "2025-09-01T10:00:00", Some(15), "FREQ=DAILY;COUNT=5").unwrap();
+    create(&conn, 2, "reminder", "Other user", None, "2025-09-01T09:00:00", None, "FREQ=DAILY").unwrap();
+    assert!(create(&conn, 1, "meeting", "Bad kind", None, "2025-09-01T10:00:00", None, "FREQ=DAILY").is_err());
+
+    set_exception(&conn, rec.id, "2025-09-02T10:00:00", None).unwrap();
+    // Moved out of the rule's own span, but still shown where it lands
+    set_exception(&conn, rec.id, "2025-09-03T10:00:00", Some("2025-09-08T16:30:00")).unwrap();
+    assert!(set_exception(&conn, rec.id, "2025-09-03T11:00:00", None).is_err());
+
+    let from = NaiveDate::from_ymd_opt(2025, 9, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let to = NaiveDate::from_ymd_opt(2025, 9, 30).unwrap().and_hms_opt(23, 59, 59).unwrap();
+    let occ = occurrences_between(&conn, Some(1), from, to).unwrap();
+    let starts: Vec<&str> = occ.iter().map(|o| o.start.as_str()).collect();
+    assert_eq!(starts, vec!["2025-09-01T10:00:00", "2025-09-04T10:00:00", "2025-09-05T10:00:00", "2025-09-08T16:30:00"]);
+    let moved = occ.last().unwrap();
+    assert!(moved.moved);
+    assert_eq!(moved.original_start, "2025-09-03T10:00:00");
+    assert_eq!(moved.end.as_deref(), Some("2025-09-08T16:45:00"));
+
+    clear_exception(&conn, rec.id, "2025-09-02T10:00:00").unwrap();
+    let occ = occurrences_between(&conn, Some(1), from, to).unwrap();
+    assert_eq!(occ.len(), 5);
+    assert!(occurrences_between(&conn, None, from, to).unwrap().len() > 5);
+
+    // Only the owner can change or delete a rule
+    assert!(get_owned(&conn, 2, rec.id).is_err());
+    assert!(delete(&conn, 2, rec.id).is_err());
+    delete(&conn, 1, rec.id).unwrap();
+    assert!(occurrences_between(&conn, Some(1), from, to).unwrap().is_empty());
+    assert_eq!(occurrences_between(&conn, Some(2), from, to).unwrap().len(), 30);
+}
@@ -0,0 +1,75 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::journals::{save_generated, set_annotations, JournalAnnotations};
+use focusd_lib::backend::retrospective::{default_anchor, generate_local, get_retro, list_retros, period_bounds};
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn seed_day(dir: &std::path::Path, date: &str, minutes: &[(&str, &str)], reasons: &[&str], done_goals: &[&str]) {
+    let conn = Connection::open(dir.join(format!("focusd_{}.sqlite3", date))).unwrap();
+    conn.execute_batch("CREATE TABLE session (id INTEGER PRIMARY KEY, start_time TEXT, end_time TEXT, notes TEXT);
+        CREATE TABLE distraction (id INTEGER PRIMARY KEY, reason TEXT);
+        CREATE TABLE goal (id INTEGER PRIMARY KEY, description TEXT, target_date TEXT, completed INTEGER DEFAULT 0);").unwrap();
+    for (start, end) in minutes {
+        conn.execute("INSERT INTO session (start_time, end_time) VALUES (?1, ?2)", [format!("{}T{}:00", date, start), format!("{}T{}:00", date, end)]).unwrap();
+    }
+    for r in reasons {
+        conn.execute("INSERT INTO distraction (reason) VALUES (?1)", [r]).unwrap();
+    }
+    for g in done_goals {
+        conn.execute("INSERT INTO goal (description, completed) VALUES (?1, 1)", [g]).unwrap();
+    }
+}
+
+#[test]
+fn test_period_bounds_and_default_anchor() {
+    assert_eq!(period_bounds("week", d("2025-09-10")).unwrap(), (d("2025-09-08"), d("2025-09-14")));
+    assert_eq!(period_bounds("month", d("2024-02-10")).unwrap(), (d("2024-02-01"), d("2024-02-29")));
+    assert_eq!(period_bounds("month", d("2025-12-31")).unwrap(), (d("2025-12-01"), d("2025-12-31")));
+    assert!(period_bounds("year", d("2025-09-10")).is_err());
+    assert_eq!(period_bounds("month", default_anchor("month", d("2025-10-03"))).unwrap().0, d("2025-09-01"));
+    assert_eq!(period_bounds("week", default_anchor("week", d("2025-09-17"))).unwrap().0, d("2025-09-08"));
+}
+
+#[test]
+fn test_local_weekly_retrospective_links_sources_and_aggregates() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().to_string_lossy().to_string();
+    seed_day(tmp.path(), "2025-09-02", &[("09:00", "10:00")], &[], &[]);
+    seed_day(tmp.path(), "2025-09-08", &[("09:00", "10:30")], &["Phone", "Slack"], &["Submit draft"]);
+    seed_day(tmp.path(), "2025-09-10", &[("14:00", "15:00")], &["phone"], &[]);
+
+    let conn = Connection::open(tmp.path().join("p.db")).unwrap();
+    let (first, _) = save_generated(&conn, 1, d("2025-09-08"), "local", None, "## Day 3\n\n## Journal Entry\nI finished the draft. Then I rested.\n\n## Feedback\nGood.", None).unwrap();
+    set_annotations(&conn, first, &JournalAnnotations { reflection: Some("Proud of this one.".into()), mood: Some(4), ..Default::default() }).unwrap();
+    let (second, _) = save_generated(&conn, 1, d("2025-09-10"), "local", None, "## Day 4\n\n## Journal Entry\nShort afternoon session.", None).unwrap();
+    save_generated(&conn, 1, d("2025-09-15"), "local", None, "Next week", None).unwrap();
+
+    let retro = generate_local(&conn, 1, "week", d("2025-09-12"), Some(&dir)).unwrap();
+    assert_eq!((retro.period_start.as_str(), retro.period_end.as_str()), ("2025-09-08", "2025-09-14"));
+    assert_eq!(retro.provider, "local");
+    assert_eq!(retro.source_entry_ids, vec![first, second]);
+    assert_eq!(retro.metrics.total_focus_minutes, 150);
+    assert_eq!(retro.metrics.previous_focus_minutes, 60);
+    assert_eq!(retro.metrics.focus_change_pct, Some(150.0));
+    assert_eq!(retro.metrics.top_distractions[0], ("Phone".to_string(), 2));
+    assert_eq!(retro.metrics.goals_completed, vec!["Submit draft"]);
+    assert_eq!(retro.metrics.avg_mood, Some(4.0));
+    assert!(retro.content.starts_with("## Week of September 8 – September 14, 2025"));
+    assert!(retro.content.contains("Mon 8 Sep: I finished the draft."));
+    assert!(retro.content.contains("(my note): Proud of this one."));
+    assert!(retro.content.contains("Track at least one session on more days (2 of 7 this week)"));
+
+    // Regenerating the same week replaces it rather than adding another row.
+    let again = generate_local(&conn, 1, "week", d("2025-09-08"), Some(&dir)).unwrap();
+    assert_eq!(again.id, retro.id);
+    assert_eq!(list_retros(&conn, 1, Some("week")).unwrap().len(), 1);
+    assert!(list_retros(&conn, 1, Some("month")).unwrap().is_empty());
+    // Only its owner can read it
+    assert_eq!(get_retro(&conn, 1, retro.id).unwrap().unwrap().source_entry_ids, vec![first, second]);
+    assert!(get_retro(&conn, 2, retro.id).unwrap().is_none());
+}
@@ -0,0 +1,93 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::search::{search_all, sanitize_query, ensure_journal_index, SearchQuery};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn journal_db(path: &std::path::Path) -> Connection {
+    let conn = Connection::open(path).unwrap();
+    conn.execute("CREATE TABLE journal_entries (id INTEGER PRIMARY KEY AUTOINCREMENT, user_id INTEGER NOT NULL, created_at TEXT NOT NULL, provider TEXT NOT NULL, model TEXT, content TEXT NOT NULL, tokens INTEGER)", []).unwrap();
+    conn
+}
+
+fn query(q: &str) -> SearchQuery {
+    SearchQuery { query: q.to_string(), ..SearchQuery::default() }
+}
+
+#[test]
+fn test_sanitize_query_quotes_terms_and_drops_operators() {
+    assert_eq!(sanitize_query("thesis OR \"draft*\" -(x)").as_deref(), Some("\"thesis\"* \"OR\"* \"draft\"* \"x\"*"));
+    assert_eq!(sanitize_query("  ^*() "), None);
+}
+
+#[test]
+fn test_search_spans_journals_and_daily_dbs_with_filters() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().to_string_lossy().to_string();
+    let p = journal_db(&tmp.path().join("personality.db"));
+    // Entries written before the index exists are backfilled.
+    p.execute("INSERT INTO journal_entries (user_id, created_at, provider, content) VALUES (1, '2025-09-09 21:00:00', 'local', 'Wrote the thesis introduction and felt calm.')", []).unwrap();
+    p.execute("INSERT INTO journal_entries (user_id, created_at, provider, content) VALUES (2, '2025-09-09 21:00:00', 'local', 'Another user thesis note.')", []).unwrap();
+    ensure_journal_index(&p).unwrap();
+    // Entries written afterwards are indexed by trigger.
+    p.execute("INSERT INTO journal_entries (user_id, created_at, provider, content) VALUES (1, '2025-09-10 21:00:00', 'local', 'Gym, then errands.')", []).unwrap();
+
+    for (date, notes, reason) in [("2025-09-09", "Thesis chapter 2 outline", "Phone"), ("2025-09-10", "Budget spreadsheet", "Thesis group chat")] {
+        let d = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        let path = ensure_daily_db(Some(&dir), d).unwrap();
+        let conn = Connection::open(path).unwrap();
+        conn.execute("INSERT INTO card (id, rfid, type, label) VALUES (1, 'A1', 'session', 'Study')", []).unwrap();
+        conn.execute("INSERT INTO session (card_id, start_time, notes) VALUES (1, ?1, ?2)", [format!("{}T09:00:00", date), notes.to_string()]).unwrap();
+        conn.execute("INSERT INTO distraction (reason) VALUES (?1)", [reason]).unwrap();
+        conn.execute("INSERT INTO goal (description, target_date) VALUES ('Finish gym routine', ?1)", [date]).unwrap();
+    }
+
+    let hits = search_all(&p, 1, Some(&dir), &query("thesis")).unwrap();
+    let mut kinds: Vec<(String, String)> = hits.iter().map(|h| (h.kind.clone(), h.date.clone())).collect();
+    kinds.sort();
+    assert_eq!(kinds, vec![
+        ("distraction".to_string(), "2025-09-10".to_string()),
+        ("journal".to_string(), "2025-09-09".to_string()),
+        ("session".to_string(), "2025-09-09".to_string()),
+    ]);
+    assert!(hits.iter().all(|h| h.snippet.to_lowercase().contains("[thesis]")));
+    assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+
+    let filtered = search_all(&p, 1, Some(&dir), &SearchQuery { start_date: Some("2025-09-10".into()), ..query("thesis") }).unwrap();
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].kind, "distraction");
+
+    let goals = search_all(&p, 1, Some(&dir), &SearchQuery { kinds: vec!["goal".into(), "journal".into()], ..query("gym") }).unwrap();
+    // Goals come from the workspace store and are dated by their deadline
+    let mut goal_days: Vec<&str> = goals.iter().filter(|h| h.kind == "goal").map(|h| h.date.as_str()).collect();
+    goal_days.sort();
+    assert_eq!(goal_days, vec!["2025-09-09", "2025-09-10"]);
+    assert_eq!(goals.iter().filter(|h| h.kind == "journal").count(), 1);
+}
+
+#[test]
+fn test_index_follows_updates_and_deletes() {
+    let tmp = tempdir().expect("tempdir");
+    let dir = tmp.path().to_string_lossy().to_string();
+    let p = journal_db(&tmp.path().join("personality.db"));
+    ensure_journal_index(&p).unwrap();
+    p.execute("INSERT INTO journal_entries (user_id, created_at, provider, content) VALUES (1, '2025-09-09', 'local', 'Morning run')", []).unwrap();
+    p.execute("UPDATE journal_entries SET content = 'Morning swim' WHERE id = 1", []).unwrap();
+    assert!(search_all(&p, 1, None, &SearchQuery { kinds: vec!["journal".into()], ..query("run") }).unwrap().is_empty());
+    assert_eq!(search_all(&p, 1, None, &SearchQuery { kinds: vec!["journal".into()], ..query("swim") }).unwrap().len(), 1);
+
+    let path = ensure_daily_db(Some(&dir), NaiveDate::from_ymd_opt(2025, 9, 9).unwrap()).unwrap();
+    let conn = Connection::open(path).unwrap();
+    conn.execute("INSERT INTO event (event_type, event_time, details_json) VALUES ('switch', '09:00', '{\"app\":\"Figma\"}')", []).unwrap();
+    assert_eq!(search_all(&p, 1, Some(&dir), &query("figma")).unwrap().len(), 1);
+    conn.execute("DELETE FROM event", []).unwrap();
+    assert!(search_all(&p, 1, Some(&dir), &query("figma")).unwrap().is_empty());
+
+    // A daily DB from before the index is migrated the first time it is searched
+    let old = Connection::open(tmp.path().join("focusd_2025-09-08.sqlite3")).unwrap();
+    old.execute_batch("CREATE TABLE distraction (id INTEGER PRIMARY KEY AUTOINCREMENT, session_id INTEGER, reason TEXT, created_at TEXT);
+        INSERT INTO distraction (reason, created_at) VALUES ('Figma plugin rabbit hole', '2025-09-08T10:00:00');").unwrap();
+    assert_eq!(search_all(&p, 1, Some(&dir), &query("figma")).unwrap().len(), 1);
+    assert_eq!(search_all(&p, 1, Some(&dir), &query("figma")).unwrap().len(), 1);
+}
@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::streaks::{compute_streaks, get_streak_calendar, get_streaks, StreakCriteria, StreakDay};
+use focusd_lib::backend::utility::ensure_daily_db;
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn history(start: &str, pattern: &str) -> Vec<StreakDay> {
+    pattern.chars().enumerate().map(|(i, c)| StreakDay {
+        day: (d(start) + chrono::Duration::days(i as i64)).format("%Y-%m-%d").to_string(),
+        focus_minutes: 0,
+        wake_time: None,
+        qualified: c == 'x',
+    }).collect()
+}
+
+#[test]
+fn test_rest_days_bridge_gaps_within_the_weekly_allowance() {
+    // Mon 2025-09-01 .. Sun 09-14; "." is a missed day
+    let days = history("2025-09-01", "xxxxxx..xxxxx.");
+    let strict = compute_streaks(&days, 0);
+    assert_eq!(strict.longest.as_ref().map(|s| (s.start.as_str(), s.end.as_str(), s.days)), Some(("2025-09-01", "2025-09-06", 6)));
+    // The last day is still in progress, so the current streak is alive
+    assert_eq!(strict.current.as_ref().map(|s| s.days), Some(5));
+    assert!(!strict.qualified_today);
+    assert_eq!((strict.total_qualified_days, strict.history_days), (11, 14));
+
+    // One rest day a week bridges Sun Sep 7 and Mon Sep 8, which fall in different ISO weeks
+    let relaxed = compute_streaks(&days, 1);
+    let longest = relaxed.longest.unwrap();
+    assert_eq!((longest.start.as_str(), longest.end.as_str(), longest.days, longest.rest_days), ("2025-09-01", "2025-09-13", 11, 2));
+    assert_eq!(relaxed.rest_days_left_this_week, 0);
+
+    // Two misses in the same week with one allowance break the streak
+    let broken = compute_streaks(&history("2025-09-08", "x..xx"), 1);
+    assert_eq!(broken.current.map(|s| (s.start, s.days)), Some(("2025-09-11".to_string(), 2)));
+    assert!(compute_streaks(&[], 1).current.is_none());
+}
+
+#[test]
+fn test_streaks_from_daily_dbs_by_focus_or_wake_tap() {
+    let tmp = tempdir().unwrap();
+    let ws = tmp.path().to_string_lossy().to_string();
+    let wd = Some(ws.clone());
+    let seed = |day: &str, sql: &str| {
+        let db = Connection::open(ensure_daily_db(Some(&ws), d(day)).unwrap()).unwrap();
+        db.execute_batch("INSERT INTO card (id, rfid, type, label) VALUES (1, 'DEEP', 'session', 'Deep work');
+            INSERT INTO card (id, rfid, type, label) VALUES (2, 'CORE', 'core', 'Core');").unwrap();
+        db.execute_batch(sql).unwrap();
+    };
+    seed("2025-09-01", "INSERT INTO session (card_id, start_time, end_time) VALUES (1, '09:00', '09:30');");
+    // 40 minutes, but 20 of them distracted
+    seed("2025-09-02", "INSERT INTO session (id, card_id, start_time, end_time) VALUES (1, 1, '09:00', '09:40');
+        INSERT INTO distraction (session_id, reason, start_time, end_time) VALUES (1, 'Phone', '09:10', '09:30');
+        INSERT INTO event (card_id, event_type, event_time) VALUES (2, 'wake', '2025-09-02T06:45:00');");
+    seed("2025-09-03", "INSERT INTO event (card_id, event_type, event_time) VALUES (2, 'wake', '2025-09-03T06:30:00');");
+    // No DB at all for Sep 4
+    seed("2025-09-05", "INSERT INTO session (card_id, start_time, end_time) VALUES (1, '10:00', '11:00');");
+
+    let focus = get_streaks(wd.clone(), Some("2025-09-05".to_string()), None, None).unwrap();
+    // Equal streaks: the most recent one is the longest
+    assert_eq!(focus.longest.map(|s| (s.start, s.days)), Some(("2025-09-05".to_string(), 1)));
+    assert_eq!(focus.current.map(|s| s.days), Some(1));
+    assert!(focus.qualified_today);
+
+    // A wake window lets Sep 2 and 3 count; one rest day bridges the missing Sep 4
+    let criteria = StreakCriteria { wake_window_start: Some("06:00".to_string()), wake_window_end: Some("07:00".to_string()), rest_days_per_week: 1, ..StreakCriteria::default() };
+    let report = get_streaks(wd.clone(), Some("2025-09-05".to_string()), Some(criteria.clone()), None).unwrap();
+    let current = report.current.unwrap();
+    assert_eq!((current.start.as_str(), current.end.as_str(), current.days, current.rest_days), ("2025-09-01", "2025-09-05", 4, 1));
+
+    // Requiring both rules leaves no day qualifying
+    let both = StreakCriteria { require_both: true, ..criteria.clone() };
+    assert!(get_streaks(wd.clone(), Some("2025-09-05".to_string()), Some(both), None).unwrap().longest.is_none());
+
+    let calendar = get_streak_calendar(wd.clone(), Some("2025-09-02".to_string()), Some("2025-09-04".to_string()), Some(criteria), None).unwrap();
+    let summary: Vec<(&str, i64, Option<&str>, bool)> = calendar.iter().map(|c| (c.day.as_str(), c.focus_minutes, c.wake_time.as_deref(), c.qualified)).collect();
+    assert_eq!(summary, vec![("2025-09-02", 20, Some("06:45"), true), ("2025-09-03", 0, Some("06:30"), true), ("2025-09-04", 0, None, false)]);
+
+    // Without criteria the focus threshold is the user's min_session_time setting
+    let db = Connection::open(ensure_daily_db(Some(&ws), d("2025-09-05")).unwrap()).unwrap();
+    db.execute_batch("CREATE TABLE user_setting (id INTEGER PRIMARY KEY AUTOINCREMENT, user_id INTEGER NOT NULL, key TEXT NOT NULL, value TEXT NOT NULL, version INTEGER NOT NULL, updated_at TEXT NOT NULL, UNIQUE(user_id, key));
+        INSERT INTO user_setting (user_id, key, value, version, updated_at) VALUES (1, 'min_session_time', '90', 1, '2025-09-05T08:00:00');").unwrap();
+    assert!(!get_streaks(wd.clone(), Some("2025-09-05".to_string()), None, Some(1)).unwrap().qualified_today);
+    assert!(get_streaks(wd.clone(), Some("2025-09-05".to_string()), None, Some(2)).unwrap().qualified_today);
+    assert!(get_streaks(wd, None, Some(StreakCriteria { rest_days_per_week: 7, ..StreakCriteria::default() }), None).is_err());
+}
@@ -0,0 +1,66 @@
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use tempfile::tempdir;
+
+use focusd_lib::backend::journals::{edit_entry, save_generated, set_annotations, JournalAnnotations};
+use focusd_lib::backend::vault_export::{export_vault, note_name, render_note, stored_hash};
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn test_render_note_hash_and_note_names() {
+    let note = render_note(&[("date", "2025-09-08".to_string())], "# Hi\n");
+    assert!(note.starts_with("---\ndate: 2025-09-08\nfocusd_hash: "));
+    assert_eq!(stored_hash(&note).unwrap().len(), 64);
+    assert_eq!(note_name("Ship v2: [beta] #launch"), "Ship v2 beta launch");
+}
+
+#[test]
+fn test_vault_sync_writes_links_and_respects_user_edits() {
+    let tmp = tempdir().expect("tempdir");
+    let ws = tmp.path().join("ws");
+    std::fs::create_dir_all(&ws).unwrap();
+    let vault = tmp.path().join("vault");
+    let daily = Connection::open(ws.join("focusd_2025-09-08.sqlite3")).unwrap();
+    daily.execute_batch("CREATE TABLE session (id INTEGER PRIMARY KEY, start_time TEXT, end_time TEXT, notes TEXT);
+        INSERT INTO session (start_time, end_time) VALUES ('2025-09-08T09:00:00', '2025-09-08T10:30:00');
+        CREATE TABLE goal (id INTEGER PRIMARY KEY, description TEXT, target_date TEXT, completed INTEGER DEFAULT 0);
+        INSERT INTO goal (description, target_date) VALUES ('Submit draft', '2025-09-12');").unwrap();
+
+    let conn = Connection::open(tmp.path().join("p.db")).unwrap();
+    let (id, _) = save_generated(&conn, 1, d("2025-09-08"), "local", None, "## Day 1\nDrafted.", None).unwrap();
+    set_annotations(&conn, id, &JournalAnnotations { mood: Some(4), tags: vec!["thesis".into()], ..Default::default() }).unwrap();
+    let (second, _) = save_generated(&conn, 1, d("2025-09-09"), "local", None, "## Day 2\nRested.", None).unwrap();
+    let ws_dir = ws.to_string_lossy().to_string();
+
+    let report = export_vault(&conn, 1, Some(&ws_dir), &vault, None).unwrap();
+    assert_eq!(report.written, vec!["Journal/2025-09-08.md", "Journal/2025-09-09.md", "Goals/Submit draft.md", "Weekly/2025-W37.md"]);
+    let note = std::fs::read_to_string(vault.join("Journal/2025-09-08.md")).unwrap();
+    assert!(note.contains("\nday: 1\nfocus_minutes: 90\nsessions: 1\nmood: 4\nenergy: \ntags: [thesis]\n"));
+    assert!(note.contains("- [[Goals/Submit draft|Submit draft]]"));
+    assert!(note.contains("Week: [[Weekly/2025-W37|2025-W37]]"));
+    assert!(std::fs::read_to_string(vault.join("Weekly/2025-W37.md")).unwrap().contains("[[Journal/2025-09-09|Tuesday]]"));
+
+    // Nothing changed: nothing rewritten.
+    let report = export_vault(&conn, 1, Some(&ws_dir), &vault, None).unwrap();
+    assert!(report.written.is_empty());
+    assert_eq!(report.unchanged.len(), 4);
+
+    // A user edit in the vault is preserved even when the entry changes in the app.
+    let path = vault.join("Journal/2025-09-08.md");
+    std::fs::write(&path, format!("{}\nMy own note.\n", note)).unwrap();
+    edit_entry(&conn, id, "## Day 1\nDrafted two chapters.").unwrap();
+    edit_entry(&conn, second, "## Day 2\nRested well.").unwrap();
+    let report = export_vault(&conn, 1, Some(&ws_dir), &vault, None).unwrap();
+    assert_eq!(report.skipped_user_edited, vec!["Journal/2025-09-08.md"]);
+    assert_eq!(report.written, vec!["Journal/2025-09-09.md"]);
+    assert!(std::fs::read_to_string(&path).unwrap().contains("My own note."));
+
+    // A partial sync keeps the weekly note built from the whole week
+    let report = export_vault(&conn, 1, Some(&ws_dir), &vault, Some(d("2025-09-09"))).unwrap();
+    assert!(report.written.is_empty());
+    assert_eq!(report.unchanged, vec!["Journal/2025-09-09.md", "Goals/Submit draft.md", "Weekly/2025-W37.md"]);
+    assert!(std::fs::read_to_string(vault.join("Weekly/2025-W37.md")).unwrap().contains("[[Journal/2025-09-08|Monday]] (90 min)"));
+}